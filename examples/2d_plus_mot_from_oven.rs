@@ -11,8 +11,8 @@ use lib::atom_sources::{VelocityCap, AtomSourcePlugin};
 use lib::destructor::ToBeDestroyed;
 use lib::integrator::Timestep;
 use lib::laser::LaserPlugin;
-use lib::laser::gaussian::GaussianBeam;
-use lib::laser_cooling::{CoolingLight, LaserCoolingPlugin};
+use lib::laser_cooling::mot::{MotBuilder, PushBeam};
+use lib::laser_cooling::LaserCoolingPlugin;
 use lib::magnetic::quadrupole::QuadrupoleField3D;
 use lib::output::file::{FileOutputPlugin};
 use lib::output::file::Text;
@@ -37,98 +37,27 @@ fn main() {
     sim_builder.add_plugin(FileOutputPlugin::<Velocity, Text, Atom>::new("vel.txt".to_string(), 10));
     let mut sim = sim_builder.build();
 
-    // Create magnetic field.
-    sim.world
-        .create_entity()
-        .with(QuadrupoleField3D::gauss_per_cm(65.0, Vector3::z()))
-        .with(Position::new())
-        .build();
-
-    // Push beam along z
-    let push_beam_radius = 1e-3;
-    let push_beam_power = 0.010;
-    let push_beam_detuning = 0.0;
-
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: Vector3::new(0.0, 0.0, 0.0),
-            e_radius: push_beam_radius,
-            power: push_beam_power,
-            direction: Vector3::z(),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Strontium88_461>(
-            push_beam_detuning,
-            -1,
-        ))
-        .build();
-
-    // Create cooling lasers.
+    // Create the magnetic field and the cooling beams of a 2D+ MOT, with a push beam along z.
     let detuning = -45.0;
     let power = 0.23;
     let radius = 33.0e-3 / (2.0 * 2.0_f64.sqrt()); // 33mm 1/e^2 diameter
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: Vector3::new(0.0, 0.0, 0.0),
-            e_radius: radius,
-            power,
-            direction: Vector3::new(1.0, 1.0, 0.0).normalize(),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Strontium88_461>(
-            detuning,
-            1,
-        ))
-        .build();
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: Vector3::new(0.0, 0.0, 0.0),
-            e_radius: radius,
-            power,
-            direction: Vector3::new(1.0, -1.0, 0.0).normalize(),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Strontium88_461>(
-            detuning,
-            1,
-        ))
-        .build();
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: Vector3::new(0.0, 0.0, 0.0),
-            e_radius: radius,
-            power,
-            direction: Vector3::new(-1.0, 1.0, 0.0).normalize(),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Strontium88_461>(
-            detuning,
-            1,
-        ))
-        .build();
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: Vector3::new(0.0, 0.0, 0.0),
-            e_radius: radius,
-            power,
-            direction: Vector3::new(-1.0, -1.0, 0.0).normalize(),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Strontium88_461>(
-            detuning,
-            1,
-        ))
-        .build();
+    let push_beam = PushBeam {
+        power: 0.010,
+        e_radius: 1e-3,
+        detuning: 0.0,
+    };
+    MotBuilder::new(
+        QuadrupoleField3D::gauss_per_cm(65.0, Vector3::z()),
+        detuning,
+        power,
+        radius,
+    )
+    .two_dimensional_plus::<Strontium88_461>(
+        Vector3::z(),
+        Vector3::new(1.0, 1.0, 0.0),
+        push_beam,
+    )
+    .build(&mut sim.world);
 
     // Create an oven.
     // The oven will eject atoms on the first frame and then be deleted.
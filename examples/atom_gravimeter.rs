@@ -0,0 +1,115 @@
+//! A Mach-Zehnder atom gravimeter.
+//!
+//! A cloud of Rb87 atoms falls under gravity through a vertical Raman beam, which applies a
+//! `π/2 - π - π/2` pulse sequence. The frequency of the beam is chirped to follow the Doppler shift
+//! of the falling atoms, and the interferometer phase is `(k_eff g - chirp) T²`. The example scans
+//! the chirp around `k_eff g`, printing the phase and contrast of the ensemble, and the value of `g`
+//! inferred from each measurement. The finite size of the beam reduces the contrast, because atoms
+//! away from the axis receive the wrong pulse areas.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Position, Velocity};
+use lib::constant::{BOLTZCONST, GC, PI};
+use lib::gravity::ApplyGravityOption;
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::interferometry::readout::{ensemble_phase_and_contrast, InterferometerOutput};
+use lib::interferometry::{InterferometerLight, InterferometryPlugin, LightPulseKind};
+use lib::laser::gaussian::GaussianBeam;
+use lib::simulation::SimulationBuilder;
+use lib::species::registry::SpeciesRegistry;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const INTERROGATION_TIME: f64 = 20.0e-3;
+const WAVELENGTH: f64 = 780.0e-9;
+
+/// Runs the gravimeter with the given chirp, returning the phase and contrast of the ensemble.
+fn run(chirp: f64) -> (f64, f64) {
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(InterferometryPlugin);
+    let mut sim = sim_builder.build();
+    sim.world.insert(Timestep { delta: 1.0e-5 });
+    sim.world.insert(ApplyGravityOption);
+
+    let beam = GaussianBeam {
+        intersection: Vector3::new(0.0, 0.0, 0.0),
+        direction: Vector3::new(0.0, 0.0, -1.0),
+        e_radius: 3.0e-3,
+        power: 0.1,
+        rayleigh_range: f64::INFINITY,
+        ellipticity: 0.0,
+    };
+    let peak = beam.power / (PI * beam.e_radius.powi(2));
+    let mut light = InterferometerLight::mach_zehnder(
+        LightPulseKind::Raman,
+        WAVELENGTH,
+        1.0e-3,
+        INTERROGATION_TIME,
+        10.0e-6,
+        peak,
+    );
+    light.chirp = chirp;
+    sim.world.create_entity().with(beam).with(light).build();
+
+    let registry = SpeciesRegistry::builtin();
+    let rb87 = registry.get("Rb87").unwrap();
+    let mass = rb87.mass();
+    let position = Normal::new(0.0, 0.5e-3).unwrap();
+    let speed = (BOLTZCONST * 0.5e-6 / (mass.value * lib::constant::AMU)).sqrt();
+    let velocity = Normal::new(0.0, speed).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..300 {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(mass.clone())
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    for _ in 0..4200 {
+        sim.step();
+    }
+
+    let outputs = sim.world.read_storage::<InterferometerOutput>();
+    ensemble_phase_and_contrast(outputs.join())
+}
+
+fn main() {
+    let now = Instant::now();
+    let k_eff = 4.0 * PI / WAVELENGTH;
+    let t2 = INTERROGATION_TIME.powi(2);
+    println!("offset (rad)\tphase (rad)\tcontrast\tg (m/s^2)");
+    for i in 0..5 {
+        let offset = (i as f64 - 2.0) * PI / 4.0;
+        let chirp = k_eff * GC - offset / t2;
+        let (phase, contrast) = run(chirp);
+        println!(
+            "{:.3}\t\t{:.3}\t\t{:.3}\t\t{:.8}",
+            offset,
+            phase,
+            contrast,
+            (chirp + phase / t2) / k_eff
+        );
+    }
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,117 @@
+//! Capture of Sr88 atoms in a broadband red MOT.
+//!
+//! The 689nm transition of Sr88 has a linewidth of only 7.4kHz, much smaller than the Doppler width of a
+//! cloud transferred from the blue MOT. The cooling beams are frequency modulated to cover a broad range of
+//! detunings, so that atoms at all velocities in the cloud are addressed. The number of atoms remaining
+//! in the MOT region is printed for single-frequency and broadband beams.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
+use lib::laser_cooling::frequency::{FrequencyModulation, LaserFrequencyNoise};
+use lib::laser_cooling::mot::MotBuilder;
+use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
+use lib::laser_cooling::LaserCoolingPlugin;
+use lib::magnetic::quadrupole::QuadrupoleField3D;
+use lib::simulation::SimulationBuilder;
+use lib::species::Strontium88_689;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 6;
+
+fn simulate(broadband: bool) -> usize {
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_689, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+
+    // The broadband beams are swept over 1MHz, red detuned from resonance.
+    let detuning = if broadband { -0.6 } else { -0.1 };
+    let beams = MotBuilder::new(
+        QuadrupoleField3D::gauss_per_cm(3.0, Vector3::z()),
+        detuning,
+        0.005,
+        5.0e-3,
+    )
+    .six_beam::<Strontium88_689>()
+    .build(&mut sim.world);
+    for beam in beams {
+        sim.world
+            .write_storage::<LaserFrequencyNoise>()
+            .insert(beam, LaserFrequencyNoise::new(1.0e3, 1.0e-6))
+            .expect("Could not insert LaserFrequencyNoise.");
+        if broadband {
+            sim.world
+                .write_storage::<FrequencyModulation>()
+                .insert(
+                    beam,
+                    FrequencyModulation::Sinusoidal {
+                        deviation: 0.5e6,
+                        frequency: 25.0e3,
+                    },
+                )
+                .expect("Could not insert FrequencyModulation.");
+        }
+    }
+
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+    sim.world
+        .insert(EmissionForceOption::On(EmissionForceConfiguration {
+            explicit_threshold: 5,
+        }));
+    sim.world.insert(ScatteringFluctuationsOption::On);
+
+    // A cloud of atoms at around 100uK, as transferred from a blue MOT.
+    let position = Normal::new(0.0, 3.0e-4).unwrap();
+    let velocity = Normal::new(0.0, 0.1).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..500 {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(Mass { value: 88.0 })
+            .with(Strontium88_689)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    for _ in 0..20000 {
+        sim.step();
+    }
+
+    let positions = sim.world.read_storage::<Position>();
+    let atoms = sim.world.read_storage::<Atom>();
+    (&positions, &atoms)
+        .join()
+        .filter(|(p, _)| p.pos.norm() < 2.0e-3)
+        .count()
+}
+
+fn main() {
+    let now = Instant::now();
+    println!("single frequency: {} atoms captured", simulate(false));
+    println!("broadband: {} atoms captured", simulate(true));
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
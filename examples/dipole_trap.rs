@@ -86,6 +86,8 @@ fn main() {
         .with(dipole::Polarizability::calculate_for(
             wavelength, 461e-9, 32.0e6,
         ))
+        // Off-resonant scattering of trap light heats the atom.
+        .with(dipole::scattering::DipoleScattering { raman_fraction: 0.0 })
         .with(atom::Atom)
         .with(lib::initiate::NewlyCreated)
         .build();
@@ -0,0 +1,178 @@
+//! Heating of atoms in a dipole trap by laser noise.
+//!
+//! A cloud of Rb87 atoms is held in a single-beam dipole trap. The trap beam has either relative intensity
+//! noise, which heats the atoms parametrically, or pointing jitter, which shakes the trap. The noise is
+//! correlated over a time comparable to the trap period, so that it is strong near the trap frequency
+//! and its second harmonic. The radial energy of the atoms is printed as the simulation runs, alongside the prediction of the
+//! Savard and Gehm heating-rate formulas.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::constant;
+use lib::dipole::{self, DipolePlugin};
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::gaussian::{calculate_rayleigh_range, GaussianBeam};
+use lib::laser::noise::{
+    intensity_noise_heating_rate, pointing_noise_heating_rate, IntensityNoise, NoiseProcess,
+    NoiseSpectrum, PointingJitter,
+};
+use lib::laser::{self, LaserPlugin};
+use lib::simulation::SimulationBuilder;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 1;
+
+const DT: f64 = 1.0e-6;
+const WAVELENGTH: f64 = 1064.0e-9;
+const POWER: f64 = 10.0;
+const E_RADIUS: f64 = 20.0e-6 / std::f64::consts::SQRT_2;
+const MASS: f64 = 87.0;
+const TEMPERATURE: f64 = 20.0e-6;
+
+fn polarizability() -> dipole::Polarizability {
+    dipole::Polarizability::calculate_for(WAVELENGTH, 780e-9, 6.07e6)
+}
+
+/// Radial trap frequency, in Hz, in the harmonic approximation.
+fn radial_trap_frequency() -> f64 {
+    let peak_intensity = POWER / (constant::PI * E_RADIUS.powi(2));
+    let spring_constant = 2.0 * polarizability().prefactor * peak_intensity / E_RADIUS.powi(2);
+    (spring_constant / (MASS * constant::AMU)).sqrt() / (2.0 * constant::PI)
+}
+
+/// Mean energy of motion along x, in the harmonic approximation, in units of kB.
+fn radial_energy(world: &World) -> f64 {
+    let omega = 2.0 * constant::PI * radial_trap_frequency();
+    let m = MASS * constant::AMU;
+    let positions = world.read_storage::<Position>();
+    let velocities = world.read_storage::<Velocity>();
+    let atoms = world.read_storage::<Atom>();
+    let energies: Vec<f64> = (&positions, &velocities, &atoms)
+        .join()
+        .map(|(p, v, _)| 0.5 * m * (v.vel[0].powi(2) + omega.powi(2) * p.pos[0].powi(2)))
+        .collect();
+    energies.iter().sum::<f64>() / energies.len() as f64 / constant::BOLTZCONST
+}
+
+fn simulate(intensity_noise: Option<IntensityNoise>, jitter: Option<PointingJitter>) -> World {
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(DipolePlugin::<{ BEAM_NUMBER }>);
+    let mut sim = sim_builder.build();
+    sim.world.insert(Timestep { delta: DT });
+
+    let mut beam = sim
+        .world
+        .create_entity()
+        .with(GaussianBeam {
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: E_RADIUS,
+            power: POWER,
+            direction: Vector3::z(),
+            rayleigh_range: calculate_rayleigh_range(&WAVELENGTH, &E_RADIUS),
+            ellipticity: 0.0,
+        })
+        .with(dipole::DipoleLight {
+            wavelength: WAVELENGTH,
+        })
+        .with(laser::frame::Frame {
+            x_vector: Vector3::x(),
+            y_vector: Vector3::y(),
+        });
+    if let Some(noise) = intensity_noise {
+        beam = beam.with(noise);
+    }
+    if let Some(jitter) = jitter {
+        beam = beam.with(jitter);
+    }
+    beam.build();
+
+    // A thermal cloud in the harmonic part of the trap.
+    let omega = 2.0 * constant::PI * radial_trap_frequency();
+    let thermal_velocity = (constant::BOLTZCONST * TEMPERATURE / (MASS * constant::AMU)).sqrt();
+    let velocity = Normal::new(0.0, thermal_velocity).unwrap();
+    let position = Normal::new(0.0, thermal_velocity / omega).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..200 {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(position.sample(&mut rng), position.sample(&mut rng), 0.0),
+            })
+            .with(Velocity {
+                vel: Vector3::new(velocity.sample(&mut rng), velocity.sample(&mut rng), 0.0),
+            })
+            .with(Force::new())
+            .with(Mass { value: MASS })
+            .with(polarizability())
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    let initial = radial_energy(&sim.world);
+    println!("time (ms)\tradial energy (uK)\tprediction (uK)");
+    for i in 0..=40000 {
+        if i % 10000 == 0 {
+            let t = i as f64 * DT;
+            println!(
+                "{:.1}\t{:.2}\t{:.2}",
+                t * 1e3,
+                radial_energy(&sim.world) * 1e6,
+                predict(&sim.world, initial, t) * 1e6
+            );
+        }
+        sim.step();
+    }
+    sim.world
+}
+
+/// Predicted radial energy, in units of kB, after a time `t`.
+fn predict(world: &World, initial: f64, t: f64) -> f64 {
+    let nu = radial_trap_frequency();
+    let beams = world.read_storage::<GaussianBeam>();
+    let intensity_noises = world.read_storage::<IntensityNoise>();
+    let jitters = world.read_storage::<PointingJitter>();
+    let mut energy = initial;
+    for (_, noise) in (&beams, &intensity_noises).join() {
+        energy *= (intensity_noise_heating_rate(nu, &noise.noise, DT) * t).exp();
+    }
+    for (_, jitter) in (&beams, &jitters).join() {
+        energy +=
+            pointing_noise_heating_rate(MASS * constant::AMU, nu, &jitter.displacement[0], DT) * t
+                / constant::BOLTZCONST;
+    }
+    energy
+}
+
+fn main() {
+    let now = Instant::now();
+    println!("radial trap frequency: {:.0} Hz", radial_trap_frequency());
+
+    let nu = radial_trap_frequency();
+    let spectrum = NoiseSpectrum::Correlated {
+        correlation_time: 1.0 / (2.0 * constant::PI * nu),
+    };
+
+    let noise = NoiseProcess::new(0.05, spectrum);
+    println!(
+        "\nrelative intensity noise, heating rate {:.1} s^-1",
+        intensity_noise_heating_rate(nu, &noise, DT)
+    );
+    simulate(Some(IntensityNoise::new(0.05, spectrum)), None);
+
+    let jitter = NoiseProcess::new(0.05e-6, spectrum);
+    println!(
+        "\npointing jitter, heating rate {:.2} uK/ms",
+        pointing_noise_heating_rate(MASS * constant::AMU, nu, &jitter, DT) / constant::BOLTZCONST
+            * 1e3
+    );
+    simulate(None, Some(PointingJitter::new(0.05e-6, 0.0, spectrum)));
+
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -14,10 +14,10 @@ use lib::atom::{Atom, Force, Mass, Position, Velocity};
 use lib::initiate::NewlyCreated;
 use lib::integrator::Timestep;
 use lib::laser::LaserPlugin;
-use lib::laser::gaussian::GaussianBeam;
 use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
 use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
-use lib::laser_cooling::{CoolingLight, LaserCoolingPlugin};
+use lib::laser_cooling::mot::MotBuilder;
+use lib::laser_cooling::LaserCoolingPlugin;
 use lib::magnetic::quadrupole::QuadrupoleField3D;
 use lib::output::file::{FileOutputPlugin};
 use lib::output::file::Text;
@@ -67,111 +67,18 @@ fn main() {
     sim_builder.add_plugin(FileOutputPlugin::<Velocity, Text, Atom>::new("vel.txt".to_string(), 10));
     let mut sim = sim_builder.build();
 
-    // Create magnetic field.
-    sim.world
-        .create_entity()
-        .with(QuadrupoleField3D::gauss_per_cm(0.001 * 18.2, Vector3::z()))
-        .with(Position {
-            pos: Vector3::new(0.0, 0.0, 0.0),
-        })
-        .build();
-
-    // Create cooling lasers.
+    // Create the magnetic field and a six-beam MOT.
     let detuning = configuration.detuning;
     let power = 0.02;
     let radius = 66.7e-3 / (2.0_f64.sqrt());
-    let beam_centre = Vector3::new(0.0, 0.0, 0.0);
-
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: beam_centre,
-            e_radius: radius,
-            power,
-            direction: Vector3::new(0.0, 0.0, 1.0),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Rubidium87_780D2>(
-            detuning,
-            -1,
-        ))
-        .build();
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: beam_centre,
-            e_radius: radius,
-            power,
-            direction: Vector3::new(0.0, 0.0, -1.0),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Rubidium87_780D2>(
-            detuning,
-            -1,
-        ))
-        .build();
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: beam_centre,
-            e_radius: radius,
-            power,
-            direction: Vector3::new(-1.0, 0.0, 0.0),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Rubidium87_780D2>(
-            detuning,
-            1,
-        ))
-        .build();
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: beam_centre,
-            e_radius: radius,
-            power,
-            direction: Vector3::new(1.0, 0.0, 0.0),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Rubidium87_780D2>(
-            detuning,
-            1,
-        ))
-        .build();
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: beam_centre,
-            e_radius: radius,
-            power,
-            direction: Vector3::new(0.0, 1.0, 0.0),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Rubidium87_780D2>(
-            detuning,
-            1,
-        ))
-        .build();
-    sim.world
-        .create_entity()
-        .with(GaussianBeam {
-            intersection: beam_centre,
-            e_radius: radius,
-            power,
-            direction: Vector3::new(0.0, -1.0, 0.0),
-            rayleigh_range: f64::INFINITY,
-            ellipticity: 0.0,
-        })
-        .with(CoolingLight::for_transition::<Rubidium87_780D2>(
-            detuning,
-            1,
-        ))
-        .build();
+    MotBuilder::new(
+        QuadrupoleField3D::gauss_per_cm(0.001 * 18.2, Vector3::z()),
+        detuning,
+        power,
+        radius,
+    )
+    .six_beam::<Rubidium87_780D2>()
+    .build(&mut sim.world);
 
     // Define timestep
     sim.world.insert(Timestep { delta: 1.0e-6 });
@@ -0,0 +1,136 @@
+//! A dual-colour MOT of Sr88 atoms.
+//!
+//! Each atom has both the broad 461nm transition and the narrow 689nm transition. A blue MOT and a red MOT
+//! share the same quadrupole field, and each set of cooling beams targets only the transition it is tuned to.
+//! The atoms are captured by the blue MOT, which is then switched off so that the broadband red MOT cools
+//! the cloud further. The temperature of the cloud is printed at the end of each stage.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::constant;
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
+use lib::laser_cooling::frequency::FrequencyModulation;
+use lib::laser_cooling::mot::MotBuilder;
+use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
+use lib::laser_cooling::{LaserCoolingPlugin, TargetTransitions};
+use lib::magnetic::quadrupole::QuadrupoleField3D;
+use lib::simulation::SimulationBuilder;
+use lib::species::{Strontium88_461, Strontium88_689};
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 12;
+
+/// Returns the temperature of the atoms in uK.
+fn temperature(world: &World, mass: f64) -> f64 {
+    let velocities = world.read_storage::<Velocity>();
+    let atoms = world.read_storage::<Atom>();
+    let speeds: Vec<f64> = (&velocities, &atoms)
+        .join()
+        .map(|(v, _)| v.vel.norm_squared())
+        .collect();
+    let mean_square = speeds.iter().sum::<f64>() / speeds.len() as f64;
+    mass * constant::AMU * mean_square / (3.0 * constant::BOLTZCONST) * 1e6
+}
+
+fn main() {
+    let now = Instant::now();
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_461, { BEAM_NUMBER }>::default());
+    sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_689, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+
+    let field = QuadrupoleField3D::gauss_per_cm(3.0, Vector3::z());
+    let blue_beams = MotBuilder::new(field, -40.0, 0.001, 5.0e-3)
+        .six_beam::<Strontium88_461>()
+        .build(&mut sim.world);
+    for beam in blue_beams.iter() {
+        sim.world
+            .write_storage::<TargetTransitions>()
+            .insert(*beam, TargetTransitions::of::<Strontium88_461>())
+            .expect("Could not insert TargetTransitions.");
+    }
+    let red_beams = MotBuilder::new(field, -0.6, 1.0e-3, 5.0e-3)
+        .six_beam::<Strontium88_689>()
+        .build(&mut sim.world);
+    for beam in red_beams {
+        sim.world
+            .write_storage::<TargetTransitions>()
+            .insert(beam, TargetTransitions::of::<Strontium88_689>())
+            .expect("Could not insert TargetTransitions.");
+        sim.world
+            .write_storage::<FrequencyModulation>()
+            .insert(
+                beam,
+                FrequencyModulation::Sinusoidal {
+                    deviation: 0.5e6,
+                    frequency: 25.0e3,
+                },
+            )
+            .expect("Could not insert FrequencyModulation.");
+    }
+
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+    sim.world
+        .insert(EmissionForceOption::On(EmissionForceConfiguration {
+            explicit_threshold: 5,
+        }));
+    sim.world.insert(ScatteringFluctuationsOption::On);
+
+    let mass = 88.0;
+    let position = Normal::new(0.0, 5.0e-4).unwrap();
+    let velocity = Normal::new(0.0, 0.3).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..200 {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(Mass { value: mass })
+            .with(Strontium88_461)
+            .with(Strontium88_689)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    for _ in 0..10000 {
+        sim.step();
+    }
+    println!("blue MOT: {:.1} uK", temperature(&sim.world, mass));
+
+    // Switch off the blue MOT beams, leaving the atoms in the red MOT.
+    for beam in blue_beams {
+        sim.world
+            .write_storage::<GaussianBeam>()
+            .get_mut(beam)
+            .expect("Blue MOT beam not found.")
+            .power = 0.0;
+    }
+    for _ in 0..40000 {
+        sim.step();
+    }
+    println!("red MOT: {:.1} uK", temperature(&sim.world, mass));
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,115 @@
+//! Deflection of beams of polar molecules and polarizable atoms by a charged electrode.
+//!
+//! Beams of CaF molecules and Rb87 atoms travel along z, passing a spherical electrode held at 10 kV. The
+//! molecules, which are oriented by the field, are pulled towards the electrode by their linear Stark shift.
+//! The quadratic Stark shift of the atoms is much weaker, so they pass almost undeflected. The example prints
+//! the mean transverse velocity of each beam after the electrode, and the Stark shift of the Rb D2 line at
+//! the closest approach.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Position, Velocity};
+use lib::constant::PI;
+use lib::electric::electrode::PointElectrode;
+use lib::electric::force::{ElectricDipole, StaticPolarizability};
+use lib::electric::ElectricFieldPlugin;
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::simulation::SimulationBuilder;
+use lib::species::registry::SpeciesRegistry;
+use lib::species::Rubidium87_780D2;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+fn main() {
+    let now = Instant::now();
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(ElectricFieldPlugin);
+    let mut sim = sim_builder.build();
+
+    let electrode = PointElectrode {
+        position: Vector3::new(-3.0e-3, 0.0, 0.0),
+        radius: 1.0e-3,
+        voltage: 10.0e3,
+    };
+    sim.world.create_entity().with(electrode.clone()).build();
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+
+    let registry = SpeciesRegistry::builtin();
+    let caf = registry.get("CaF").unwrap();
+    let rb87 = registry.get("Rb87").unwrap();
+
+    let position = Normal::new(0.0, 0.2e-3).unwrap();
+    let velocity = Normal::new(150.0, 10.0).unwrap();
+    let mut rng = rand::thread_rng();
+    let number = 100;
+    for i in 0..2 * number {
+        let mut builder = sim
+            .world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    -10.0e-3,
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(0.0, 0.0, velocity.sample(&mut rng)),
+            })
+            .with(Force::new())
+            .with(Atom)
+            .with(NewlyCreated);
+        builder = if i < number {
+            builder
+                .with(caf.mass())
+                .with(caf.electric_dipole().unwrap())
+        } else {
+            builder
+                .with(rb87.mass())
+                .with(rb87.static_polarizability().unwrap())
+        };
+        builder.build();
+    }
+
+    for _ in 0..150 {
+        sim.step();
+    }
+
+    let velocities = sim.world.read_storage::<Velocity>();
+    let dipoles = sim.world.read_storage::<ElectricDipole>();
+    let polarizabilities = sim.world.read_storage::<StaticPolarizability>();
+    let mean = |velocities: Vec<f64>| velocities.iter().sum::<f64>() / velocities.len() as f64;
+    println!(
+        "CaF: mean transverse velocity {:.3} m/s",
+        mean(
+            (&velocities, &dipoles)
+                .join()
+                .map(|(v, _)| v.vel.x)
+                .collect()
+        )
+    );
+    println!(
+        "Rb87: mean transverse velocity {:.3} m/s",
+        mean(
+            (&velocities, &polarizabilities)
+                .join()
+                .map(|(v, _)| v.vel.x)
+                .collect()
+        )
+    );
+
+    // The D2 line is shifted because the excited state is more polarizable than the ground state.
+    let field = electrode.field(&Vector3::new(0.0, 0.0, 0.0)).0.norm();
+    let ground = rb87.static_polarizability().unwrap();
+    let excited = rb87.excited_state_stark::<Rubidium87_780D2>("D2").unwrap();
+    let shift = (excited.energy(field) - ground.energy(field)) / lib::constant::HBAR / (2.0 * PI);
+    println!(
+        "Rb87 D2 Stark shift at {:.1} kV/cm: {:.1} kHz",
+        field / 1.0e5,
+        shift / 1.0e3
+    );
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,130 @@
+//! Simulate a grating MOT of Rb87 atoms.
+//!
+//! A single beam propagates down onto a tri-grating, which diffracts three beams back up towards
+//! the atoms. A cloud of atoms starts 2mm above the grating, and the position and size of the cloud
+//! are printed as it is captured.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::grating::Grating;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
+use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
+use lib::laser_cooling::{CoolingLight, LaserCoolingPlugin};
+use lib::magnetic::quadrupole::QuadrupoleField3D;
+use lib::simulation::SimulationBuilder;
+use lib::species::Rubidium87_780D2;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 4;
+
+fn main() {
+    let now = Instant::now();
+
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<Rubidium87_780D2, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+
+    // The quadrupole node lies 2mm above the grating.
+    let centre = Vector3::new(0.0, 0.0, 0.0);
+    sim.world
+        .create_entity()
+        .with(QuadrupoleField3D::gauss_per_cm(15.0, Vector3::z()))
+        .with(Position { pos: centre })
+        .build();
+
+    // The incident beam, which is diffracted by the grating into three further beams.
+    sim.world
+        .create_entity()
+        .with(GaussianBeam {
+            intersection: centre,
+            e_radius: 10.0e-3 / 2.0_f64.sqrt(),
+            power: 0.03,
+            direction: -Vector3::z(),
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        })
+        .with(CoolingLight::for_transition::<Rubidium87_780D2>(-12.0, -1))
+        .with(Grating::new(
+            Vector3::new(0.0, 0.0, -2.0e-3),
+            Vector3::z(),
+            1.08e-6,
+            3,
+            0.33,
+            10.0e-3,
+        ))
+        .build();
+
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+    sim.world
+        .insert(EmissionForceOption::On(EmissionForceConfiguration {
+            explicit_threshold: 5,
+        }));
+    sim.world.insert(ScatteringFluctuationsOption::On);
+
+    // A cloud of atoms at 1mK around the quadrupole node.
+    let position = Normal::new(0.0, 3.0e-4).unwrap();
+    let velocity = Normal::new(0.0, 0.3).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..500 {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: centre
+                    + Vector3::new(
+                        position.sample(&mut rng),
+                        position.sample(&mut rng),
+                        position.sample(&mut rng),
+                    ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(Mass { value: 87.0 })
+            .with(Rubidium87_780D2)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    println!("time (ms)\tmean position (mm)\trms radius (mm)\tatoms in region");
+    for i in 0..20000 {
+        sim.step();
+        if i % 2000 == 0 {
+            let positions = sim.world.read_storage::<Position>();
+            let atom_markers = sim.world.read_storage::<Atom>();
+            let atoms: Vec<Vector3<f64>> = (&positions, &atom_markers)
+                .join()
+                .map(|(p, _)| p.pos)
+                .filter(|p| (p - centre).norm() < 5.0e-3)
+                .collect();
+            let mean: Vector3<f64> = atoms.iter().sum::<Vector3<f64>>() / atoms.len() as f64;
+            let rms = (atoms.iter().map(|p| (p - mean).norm_squared()).sum::<f64>()
+                / atoms.len() as f64)
+                .sqrt();
+            println!(
+                "{:.0}\t({:.3}, {:.3}, {:.3})\t{:.3}\t{}",
+                i as f64 * 1e-3,
+                mean[0] * 1e3,
+                mean[1] * 1e3,
+                mean[2] * 1e3,
+                rms * 1e3,
+                atoms.len()
+            );
+        }
+    }
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,122 @@
+//! A blue MOT which selectively captures Sr86 from a cloud of strontium in its natural abundance.
+//!
+//! The isotopes of strontium are taken from the species registry. Every atom has the `Strontium88_461`
+//! transition, and atoms of the minority isotopes carry an `IsotopeShift` so that they see the MOT beams
+//! with their own detuning. The MOT is tuned just below the Sr86 resonance, where it is far detuned from
+//! the other isotopes, which are only weakly confined. The number of atoms of each isotope remaining in
+//! the centre of the MOT is printed at the end.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
+use lib::laser_cooling::isotope::IsotopeShift;
+use lib::laser_cooling::mot::MotBuilder;
+use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
+use lib::laser_cooling::LaserCoolingPlugin;
+use lib::magnetic::quadrupole::QuadrupoleField3D;
+use lib::simulation::SimulationBuilder;
+use lib::species::registry::SpeciesRegistry;
+use lib::species::Strontium88_461;
+use nalgebra::Vector3;
+use rand::distributions::WeightedIndex;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 6;
+
+fn main() {
+    let now = Instant::now();
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_461, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+
+    // Tune the MOT below the Sr86 resonance, which is 124.8MHz below that of Sr88.
+    MotBuilder::new(
+        QuadrupoleField3D::gauss_per_cm(30.0, Vector3::z()),
+        -124.8 - 16.0,
+        0.01,
+        5.0e-3,
+    )
+    .six_beam::<Strontium88_461>()
+    .build(&mut sim.world);
+
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+    sim.world
+        .insert(EmissionForceOption::On(EmissionForceConfiguration {
+            explicit_threshold: 5,
+        }));
+    sim.world.insert(ScatteringFluctuationsOption::On);
+
+    let registry = SpeciesRegistry::builtin();
+    let sr = registry.get("Sr88").unwrap();
+    let distribution = sr.mass_distribution();
+    let isotopes = sr.isotope_distribution();
+    let isotope_index =
+        WeightedIndex::new(distribution.distribution.iter().map(|mr| mr.ratio)).unwrap();
+
+    // Atoms start in a cloud at around 2mK.
+    let position = Normal::new(0.0, 1.0e-3).unwrap();
+    let velocity = Normal::new(0.0, 0.4).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..1000 {
+        let index = isotope_index.sample(&mut rng);
+        let isotope = &distribution.distribution[index];
+        let atom = sim
+            .world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(Mass {
+                value: isotope.mass,
+            })
+            .with(Strontium88_461)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+        if let Some(shift) = isotopes.isotopes[index]
+            .as_ref()
+            .and_then(|shifts| shifts.get("461"))
+        {
+            sim.world
+                .write_storage::<IsotopeShift<Strontium88_461>>()
+                .insert(atom, IsotopeShift::new(*shift))
+                .expect("Could not insert IsotopeShift.");
+        }
+    }
+
+    for _ in 0..20000 {
+        sim.step();
+    }
+
+    // Count the atoms of each isotope which remain near the centre of the MOT.
+    let positions = sim.world.read_storage::<Position>();
+    let masses = sim.world.read_storage::<Mass>();
+    for isotope in distribution.distribution.iter() {
+        let captured = (&positions, &masses)
+            .join()
+            .filter(|(_, mass)| mass.value == isotope.mass)
+            .filter(|(position, _)| position.pos.norm() < 0.5e-3)
+            .count();
+        println!("Sr{:.0}: {} atoms captured", isotope.mass, captured);
+    }
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
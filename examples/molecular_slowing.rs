@@ -0,0 +1,163 @@
+//! Radiation pressure slowing of a beam of CaF molecules.
+//!
+//! A counter-propagating slowing beam, swept in frequency to follow the Doppler shift, decelerates a beam of
+//! molecules travelling at 150 m/s. Stopping a CaF molecule takes over ten thousand photons, far more than it
+//! scatters before it decays into an excited vibrational level. The simulation is repeated with repump
+//! beams for none, one and two of the excited vibrational levels, and prints the number of molecules which
+//! remain in the cooling cycle and the number which are slowed below 30 m/s.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Position, Velocity};
+use lib::constant::C;
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
+use lib::laser_cooling::frequency::FrequencyModulation;
+use lib::laser_cooling::molecule::vibrational_state;
+use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
+use lib::laser_cooling::repump::DarkState;
+use lib::laser_cooling::{CoolingLight, LaserCoolingPlugin, TargetTransitions};
+use lib::simulation::SimulationBuilder;
+use lib::species::registry::SpeciesRegistry;
+use lib::species::CalciumFluoride59_606;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 3;
+
+/// Wavelengths of the X(v=1) -> A(v'=0) and X(v=2) -> A(v'=1) repump transitions, in m.
+const REPUMP_WAVELENGTHS: [f64; 2] = [628.6e-9, 628.1e-9];
+
+fn slowing_beam() -> GaussianBeam {
+    GaussianBeam {
+        intersection: Vector3::new(0.0, 0.0, 0.0),
+        e_radius: 5.0e-3,
+        power: 0.1,
+        direction: -Vector3::z(),
+        rayleigh_range: f64::INFINITY,
+        ellipticity: 0.0,
+    }
+}
+
+fn simulate(repumps: usize) {
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<CalciumFluoride59_606, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+
+    let caf = SpeciesRegistry::builtin().get("CaF").unwrap().clone();
+    let branching = caf.vibrational_branching().unwrap();
+    sim.world
+        .insert(branching.dark_state_branching::<CalciumFluoride59_606>());
+
+    // The slowing beam is swept over the Doppler shifts of molecules between 20 and 170 m/s.
+    sim.world
+        .create_entity()
+        .with(slowing_beam())
+        .with(CoolingLight::for_transition::<CalciumFluoride59_606>(
+            -155.0, 1,
+        ))
+        .with(FrequencyModulation::Sinusoidal {
+            deviation: 125.0e6,
+            frequency: 20.0e3,
+        })
+        .build();
+
+    // Repump beams overlap the slowing beam and are swept in the same way, but do not drive the cooling transition.
+    for level in 1..=repumps {
+        let repump =
+            branching.repump::<CalciumFluoride59_606>(level, C / REPUMP_WAVELENGTHS[level - 1]);
+        sim.world
+            .create_entity()
+            .with(slowing_beam())
+            .with(repump.cooling_light(-155.0, 1))
+            .with(FrequencyModulation::Sinusoidal {
+                deviation: 125.0e6,
+                frequency: 20.0e3,
+            })
+            .with(TargetTransitions::default())
+            .with(repump)
+            .build();
+    }
+
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+    sim.world
+        .insert(EmissionForceOption::On(EmissionForceConfiguration {
+            explicit_threshold: 5,
+        }));
+    sim.world.insert(ScatteringFluctuationsOption::On);
+
+    let position = Normal::new(0.0, 1.0e-3).unwrap();
+    let transverse = Normal::new(0.0, 1.0).unwrap();
+    let forward = Normal::new(150.0, 15.0).unwrap();
+    let mut rng = rand::thread_rng();
+    let number = 200;
+    for _ in 0..number {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(position.sample(&mut rng), position.sample(&mut rng), 0.0),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    transverse.sample(&mut rng),
+                    transverse.sample(&mut rng),
+                    forward.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(caf.mass())
+            .with(CalciumFluoride59_606)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    for _ in 0..15000 {
+        sim.step();
+    }
+
+    let velocities = sim.world.read_storage::<Velocity>();
+    let dark_states = sim.world.read_storage::<DarkState>();
+    let atoms = sim.world.read_storage::<Atom>();
+    let mut bright = 0;
+    let mut slowed = 0;
+    let mut lost = vec![0; branching.ratios.len()];
+    for (velocity, dark_state, _) in (&velocities, dark_states.maybe(), &atoms).join() {
+        match dark_state {
+            None => bright += 1,
+            Some(dark_state) => {
+                for (level, count) in lost.iter_mut().enumerate() {
+                    if dark_state.state == vibrational_state(level) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+        if velocity.vel.z < 30.0 {
+            slowed += 1;
+        }
+    }
+    println!(
+        "{} repumps (photon budget {:.0}): {}/{} molecules in the cycle, {} slowed below 30 m/s, molecules in v=1,2,3: {:?}",
+        repumps,
+        branching.photon_budget(repumps),
+        bright,
+        number,
+        slowed,
+        &lost[1..]
+    );
+}
+
+fn main() {
+    let now = Instant::now();
+    for repumps in 0..=2 {
+        simulate(repumps);
+    }
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,108 @@
+//! Cooling of Sr88 atoms on the narrow 689nm line.
+//!
+//! The recoil from a single 689nm photon shifts the atom's Doppler detuning by a large fraction of the
+//! 7.4kHz natural linewidth. A cloud of atoms is loaded into a red MOT, where the atoms sag under gravity
+//! to the bottom of the trap. The MOT is simulated using both the rate-equation treatment of photon
+//! scattering and the quantum-jump treatment, and the final temperatures are printed.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::constant;
+use lib::gravity::ApplyGravityOption;
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
+use lib::laser_cooling::mot::MotBuilder;
+use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
+use lib::laser_cooling::LaserCoolingPlugin;
+use lib::magnetic::quadrupole::QuadrupoleField3D;
+use lib::simulation::SimulationBuilder;
+use lib::species::Strontium88_689;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 6;
+
+/// Returns the temperature of the atoms in uK.
+fn simulate(option: ScatteringFluctuationsOption) -> f64 {
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_689, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+    sim.world.insert(ApplyGravityOption);
+
+    MotBuilder::new(
+        QuadrupoleField3D::gauss_per_cm(3.0, Vector3::z()),
+        -0.2,
+        1.0e-4,
+        5.0e-3,
+    )
+    .six_beam::<Strontium88_689>()
+    .build(&mut sim.world);
+
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+    sim.world
+        .insert(EmissionForceOption::On(EmissionForceConfiguration {
+            explicit_threshold: 5,
+        }));
+    sim.world.insert(option);
+
+    let mass = 88.0;
+    let position = Normal::new(0.0, 1.0e-4).unwrap();
+    let velocity = Normal::new(0.0, 0.01).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..200 {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(Mass { value: mass })
+            .with(Strontium88_689)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    for _ in 0..30000 {
+        sim.step();
+    }
+
+    let velocities = sim.world.read_storage::<Velocity>();
+    let atoms = sim.world.read_storage::<Atom>();
+    let speeds: Vec<f64> = (&velocities, &atoms)
+        .join()
+        .map(|(v, _)| v.vel.norm_squared())
+        .collect();
+    let mean_square = speeds.iter().sum::<f64>() / speeds.len() as f64;
+    mass * constant::AMU * mean_square / (3.0 * constant::BOLTZCONST) * 1e6
+}
+
+fn main() {
+    let now = Instant::now();
+    println!(
+        "rate equations: {:.2} uK",
+        simulate(ScatteringFluctuationsOption::On)
+    );
+    println!(
+        "quantum jumps: {:.2} uK",
+        simulate(ScatteringFluctuationsOption::QuantumJumps)
+    );
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
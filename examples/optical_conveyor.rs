@@ -0,0 +1,151 @@
+//! Transport of atoms in an optical conveyor belt.
+//!
+//! Two counter-propagating coherent beams form a 1D lattice along z. Shifting the phase of one
+//! beam translates the lattice, which carries the atoms with it. The phase is varied so that the
+//! lattice follows a minimum-jerk trajectory, moving the atoms by 100um.
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{self, Atom, Position, Velocity};
+use lib::constant::{AMU, BOLTZCONST, PI};
+use lib::dipole::{self, DipolePlugin};
+use lib::integrator::Timestep;
+use lib::laser::coherent::CoherentBeam;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::polarization::Polarization;
+use lib::laser::trajectory::BeamTrajectory;
+use lib::laser::{self, LaserPlugin};
+use lib::simulation::SimulationBuilder;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 2;
+
+fn main() {
+    let now = Instant::now();
+
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(DipolePlugin::<{ BEAM_NUMBER }>);
+    let mut sim = sim_builder.build();
+
+    let dt = 1.0e-7;
+    sim.world.insert(Timestep { delta: dt });
+
+    // Create the two lattice beams.
+    let power = 2.0;
+    let e_radius = 50.0e-6 / (2.0_f64.sqrt());
+    let wavelength = 1064.0e-9;
+    let mut beams = Vec::new();
+    for direction in [Vector3::z(), -Vector3::z()].iter() {
+        let beam = sim
+            .world
+            .create_entity()
+            .with(GaussianBeam {
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius,
+                power,
+                direction: *direction,
+                rayleigh_range: laser::gaussian::calculate_rayleigh_range(&wavelength, &e_radius),
+                ellipticity: 0.0,
+            })
+            .with(dipole::DipoleLight { wavelength })
+            .with(laser::frame::Frame {
+                x_vector: Vector3::x(),
+                y_vector: Vector3::y(),
+            })
+            .with(CoherentBeam {
+                group: 0,
+                phase: 0.0,
+                frequency_offset: 0.0,
+            })
+            .with(Polarization::linear(Vector3::x()))
+            .build();
+        beams.push(beam);
+    }
+
+    // The lattice moves by `d` when the phase of the backward beam changes by `2 k d`.
+    let distance = 100.0e-6;
+    let move_time = 1.0e-3;
+    let conveyor = BeamTrajectory::MinimumJerk {
+        start: Vector3::new(0.0, 0.0, 0.0),
+        end: Vector3::new(0.0, 0.0, distance),
+        start_time: 0.0,
+        duration: move_time,
+    };
+    let wavenumber = 2.0 * PI / wavelength;
+
+    // Load Rb87 atoms at 5uK into the lattice sites around the origin.
+    let mass = 87.0;
+    let velocity_width = (BOLTZCONST * 5.0e-6 / (mass * AMU)).sqrt();
+    let radial = Normal::new(0.0, 5.0e-6).unwrap();
+    let axial = Normal::new(0.0, 10.0e-9).unwrap();
+    let velocity = Normal::new(0.0, velocity_width).unwrap();
+    let mut rng = rand::thread_rng();
+    let number_of_atoms = 100;
+    for i in 0..number_of_atoms {
+        let site = (i as f64 - number_of_atoms as f64 / 2.0) * wavelength / 2.0;
+        sim.world
+            .create_entity()
+            .with(atom::Mass { value: mass })
+            .with(atom::Force::new())
+            .with(Position {
+                pos: Vector3::new(
+                    radial.sample(&mut rng),
+                    radial.sample(&mut rng),
+                    site + axial.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(dipole::Polarizability::calculate_for(
+                wavelength, 780e-9, 6.07e6,
+            ))
+            .with(Atom)
+            .with(lib::initiate::NewlyCreated)
+            .build();
+    }
+
+    let initial_positions: Vec<f64> = sim
+        .world
+        .read_storage::<Position>()
+        .join()
+        .map(|p| p.pos[2])
+        .collect();
+
+    let steps = (1.2 * move_time / dt) as u64;
+    for i in 0..steps {
+        {
+            let time = i as f64 * dt;
+            let mut coherent_beams = sim.world.write_storage::<CoherentBeam>();
+            coherent_beams.get_mut(beams[1]).unwrap().phase =
+                2.0 * wavenumber * conveyor.get_position(time).unwrap()[2];
+        }
+        sim.step();
+    }
+
+    let displacements: Vec<f64> = sim
+        .world
+        .read_storage::<Position>()
+        .join()
+        .zip(initial_positions.iter())
+        .map(|(p, z0)| p.pos[2] - z0)
+        .collect();
+    let transported = displacements
+        .iter()
+        .filter(|d| (*d - distance).abs() < wavelength)
+        .count();
+    println!(
+        "{} of {} atoms transported by {}um.",
+        transported,
+        number_of_atoms,
+        distance * 1e6
+    );
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,100 @@
+//! Atoms loaded into a one dimensional optical lattice, formed by two counter-propagating coherent beams.
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{self, Atom, Position};
+use lib::dipole::{self, DipolePlugin};
+use lib::integrator::Timestep;
+use lib::laser::coherent::CoherentBeam;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::polarization::Polarization;
+use lib::laser::{self, LaserPlugin};
+use lib::output::file::FileOutputPlugin;
+use lib::output::file::Text;
+use lib::simulation::SimulationBuilder;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 2;
+
+fn main() {
+    let now = Instant::now();
+
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(DipolePlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(FileOutputPlugin::<Position, Text, Atom>::new(
+        "pos.txt".to_string(),
+        100,
+    ));
+    let mut sim = sim_builder.build();
+
+    // Create the two lattice beams. They share a coherent group and polarization.
+    let power = 1.0;
+    let e_radius = 100.0e-6 / (2.0_f64.sqrt());
+    let wavelength = 1064.0e-9;
+    for direction in [Vector3::z(), -Vector3::z()].iter() {
+        sim.world
+            .create_entity()
+            .with(GaussianBeam {
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius,
+                power,
+                direction: *direction,
+                rayleigh_range: laser::gaussian::calculate_rayleigh_range(&wavelength, &e_radius),
+                ellipticity: 0.0,
+            })
+            .with(dipole::DipoleLight { wavelength })
+            .with(laser::frame::Frame {
+                x_vector: Vector3::x(),
+                y_vector: Vector3::y(),
+            })
+            .with(CoherentBeam {
+                group: 0,
+                phase: 0.0,
+                frequency_offset: 0.0,
+            })
+            .with(Polarization::linear(Vector3::x()))
+            .build();
+    }
+
+    sim.world.insert(Timestep { delta: 1.0e-7 });
+
+    // Create a cloud of cold atoms spread over many lattice sites.
+    let position_distribution = Normal::new(0.0, 5.0e-6).unwrap();
+    let velocity_distribution = Normal::new(0.0, 0.005).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        sim.world
+            .create_entity()
+            .with(atom::Mass { value: 87.0 })
+            .with(atom::Force::new())
+            .with(atom::Position {
+                pos: Vector3::new(
+                    position_distribution.sample(&mut rng),
+                    position_distribution.sample(&mut rng),
+                    position_distribution.sample(&mut rng),
+                ),
+            })
+            .with(atom::Velocity {
+                vel: Vector3::new(
+                    velocity_distribution.sample(&mut rng),
+                    velocity_distribution.sample(&mut rng),
+                    velocity_distribution.sample(&mut rng),
+                ),
+            })
+            .with(dipole::Polarizability::calculate_for(
+                wavelength, 780e-9, 6.07e6,
+            ))
+            .with(Atom)
+            .with(lib::initiate::NewlyCreated)
+            .build();
+    }
+
+    for _i in 0..20_000 {
+        sim.step();
+    }
+
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,117 @@
+//! Simulate a 1D MOT formed by a single retro-reflected beam.
+//!
+//! The beam passes through a viewport, reflects from a mirror and quarter-wave plate, and returns
+//! through the viewport to form the counter-propagating beam. The return pass is weaker because of
+//! the losses of the optics, which pushes the captured atoms away from the quadrupole node. Halfway
+//! through, the power of the source beam is reduced, and the return pass follows automatically.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::optics::BeamPath;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::{CoolingLight, LaserCoolingPlugin};
+use lib::magnetic::quadrupole::QuadrupoleField3D;
+use lib::simulation::{Simulation, SimulationBuilder};
+use lib::species::Strontium88_461;
+use nalgebra::Vector3;
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 6;
+
+/// Prints the power of the source and return beams, and the position of the captured atoms.
+fn report(sim: &Simulation, source: Entity) {
+    let beams = sim.world.read_storage::<GaussianBeam>();
+    let paths = sim.world.read_storage::<BeamPath>();
+    let returned = paths.get(source).unwrap().derived_beams()[0];
+    let positions = sim.world.read_storage::<Position>();
+    let velocities = sim.world.read_storage::<Velocity>();
+    let atoms = sim.world.read_storage::<Atom>();
+    let captured: Vec<f64> = (&positions, &velocities, &atoms)
+        .join()
+        .filter(|(position, velocity, _)| {
+            position.pos.z.abs() < 5.0e-3 && velocity.vel.norm() < 1.0
+        })
+        .map(|(position, _, _)| position.pos.z)
+        .collect();
+    let mean = captured.iter().sum::<f64>() / captured.len().max(1) as f64;
+    println!(
+        "source power {:.1} mW, return power {:.1} mW: {} atoms captured at z = {:.3} mm",
+        beams.get(source).unwrap().power * 1.0e3,
+        beams.get(returned).unwrap().power * 1.0e3,
+        captured.len(),
+        mean * 1.0e3
+    );
+}
+
+fn main() {
+    let now = Instant::now();
+
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_461, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+
+    // Create magnetic field.
+    sim.world
+        .create_entity()
+        .with(QuadrupoleField3D::gauss_per_cm(15.0, Vector3::z()))
+        .with(Position::new())
+        .build();
+
+    // Create the cooling laser, which is retro-reflected by a mirror 30cm from the atoms.
+    let source = sim
+        .world
+        .create_entity()
+        .with(GaussianBeam {
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 0.01,
+            power: 0.03,
+            direction: -Vector3::z(),
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        })
+        .with(CoolingLight::for_transition::<Strontium88_461>(-12.0, -1))
+        .with(BeamPath::retro_reflected(0.3, 0.99, 0.96))
+        .build();
+
+    // Create atoms
+    for i in 0..20 {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(0.0, 0.0, -0.05),
+            })
+            .with(Atom)
+            .with(Force::new())
+            .with(Velocity {
+                vel: Vector3::new(0.0, 0.0, 10.0 + (i as f64) * 5.0),
+            })
+            .with(NewlyCreated)
+            .with(Strontium88_461)
+            .with(Mass { value: 88.0 })
+            .build();
+    }
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+
+    for _ in 0..5000 {
+        sim.step();
+    }
+    report(&sim, source);
+
+    sim.world
+        .write_storage::<GaussianBeam>()
+        .get_mut(source)
+        .unwrap()
+        .power = 0.01;
+    for _ in 0..5000 {
+        sim.step();
+    }
+    report(&sim, source);
+
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,130 @@
+//! Rydberg blockade in a cloud of Rb87 atoms.
+//!
+//! A frozen cloud of atoms is excited to the 70S Rydberg state by two-photon excitation, through the 5P3/2
+//! state with a detuning of 500 MHz. The van der Waals interaction between Rydberg atoms shifts the Rydberg
+//! state of their neighbours out of resonance, so only one atom can be excited within the blockade radius.
+//! The simulation is repeated without interactions, and prints the number of Rydberg atoms at the end.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::constant::{HBAR, PI};
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::LaserPlugin;
+use lib::rydberg::{
+    ExcitationStep, Rydberg, RydbergExcitation, RydbergExcited, RydbergLight, RydbergPlugin,
+};
+use lib::simulation::SimulationBuilder;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 2;
+
+fn simulate(rydberg: Rydberg) {
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(RydbergPlugin::<{ BEAM_NUMBER }>);
+    let mut sim = sim_builder.build();
+
+    // The 780nm beam drives the Rb D2 line, with a Rabi frequency of 2pi 50MHz.
+    sim.world
+        .create_entity()
+        .with(GaussianBeam {
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 1.0e-3,
+            power: 7.1e-3,
+            direction: Vector3::x(),
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        })
+        .with(RydbergLight::from_transition(
+            ExcitationStep::Lower,
+            6.065e6,
+            16.69,
+        ))
+        .build();
+
+    // The 480nm beam drives the 5P3/2 -> 70S transition, with a Rabi frequency of 2pi 20MHz.
+    let bohr_radius = 5.29e-11;
+    let electron_charge = 1.602e-19;
+    sim.world
+        .create_entity()
+        .with(GaussianBeam {
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 100.0e-6,
+            power: 1.0,
+            direction: -Vector3::x(),
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        })
+        .with(RydbergLight::from_dipole_moment(
+            ExcitationStep::Upper,
+            0.01 * electron_charge * bohr_radius,
+        ))
+        .build();
+
+    let excitation = RydbergExcitation {
+        intermediate_detuning: 2.0 * PI * 500.0e6,
+        detuning: 0.0,
+        dephasing: 2.0 * PI * 1.0e6,
+    };
+    sim.world.insert(excitation);
+    sim.world.insert(Timestep { delta: 1.0e-8 });
+
+    let position = Normal::new(0.0, 10.0e-6).unwrap();
+    let mut rng = rand::thread_rng();
+    let number = 200;
+    for _ in 0..number {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(0.0, 0.0, 0.0),
+            })
+            .with(Force::new())
+            .with(Mass { value: 87.0 })
+            .with(rydberg)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    for _ in 0..500 {
+        sim.step();
+    }
+
+    let excited = sim.world.read_storage::<RydbergExcited>();
+    let atoms = sim.world.read_storage::<Atom>();
+    println!(
+        "C6 = {:.1e} J m^6 (blockade radius {:.1} um): {}/{} atoms in the Rydberg state",
+        rydberg.c6,
+        rydberg.blockade_radius(excitation.dephasing) * 1.0e6,
+        (&atoms, &excited).join().count(),
+        number
+    );
+}
+
+fn main() {
+    let now = Instant::now();
+    let rydberg = Rydberg {
+        c6: 5.7e-58,
+        lifetime: 150.0e-6,
+    };
+    println!(
+        "Interaction at 10um: 2pi {:.2} MHz",
+        rydberg.interaction(10.0e-6) / HBAR / (2.0 * PI * 1.0e6)
+    );
+    simulate(rydberg);
+    simulate(Rydberg { c6: 0.0, ..rydberg });
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
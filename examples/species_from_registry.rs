@@ -0,0 +1,154 @@
+//! Simulate a MOT of a species chosen at runtime from the species registry.
+//!
+//! The species and cooling transition are read from a configuration file called `species.json`, for example
+//! `{ "species": "Cs133", "transition": "D2", "detuning": -0.5, "number_of_steps": 10000 }`. If the file is
+//! not written, K39 atoms are cooled on the D2 line. The detuning is given in units of the linewidth, so the
+//! same configuration can be used for every species. The final temperature is printed alongside the Doppler
+//! limit of the transition.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Position, Velocity};
+use lib::constant;
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
+use lib::laser_cooling::mot::MotBuilder;
+use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
+use lib::laser_cooling::transition::AtomicTransition;
+use lib::laser_cooling::LaserCoolingPlugin;
+use lib::magnetic::quadrupole::QuadrupoleField3D;
+use lib::simulation::SimulationBuilder;
+use lib::species::registry::SpeciesRegistry;
+use lib::species::DynamicTransition;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::fs::read_to_string;
+use std::time::Instant;
+
+extern crate serde;
+use serde::Deserialize;
+
+const BEAM_NUMBER: usize = 6;
+
+#[derive(Deserialize)]
+pub struct SpeciesSimulationConfiguration {
+    /// Name of the species in the registry.
+    pub species: String,
+    /// Name of the cooling transition. The default cooling transition of the species is used if not given.
+    pub transition: Option<String>,
+    /// Detuning of laser beams, in units of the linewidth.
+    pub detuning: f64,
+    /// Number of simulation steps to evolve for.
+    pub number_of_steps: i32,
+}
+impl Default for SpeciesSimulationConfiguration {
+    fn default() -> Self {
+        SpeciesSimulationConfiguration {
+            species: "K39".to_string(),
+            transition: None,
+            detuning: -0.5,
+            number_of_steps: 10000,
+        }
+    }
+}
+
+fn main() {
+    let now = Instant::now();
+
+    // Load configuration if one exists.
+    let configuration: SpeciesSimulationConfiguration = match read_to_string("species.json") {
+        Ok(json_str) => serde_json::from_str(&json_str).unwrap(),
+        Err(_) => SpeciesSimulationConfiguration::default(),
+    };
+
+    // Set the cooling transition from the registry.
+    let registry = SpeciesRegistry::builtin();
+    let species = registry
+        .get(&configuration.species)
+        .expect("Species not found in registry.");
+    let transition = match &configuration.transition {
+        Some(name) => species.transition(name),
+        None => species.cooling_transition(),
+    }
+    .expect("Transition not found in registry.");
+    DynamicTransition::set(*transition).unwrap();
+
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<DynamicTransition, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+
+    let detuning = configuration.detuning * DynamicTransition::linewidth() / 1.0e6;
+    let power = 0.02;
+    let radius = 5.0e-3;
+    MotBuilder::new(
+        QuadrupoleField3D::gauss_per_cm(10.0, Vector3::z()),
+        detuning,
+        power,
+        radius,
+    )
+    .six_beam::<DynamicTransition>()
+    .build(&mut sim.world);
+
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+    sim.world
+        .insert(EmissionForceOption::On(EmissionForceConfiguration {
+            explicit_threshold: 5,
+        }));
+    sim.world.insert(ScatteringFluctuationsOption::On);
+
+    // Atoms start at around 1mK.
+    let mass = species.mass();
+    let thermal_velocity = (constant::BOLTZCONST * 1.0e-3 / (mass.value * constant::AMU)).sqrt();
+    let position = Normal::new(0.0, 5.0e-4).unwrap();
+    let velocity = Normal::new(0.0, thermal_velocity).unwrap();
+    let mut rng = rand::thread_rng();
+    for _ in 0..200 {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(mass.clone())
+            .with(DynamicTransition)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    for _ in 0..configuration.number_of_steps {
+        sim.step();
+    }
+
+    let velocities = sim.world.read_storage::<Velocity>();
+    let atoms = sim.world.read_storage::<Atom>();
+    let speeds: Vec<f64> = (&velocities, &atoms)
+        .join()
+        .map(|(v, _)| v.vel.norm_squared())
+        .collect();
+    let mean_square = speeds.iter().sum::<f64>() / speeds.len() as f64;
+    let temperature = mass.value * constant::AMU * mean_square / (3.0 * constant::BOLTZCONST);
+    let doppler_limit = constant::HBAR * DynamicTransition::gamma() / (2.0 * constant::BOLTZCONST);
+    println!(
+        "{}: temperature {:.1} uK, Doppler limit {:.1} uK",
+        configuration.species,
+        temperature * 1e6,
+        doppler_limit * 1e6
+    );
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,141 @@
+//! A blue MOT of Sr88 atoms which leaks into the metastable 3P2 and 3P0 states.
+//!
+//! Atoms in the 1P1 state decay through the 1D2 state into the 3P2 state with a small probability, which
+//! removes them from the cooling cycle. The 707nm repump excites atoms from 3P2 to the 3S1 state, which can
+//! decay into 3P0, so a second 679nm repump is needed to return all atoms to the cycle. The simulation is
+//! repeated without repumps, with the 707nm repump only, and with both repumps, and prints the number of
+//! atoms in each state at the end.
+
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{Atom, Force, Mass, Position, Velocity};
+use lib::initiate::NewlyCreated;
+use lib::integrator::Timestep;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::LaserPlugin;
+use lib::laser_cooling::force::{EmissionForceConfiguration, EmissionForceOption};
+use lib::laser_cooling::mot::MotBuilder;
+use lib::laser_cooling::photons_scattered::ScatteringFluctuationsOption;
+use lib::laser_cooling::repump::{DarkState, DarkStateBranching, RepumpLight};
+use lib::laser_cooling::{LaserCoolingPlugin, TargetTransitions};
+use lib::magnetic::quadrupole::QuadrupoleField3D;
+use lib::simulation::SimulationBuilder;
+use lib::species::Strontium88_461;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 8;
+
+/// Creates a repump of a 3PJ state, through the 3S1 state which decays into 3P0, 3P1 and 3P2.
+fn repump(state: &str, frequency: f64, saturation_intensity: f64) -> RepumpLight {
+    RepumpLight::new(state, frequency, 11.4e6, saturation_intensity)
+        .with_decay("3P0", 0.11)
+        .with_decay("3P2", 0.55)
+}
+
+fn simulate(repumps: &[RepumpLight]) {
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_461, { BEAM_NUMBER }>::default());
+    let mut sim = sim_builder.build();
+
+    MotBuilder::new(
+        QuadrupoleField3D::gauss_per_cm(30.0, Vector3::z()),
+        -40.0,
+        0.01,
+        5.0e-3,
+    )
+    .six_beam::<Strontium88_461>()
+    .build(&mut sim.world);
+
+    for (i, repump) in repumps.iter().enumerate() {
+        let direction = if i == 0 { Vector3::x() } else { Vector3::y() };
+        sim.world
+            .create_entity()
+            .with(GaussianBeam {
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius: 3.0e-3,
+                power: 1.0e-3,
+                direction,
+                rayleigh_range: f64::INFINITY,
+                ellipticity: 0.0,
+            })
+            .with(repump.cooling_light(0.0, 1))
+            .with(repump.clone())
+            .with(TargetTransitions::default())
+            .build();
+    }
+
+    // Decay from 1P1 into 1D2 happens once in 50000 photons, and a third of those atoms decay into 3P2.
+    // Atoms which decay into 3P1 return to the cycle within 21us, so are neglected.
+    sim.world
+        .insert(DarkStateBranching::<Strontium88_461>::default().with_state("3P2", 7.0e-6));
+    sim.world.insert(Timestep { delta: 1.0e-6 });
+    sim.world
+        .insert(EmissionForceOption::On(EmissionForceConfiguration {
+            explicit_threshold: 5,
+        }));
+    sim.world.insert(ScatteringFluctuationsOption::On);
+
+    let position = Normal::new(0.0, 5.0e-4).unwrap();
+    let velocity = Normal::new(0.0, 0.3).unwrap();
+    let mut rng = rand::thread_rng();
+    let number = 200;
+    for _ in 0..number {
+        sim.world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                    position.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(Force::new())
+            .with(Mass { value: 88.0 })
+            .with(Strontium88_461)
+            .with(Atom)
+            .with(NewlyCreated)
+            .build();
+    }
+
+    for _ in 0..30000 {
+        sim.step();
+    }
+
+    let atoms = sim.world.read_storage::<Atom>();
+    let dark_states = sim.world.read_storage::<DarkState>();
+    let count = |state: &str| {
+        (&atoms, &dark_states)
+            .join()
+            .filter(|(_, dark_state)| dark_state.state == state)
+            .count()
+    };
+    let names: Vec<&str> = repumps.iter().map(|r| r.state.as_str()).collect();
+    println!(
+        "repumps {:?}: {} atoms in 3P2, {} in 3P0, {} in the cycle",
+        names,
+        count("3P2"),
+        count("3P0"),
+        number - count("3P2") - count("3P0")
+    );
+}
+
+fn main() {
+    let now = Instant::now();
+    let repump_707 = repump("3P2", 423.91e12, 42.0);
+    let repump_679 = repump("3P0", 441.33e12, 48.0);
+    simulate(&[]);
+    simulate(std::slice::from_ref(&repump_707));
+    simulate(&[repump_707, repump_679]);
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,126 @@
+//! Rearrangement of atoms in a tweezer array.
+//!
+//! A row of five static tweezers is partially loaded, with atoms in sites 0, 2 and 4. A deeper,
+//! mobile tweezer is switched on at site 4, carries the atom to the empty site 1, and is then
+//! switched off so that the atom is handed over to the static tweezer. The occupancy of each
+//! site is printed at the end of the sequence.
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{self, Atom, Position, Velocity};
+use lib::dipole::{self, DipolePlugin};
+use lib::integrator::Timestep;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::trajectory::{BeamTrajectory, PowerModulation};
+use lib::laser::{self, LaserPlugin};
+use lib::simulation::SimulationBuilder;
+use nalgebra::Vector3;
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 6;
+
+fn main() {
+    let now = Instant::now();
+
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(DipolePlugin::<{ BEAM_NUMBER }>);
+    let mut sim = sim_builder.build();
+
+    let dt = 1.0e-7;
+    sim.world.insert(Timestep { delta: dt });
+
+    let wavelength = 1064.0e-9;
+    let e_radius = 1.0e-6 / 2.0_f64.sqrt();
+    let rayleigh_range = laser::gaussian::calculate_rayleigh_range(&wavelength, &e_radius);
+    let spacing = 5.0e-6;
+    let site = |i: usize| Vector3::new(i as f64 * spacing, 0.0, 0.0);
+
+    // The static tweezer array.
+    for i in 0..5 {
+        sim.world
+            .create_entity()
+            .with(GaussianBeam {
+                intersection: site(i),
+                e_radius,
+                power: 0.005,
+                direction: Vector3::z(),
+                rayleigh_range,
+                ellipticity: 0.0,
+            })
+            .with(dipole::DipoleLight { wavelength })
+            .with(laser::frame::Frame {
+                x_vector: Vector3::x(),
+                y_vector: Vector3::y(),
+            })
+            .build();
+    }
+
+    // The mobile tweezer, which ramps up at site 4, moves to site 1, and ramps down.
+    let ramp_time = 50.0e-6;
+    let move_time = 200.0e-6;
+    sim.world
+        .create_entity()
+        .with(GaussianBeam {
+            intersection: site(4),
+            e_radius,
+            power: 0.0,
+            direction: Vector3::z(),
+            rayleigh_range,
+            ellipticity: 0.0,
+        })
+        .with(dipole::DipoleLight { wavelength })
+        .with(laser::frame::Frame {
+            x_vector: Vector3::x(),
+            y_vector: Vector3::y(),
+        })
+        .with(BeamTrajectory::MinimumJerk {
+            start: site(4),
+            end: site(1),
+            start_time: ramp_time,
+            duration: move_time,
+        })
+        .with(PowerModulation::Table(vec![
+            (0.0, 0.0),
+            (ramp_time, 0.02),
+            (ramp_time + move_time, 0.02),
+            (2.0 * ramp_time + move_time, 0.0),
+        ]))
+        .build();
+
+    // Load atoms at rest into sites 0, 2 and 4.
+    for i in [0, 2, 4].iter() {
+        sim.world
+            .create_entity()
+            .with(atom::Mass { value: 87.0 })
+            .with(atom::Force::new())
+            .with(Position { pos: site(*i) })
+            .with(Velocity {
+                vel: Vector3::new(0.0, 0.0, 0.0),
+            })
+            .with(dipole::Polarizability::calculate_for(
+                wavelength, 780e-9, 6.07e6,
+            ))
+            .with(Atom)
+            .with(lib::initiate::NewlyCreated)
+            .build();
+    }
+
+    let total_time = 3.0 * ramp_time + move_time;
+    for _ in 0..(total_time / dt) as u64 {
+        sim.step();
+    }
+
+    let positions = sim.world.read_storage::<Position>();
+    for i in 0..5 {
+        let occupied = positions
+            .join()
+            .any(|p| (p.pos - site(i)).norm() < spacing / 4.0);
+        println!(
+            "site {}: {}",
+            i,
+            if occupied { "occupied" } else { "empty" }
+        );
+    }
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
@@ -0,0 +1,140 @@
+//! Transport of atoms in a moving optical tweezer.
+//!
+//! A tweezer holding a thermal cloud is moved by 20um along a minimum-jerk trajectory.
+//! The simulation is repeated for different move times, and the temperature of the atoms after
+//! the move and the fraction of atoms which remain trapped are printed for each.
+extern crate atomecs as lib;
+extern crate nalgebra;
+use lib::atom::{self, Atom, Position, Velocity};
+use lib::constant::{AMU, BOLTZCONST};
+use lib::dipole::{self, DipolePlugin};
+use lib::integrator::Timestep;
+use lib::laser::gaussian::GaussianBeam;
+use lib::laser::trajectory::BeamTrajectory;
+use lib::laser::{self, LaserPlugin};
+use lib::simulation::SimulationBuilder;
+use nalgebra::Vector3;
+use rand_distr::{Distribution, Normal};
+use specs::prelude::*;
+use std::time::Instant;
+
+const BEAM_NUMBER: usize = 1;
+
+fn main() {
+    let now = Instant::now();
+    println!("move time (us)\ttemperature (uK)\ttrapped fraction");
+    for move_time in [20.0e-6, 50.0e-6, 100.0e-6, 200.0e-6].iter() {
+        let (temperature, trapped) = simulate_transport(*move_time);
+        println!(
+            "{:.0}\t{:.1}\t{:.2}",
+            move_time * 1e6,
+            temperature * 1e6,
+            trapped
+        );
+    }
+    println!("Simulation completed in {} ms.", now.elapsed().as_millis());
+}
+
+/// Simulates a single move, returning the temperature (in K) and fraction of atoms remaining in the tweezer.
+fn simulate_transport(move_time: f64) -> (f64, f64) {
+    let mut sim_builder = SimulationBuilder::default();
+    sim_builder.add_plugin(LaserPlugin::<{ BEAM_NUMBER }>);
+    sim_builder.add_plugin(DipolePlugin::<{ BEAM_NUMBER }>);
+    let mut sim = sim_builder.build();
+
+    let dt = 1.0e-7;
+    sim.world.insert(Timestep { delta: dt });
+
+    // A 1064nm tweezer focused to 1um, which moves by 20um along x.
+    let wavelength = 1064.0e-9;
+    let e_radius = 1.0e-6 / 2.0_f64.sqrt();
+    let start = Vector3::new(0.0, 0.0, 0.0);
+    let end = Vector3::new(20.0e-6, 0.0, 0.0);
+    let hold_time = 100.0e-6;
+    sim.world
+        .create_entity()
+        .with(GaussianBeam {
+            intersection: start,
+            e_radius,
+            power: 0.01,
+            direction: Vector3::z(),
+            rayleigh_range: laser::gaussian::calculate_rayleigh_range(&wavelength, &e_radius),
+            ellipticity: 0.0,
+        })
+        .with(dipole::DipoleLight { wavelength })
+        .with(laser::frame::Frame {
+            x_vector: Vector3::x(),
+            y_vector: Vector3::y(),
+        })
+        .with(BeamTrajectory::MinimumJerk {
+            start,
+            end,
+            start_time: 0.0,
+            duration: move_time,
+        })
+        .build();
+
+    // A thermal cloud of Rb87 atoms at 20uK, in a trap with radial and axial frequencies of about 96kHz and 23kHz.
+    let mass = 87.0;
+    let temperature = 20.0e-6;
+    let velocity_width = (BOLTZCONST * temperature / (mass * AMU)).sqrt();
+    let radial_width = velocity_width / (2.0 * std::f64::consts::PI * 96.0e3);
+    let axial_width = velocity_width / (2.0 * std::f64::consts::PI * 23.0e3);
+    let mut rng = rand::thread_rng();
+    let radial = Normal::new(0.0, radial_width).unwrap();
+    let axial = Normal::new(0.0, axial_width).unwrap();
+    let velocity = Normal::new(0.0, velocity_width).unwrap();
+    let number_of_atoms = 50;
+    for _ in 0..number_of_atoms {
+        sim.world
+            .create_entity()
+            .with(atom::Mass { value: mass })
+            .with(atom::Force::new())
+            .with(Position {
+                pos: Vector3::new(
+                    radial.sample(&mut rng),
+                    radial.sample(&mut rng),
+                    axial.sample(&mut rng),
+                ),
+            })
+            .with(Velocity {
+                vel: Vector3::new(
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                    velocity.sample(&mut rng),
+                ),
+            })
+            .with(dipole::Polarizability::calculate_for(
+                wavelength, 780e-9, 6.07e6,
+            ))
+            .with(Atom)
+            .with(lib::initiate::NewlyCreated)
+            .build();
+    }
+
+    let move_steps = (move_time / dt) as u64;
+    let hold_steps = (hold_time / dt) as u64;
+    for _ in 0..move_steps {
+        sim.step();
+    }
+
+    // After the move, time-average the kinetic energy to estimate the temperature.
+    let mut kinetic_energy = 0.0;
+    for _ in 0..hold_steps {
+        sim.step();
+        let velocities = sim.world.read_storage::<Velocity>();
+        kinetic_energy += velocities
+            .join()
+            .map(|v| 0.5 * mass * AMU * v.vel.norm_squared())
+            .sum::<f64>()
+            / (number_of_atoms as f64 * hold_steps as f64);
+    }
+    let temperature = kinetic_energy / (1.5 * BOLTZCONST);
+
+    let positions = sim.world.read_storage::<Position>();
+    let trapped = positions
+        .join()
+        .filter(|p| (p.pos - end).norm() < 5.0e-6)
+        .count();
+    (temperature, trapped as f64 / number_of_atoms as f64)
+}
@@ -1,6 +1,7 @@
 //! Masses and isotopes of atoms
 
 use crate::atom::Mass;
+use crate::laser_cooling::isotope::IsotopeShifts;
 use rand;
 use rand::Rng;
 extern crate specs;
@@ -74,6 +75,19 @@ impl MassDistribution {
     }
 }
 
+/// The isotope of each mass of a [MassDistribution](struct.MassDistribution.html).
+///
+/// Add this component to an atom source alongside its `MassDistribution`. The `i`th entry gives the
+/// `IsotopeShifts` of atoms drawn from the `i`th [MassRatio](struct.MassRatio.html) of the distribution,
+/// or is `None` if the transitions of those atoms are not shifted.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct IsotopeDistribution {
+    pub isotopes: Vec<Option<IsotopeShifts>>,
+}
+impl Component for IsotopeDistribution {
+    type Storage = HashMapStorage<Self>;
+}
+
 pub mod tests {
     #[allow(unused_imports)]
     use super::*;
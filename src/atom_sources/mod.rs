@@ -114,6 +114,7 @@ fn add_systems_to_dispatch<T>(
 fn register_components<T>(world: &mut World) where T : AtomCreator + 'static {
     world.register::<oven::Oven<T>>();
     world.register::<mass::MassDistribution>();
+    world.register::<mass::IsotopeDistribution>();
     world.register::<emit::EmitFixedRate>();
     world.register::<emit::EmitNumberPerFrame>();
     world.register::<emit::EmitOnce>();
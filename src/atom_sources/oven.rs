@@ -204,7 +204,7 @@ impl<'a, T> System<'a> for OvenCreateAtomsSystem<T> where T : AtomCreator + 'sta
             (&oven, &numbers_to_emit, &pos, &precalcs).join()
         {
             for _i in 0..number_to_emit.number {
-                let (mass, speed) = precalcs.generate_random_mass_v(&mut rng);
+                let (mass, isotope, speed) = precalcs.generate_random_isotope_v(&mut rng);
                 if speed > max_vel {
                     continue;
                 }
@@ -235,6 +235,9 @@ impl<'a, T> System<'a> for OvenCreateAtomsSystem<T> where T : AtomCreator + 'sta
                 updater.insert(new_atom, InitialVelocity { vel: new_vel });
                 updater.insert(new_atom, NewlyCreated);
                 T::mutate(&updater, new_atom);
+                if let Some(isotope) = isotope {
+                    T::mutate_isotope(&updater, new_atom, isotope);
+                }
             }
         }
     }
@@ -1,8 +1,9 @@
 //! Utilities for precalculating quantities such as mass and velocity distributions.
 
-use super::mass::MassDistribution;
+use super::mass::{IsotopeDistribution, MassDistribution};
 use super::WeightedProbabilityDistribution;
 use crate::constant::{AMU, BOLTZCONST, EXP};
+use crate::laser_cooling::isotope::IsotopeShifts;
 
 use rand;
 use rand::distributions::Distribution;
@@ -64,13 +65,16 @@ pub fn probability_v(temperature: f64, mass: f64, v: f64, power: f64) -> f64 {
 pub struct Species {
     /// Mass of the species, in atomic mass units
     mass: f64,
+    /// Isotope shifts of the transitions of the species, if any.
+    isotope: Option<IsotopeShifts>,
     /// Distribution that can be used to generate random velocity magnitudes `v`.
     v_distribution: WeightedProbabilityDistribution,
 }
 impl Species {
-    fn create(mass: f64, temperature: f64, power: f64) -> Self {
+    fn create(mass: f64, isotope: Option<IsotopeShifts>, temperature: f64, power: f64) -> Self {
         Species {
             mass,
+            isotope,
             v_distribution: create_v_distribution(temperature, mass * AMU, power),
         }
     }
@@ -94,12 +98,34 @@ impl PrecalculatedSpeciesInformation {
         (species.mass, species.v_distribution.sample(rng))
     }
 
-    fn create(temperature: f64, mass_distribution: &MassDistribution, power: f64) -> Self {
+    /// Gets a random mass, isotope and velocity from the precalculated distributions.
+    ///
+    /// The tuple returned is of the form (mass, isotope, velocity), see `generate_random_mass_v`.
+    pub fn generate_random_isotope_v<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> (f64, Option<&IsotopeShifts>, f64) {
+        let i = self.distribution.sample(rng);
+        let species = &self.species[i];
+        (
+            species.mass,
+            species.isotope.as_ref(),
+            species.v_distribution.sample(rng),
+        )
+    }
+
+    fn create(
+        temperature: f64,
+        mass_distribution: &MassDistribution,
+        isotopes: Option<&IsotopeDistribution>,
+        power: f64,
+    ) -> Self {
         let mut species = Vec::<Species>::new();
         let mut ratios = Vec::<f64>::new();
-        for mr in &mass_distribution.distribution {
+        for (i, mr) in mass_distribution.distribution.iter().enumerate() {
+            let isotope = isotopes.and_then(|isotopes| isotopes.isotopes.get(i).cloned().flatten());
             ratios.push(mr.ratio);
-            species.push(Species::create(mr.mass, temperature, power));
+            species.push(Species::create(mr.mass, isotope, temperature, power));
         }
         PrecalculatedSpeciesInformation {
             species,
@@ -118,9 +144,10 @@ pub trait MaxwellBoltzmannSource {
 
 /// Precalculates different distributions used by the Oven systems.
 ///
-/// This system removes the [MassDistribution](struct.MassDistribution.html) component from
-/// the oven and replaces it with a [PrecalculatedForSpeciesSystem] that contains all
-/// precalculated information required to generate atoms from the distribution.
+/// This system removes the [MassDistribution](struct.MassDistribution.html) component, and any
+/// [IsotopeDistribution](struct.IsotopeDistribution.html), from the oven and replaces them with a
+/// [PrecalculatedForSpeciesSystem] that contains all precalculated information required to generate
+/// atoms from the distribution.
 #[derive(Default)]
 pub struct PrecalculateForSpeciesSystem<T: MaxwellBoltzmannSource> {
     pub marker: PhantomData<T>,
@@ -134,18 +161,35 @@ where
         Entities<'a>,
         ReadStorage<'a, T>,
         WriteStorage<'a, MassDistribution>,
+        WriteStorage<'a, IsotopeDistribution>,
         WriteStorage<'a, PrecalculatedSpeciesInformation>,
     );
 
-    fn run(&mut self, (entities, sources, mut mass_distributions, mut precalcs): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            entities,
+            sources,
+            mut mass_distributions,
+            mut isotope_distributions,
+            mut precalcs,
+        ): Self::SystemData,
+    ) {
         // Precalculate for ovens which do not currently have precalculated information.
         let mut precalculated_data = Vec::<(Entity, PrecalculatedSpeciesInformation)>::new();
-        for (entity, source, mass_dist, _) in
-            (&entities, &sources, &mass_distributions, !&precalcs).join()
+        for (entity, source, mass_dist, isotopes, _) in (
+            &entities,
+            &sources,
+            &mass_distributions,
+            isotope_distributions.maybe(),
+            !&precalcs,
+        )
+            .join()
         {
             let precalculated = PrecalculatedSpeciesInformation::create(
                 source.get_temperature(),
                 mass_dist,
+                isotopes,
                 source.get_v_dist_power(),
             );
             //mass_distributions.remove(entity);
@@ -156,9 +200,57 @@ where
 
         for (entity, precalculated) in precalculated_data {
             mass_distributions.remove(entity);
+            isotope_distributions.remove(entity);
             precalcs
                 .insert(entity, precalculated)
                 .expect("Could not add precalculated data to oven.");
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::atom_sources::mass::MassRatio;
+    use crate::laser_cooling::isotope::Isotope;
+
+    #[test]
+    fn test_generate_random_isotope() {
+        let distribution = MassDistribution::new(vec![
+            MassRatio {
+                mass: 88.0,
+                ratio: 1.0,
+            },
+            MassRatio {
+                mass: 86.0,
+                ratio: 1.0,
+            },
+        ]);
+        let isotopes = IsotopeDistribution {
+            isotopes: vec![
+                None,
+                Some(IsotopeShifts::from([(
+                    "461".to_string(),
+                    Isotope::shifted(-124.8e6),
+                )])),
+            ],
+        };
+        let precalculated =
+            PrecalculatedSpeciesInformation::create(500.0, &distribution, Some(&isotopes), 3.0);
+        let mut rng = rand::thread_rng();
+        let mut shifted = 0;
+        for _ in 0..1000 {
+            let (mass, isotope, speed) = precalculated.generate_random_isotope_v(&mut rng);
+            assert!(speed > 0.0);
+            match isotope {
+                Some(isotope) => {
+                    assert_eq!(mass, 86.0);
+                    assert_eq!(isotope["461"].shift, -124.8e6);
+                    shifted += 1;
+                }
+                None => assert_eq!(mass, 88.0),
+            }
+        }
+        assert!(shifted > 400 && shifted < 600);
+    }
+}
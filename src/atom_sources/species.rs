@@ -1,11 +1,15 @@
 //! Different implementations for created atom species.
 
+use crate::laser_cooling::isotope::IsotopeShifts;
 use specs::prelude::*;
 
 /// Allows atoms to be modified after they are created.
 pub trait AtomCreationModifier {
     /// Modifies the created atom
     fn mutate(updater: &LazyUpdate, new_atom: Entity);
+
+    /// Modifies a created atom which belongs to a minority isotope.
+    fn mutate_isotope(_updater: &LazyUpdate, _new_atom: Entity, _isotope: &IsotopeShifts) {}
 }
 pub trait AtomCreator : AtomCreationModifier + Copy + Send + Sync + Default {}
 impl<T> AtomCreator for T where T : AtomCreationModifier + Copy + Send + Sync + Default {}
@@ -19,6 +23,10 @@ impl<T> Species for T where T : AtomCreator {}
 /// * `species_name`: name of the generated struct.
 /// * `transition`: laser cooling transition to use.
 /// * `mass`: mass of this species in atomic mass units.
+/// * `isotopes` (optional): the transitions which are shifted for atoms of a minority isotope, as a list of
+///   `"name" => Transition`, where `name` is the name of the transition in the `IsotopeShifts`, eg
+///   `isotopes: ["461" => Strontium88_461, "689" => Strontium88_689]`. An `IsotopeShift` is attached for
+///   each listed transition that the isotope shifts; without this list, no isotope shifts are attached.
 #[macro_export]
 macro_rules! species {
     // This macro takes an argument of designator `ident` and
@@ -34,4 +42,66 @@ macro_rules! species {
             }
         }
     };
+    (
+        $species_name:ident,
+        $transition: ident,
+        $mass: literal,
+        isotopes: [$($name: literal => $shifted: ident),+ $(,)?]
+    ) => {
+        /// A species that can be used in an atom source.
+        #[derive(Copy, Clone, Default)]
+        pub struct $species_name;
+        impl $crate::atom_sources::species::AtomCreationModifier for $species_name {
+            fn mutate(updater: &specs::LazyUpdate, new_atom: specs::Entity) {
+                updater.insert(new_atom, $transition::default());
+            }
+            fn mutate_isotope(
+                updater: &specs::LazyUpdate,
+                new_atom: specs::Entity,
+                isotope: &$crate::laser_cooling::isotope::IsotopeShifts,
+            ) {
+                $(
+                    if let Some(shift) = isotope.get($name) {
+                        updater.insert(
+                            new_atom,
+                            $crate::laser_cooling::isotope::IsotopeShift::<$shifted>::new(*shift),
+                        );
+                    }
+                )+
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::laser_cooling::isotope::{Isotope, IsotopeShift};
+    use crate::species::{Strontium88, Strontium88_461, Strontium88_689};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_isotope_shifts_attached_for_each_transition() {
+        let mut test_world = World::new();
+        test_world.register::<Strontium88_461>();
+        test_world.register::<IsotopeShift<Strontium88_461>>();
+        test_world.register::<IsotopeShift<Strontium88_689>>();
+
+        let isotope = IsotopeShifts::from([
+            ("461".to_string(), Isotope::shifted(-124.8e6)),
+            ("689".to_string(), Isotope::shifted(-163.8e6)),
+        ]);
+        let atom = test_world.create_entity().build();
+        {
+            let updater = test_world.read_resource::<LazyUpdate>();
+            Strontium88::mutate(&updater, atom);
+            Strontium88::mutate_isotope(&updater, atom, &isotope);
+        }
+        test_world.maintain();
+
+        let blue = test_world.read_storage::<IsotopeShift<Strontium88_461>>();
+        let red = test_world.read_storage::<IsotopeShift<Strontium88_689>>();
+        assert_approx_eq!(blue.get(atom).unwrap().isotope.shift, -124.8e6);
+        assert_approx_eq!(red.get(atom).unwrap().isotope.shift, -163.8e6);
+    }
 }
@@ -83,8 +83,8 @@ impl<'a, T> System<'a> for CreateAtomsOnSurfaceSystem<T> where T : AtomCreator +
             .join()
         {
             for _i in 0..number_to_emit.number {
-                // Get random speed and mass.
-                let (mass, speed) = species.generate_random_mass_v(&mut rng);
+                // Get random speed, mass and isotope.
+                let (mass, isotope, speed) = species.generate_random_isotope_v(&mut rng);
                 if speed > max_vel {
                     continue;
                 }
@@ -126,6 +126,9 @@ impl<'a, T> System<'a> for CreateAtomsOnSurfaceSystem<T> where T : AtomCreator +
                 updater.insert(new_atom, InitialVelocity { vel: velocity });
                 updater.insert(new_atom, NewlyCreated);
                 T::mutate(&updater, new_atom);
+                if let Some(isotope) = isotope {
+                    T::mutate_isotope(&updater, new_atom, isotope);
+                }
             }
         }
     }
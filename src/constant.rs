@@ -24,5 +24,8 @@ pub const AMU: f64 = 1.6605e-27;
 /// Speed of light in SI units of m/s
 pub const C: f64 = 299297458.0;
 
+/// Vacuum permittivity in SI units
+pub const EPSILON0: f64 = 8.8541878128e-12;
+
 /// Sqrt of 2
 pub const SQRT2: f64 = std::f64::consts::SQRT_2;
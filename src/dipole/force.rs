@@ -4,13 +4,15 @@ use specs::{Join, ReadStorage, System, WriteStorage};
 extern crate nalgebra;
 use crate::atom::Force;
 use crate::dipole::DipoleLight;
+use crate::dipole::polarizability::DipolePrefactors;
 use crate::dipole::Polarizability;
 use crate::laser::index::LaserIndex;
 
 /// Calculates forces exerted onto the atoms by dipole laser beams.
 ///
 /// It uses the `LaserIntensityGradientSamplers` and the properties of the `DipoleLight`
-/// to add the respective amount of force to `Force`
+/// to add the respective amount of force to `Force`. Atoms with `DipolePrefactors` use
+/// the prefactor for each beam, otherwise the scalar `Polarizability` is used.
 pub struct ApplyDipoleForceSystem<const N: usize>;
 
 impl<'a, const N: usize> System<'a> for ApplyDipoleForceSystem<N> {
@@ -18,20 +20,30 @@ impl<'a, const N: usize> System<'a> for ApplyDipoleForceSystem<N> {
         ReadStorage<'a, DipoleLight>,
         ReadStorage<'a, LaserIndex>,
         ReadStorage<'a, Polarizability>,
+        ReadStorage<'a, DipolePrefactors<N>>,
         ReadStorage<'a, LaserIntensityGradientSamplers<N>>,
         WriteStorage<'a, Force>,
     );
 
     fn run(
         &mut self,
-        (dipole_light, dipole_index, polarizability, gradient_sampler, mut force): Self::SystemData,
+        (dipole_light, dipole_index, polarizability, prefactors, gradient_sampler, mut force): Self::SystemData,
     ) {
-        (&mut force, &polarizability, &gradient_sampler)
+        (
+            &mut force,
+            polarizability.maybe(),
+            prefactors.maybe(),
+            &gradient_sampler,
+        )
             .par_join()
-            .for_each(|(force, polarizability, sampler)| {
+            .for_each(|(force, polarizability, prefactors, sampler)| {
                 for (index, _dipole) in (&dipole_index, &dipole_light).join() {
-                    force.force +=
-                        polarizability.prefactor * sampler.contents[index.index].gradient;
+                    let prefactor = match (prefactors, polarizability) {
+                        (Some(prefactors), _) => prefactors.contents[index.index],
+                        (None, Some(polarizability)) => polarizability.prefactor,
+                        (None, None) => continue,
+                    };
+                    force.force += prefactor * sampler.contents[index.index].gradient;
                 }
             });
     }
@@ -60,6 +72,7 @@ pub mod tests {
         test_world.register::<Force>();
         test_world.register::<LaserIntensityGradientSamplers<{ DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<Polarizability>();
+        test_world.register::<DipolePrefactors<{ DEFAULT_BEAM_LIMIT }>>();
 
         let transition_linewidth = 32e6;
         let transition_lambda = 461e-9;
@@ -115,6 +128,7 @@ pub mod tests {
         test_world.register::<Force>();
         test_world.register::<LaserIntensityGradientSamplers<{ DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<Polarizability>();
+        test_world.register::<DipolePrefactors<{ DEFAULT_BEAM_LIMIT }>>();
 
         test_world
             .create_entity()
@@ -160,9 +174,14 @@ pub mod tests {
         test_world.register::<Force>();
         test_world.register::<LaserIntensityGradientSamplers<{ DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<Polarizability>();
+        test_world.register::<DipolePrefactors<{ DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<crate::atom::Position>();
         test_world.register::<crate::laser::gaussian::GaussianBeam>();
         test_world.register::<crate::laser::frame::Frame>();
+        test_world.register::<crate::laser::profile::BeamProfile>();
+        test_world.register::<crate::laser::gaussian::AstigmaticBeam>();
+        test_world.register::<crate::laser::gaussian::CircularMask>();
+        test_world.register::<crate::laser::grating::GratingFootprint>();
 
         let power = 10.0;
         let e_radius = 60.0e-6 / (2.0_f64.sqrt());
@@ -250,12 +269,12 @@ pub mod tests {
         //println!("gradient 2 is: {}", sim_result_grad[1].gradient);
 
         assert_approx_eq!(
-            0.000000000000000000000000000000000127913190642808,
+            0.000000000000000000000000000000000127914209996983,
             sim_result_force[0],
             3e-46_f64
         );
         assert_approx_eq!(
-            0.000000000000000000000000000000000127913190642808,
+            0.000000000000000000000000000000000127914209996983,
             sim_result_force[1],
             2e-46_f64
         );
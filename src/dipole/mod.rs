@@ -3,6 +3,7 @@
 use specs::DispatcherBuilder;
 
 use crate::laser::LaserPlugin;
+use crate::magnetic::MagneticsPlugin;
 use crate::{constant, simulation::Plugin};
 use crate::laser::index::LaserIndex;
 
@@ -10,6 +11,8 @@ use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 
 pub mod force;
+pub mod polarizability;
+pub mod scattering;
 
 /// A component marking the entity as laser beam for dipole forces and
 /// holding properties of the light
@@ -85,6 +88,9 @@ impl<'a> System<'a> for AttachIndexToDipoleLightSystem {
 /// This plugin implements a dipole force that can be used to confine cold atoms.
 /// 
 /// See also [crate::dipole]
+///
+/// Atoms with a [polarizability::StatePolarizability] use the local magnetic field as quantisation
+/// axis, so this plugin depends on the `MagneticsPlugin`, which is added by the default `SimulationBuilder`.
 /// 
 /// # Generic Arguments
 /// 
@@ -93,10 +99,10 @@ pub struct DipolePlugin<const N : usize>;
 impl<const N: usize> Plugin for DipolePlugin<N> {
     fn build(&self, builder: &mut crate::simulation::SimulationBuilder) {
         add_systems_to_dispatch::<N>(&mut builder.dispatcher_builder, &[]);
-        register_components(&mut builder.world);
+        register_components::<N>(&mut builder.world);
     }
     fn deps(&self) -> Vec::<Box<dyn Plugin>> {
-        vec![Box::new(LaserPlugin::<{N}>)]
+        vec![Box::new(LaserPlugin::<{N}>), Box::new(MagneticsPlugin)]
     }
 }
 
@@ -111,10 +117,34 @@ fn add_systems_to_dispatch<const N: usize>(
     builder: &mut DispatcherBuilder<'static, 'static>,
     deps: &[&str],
 ) {
+    builder.add(
+        polarizability::AttachDipolePrefactorsSystem::<N>,
+        "attach_dipole_prefactors",
+        deps,
+    );
+    builder.add(
+        polarizability::CalculateDipolePrefactorsSystem::<N>,
+        "calculate_dipole_prefactors",
+        &["index_lasers", "magnetics_magnitude"],
+    );
     builder.add(
         force::ApplyDipoleForceSystem::<N>,
         "apply_dipole_force",
-        &["sample_intensity_gradient"],
+        &[
+            "sample_intensity_gradient",
+            "sample_coherent_beams",
+            "calculate_dipole_prefactors",
+        ],
+    );
+    builder.add(
+        scattering::DipoleScatteringSystem::<N>,
+        "dipole_scattering",
+        &[
+            "sample_laser_intensity",
+            "sample_coherent_beams",
+            "calculate_dipole_prefactors",
+            "apply_dipole_force",
+        ],
     );
     builder.add(
         crate::dipole::AttachIndexToDipoleLightSystem,
@@ -123,6 +153,10 @@ fn add_systems_to_dispatch<const N: usize>(
     );
 }
 
-fn register_components(world: &mut World) {
+fn register_components<const N: usize>(world: &mut World) {
     world.register::<DipoleLight>();
+    world.register::<polarizability::StatePolarizability>();
+    world.register::<polarizability::DipolePrefactors<N>>();
+    world.register::<crate::magnetic::MagneticFieldSampler>();
+    world.register::<scattering::DipoleScattering>();
 }
@@ -0,0 +1,336 @@
+//! State-dependent polarizability, calculated from a list of dipole transitions.
+//!
+//! The dynamic polarizability of a fine-structure level `|J, m_J>` is decomposed into scalar,
+//! vector and tensor parts (see e.g. [Le Kien et al., Eur. Phys. J. D 67, 92 (2013)](https://doi.org/10.1140/epjd/e2013-30729-x)).
+//! The vector and tensor parts depend on the polarization of the `DipoleLight` and on the
+//! local quantisation axis, which is taken from the `MagneticFieldSampler` of the atom.
+//!
+//! All polarizabilities here are expressed in the same units as `Polarizability::prefactor`,
+//! so that the energy shift in a beam of intensity `I` is `-prefactor * I`.
+
+use std::sync::Arc;
+
+use super::DipoleLight;
+use crate::constant;
+use crate::initiate::NewlyCreated;
+use crate::laser::index::LaserIndex;
+use crate::laser::polarization::Polarization;
+use crate::magnetic::MagneticFieldSampler;
+use crate::maths::wigner_6j;
+use nalgebra::{Complex, Vector3};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+/// A dipole transition which couples an atomic level to another level.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct DipoleTransition {
+    /// Wavelength of the transition, in SI units of m.
+    pub wavelength: f64,
+    /// Linewidth of the transition, in units of Hz. This is the decay rate of the upper level into the lower level, divided by 2π.
+    pub linewidth: f64,
+    /// Total angular momentum `J` of the other level.
+    pub j: f64,
+    /// True if the other level lies above the level being described.
+    pub upper: bool,
+}
+
+/// A fine-structure level, described by its total angular momentum and the transitions that couple to it.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct AtomicLevel {
+    /// Total angular momentum `J` of the level.
+    pub j: f64,
+    /// All transitions to other levels which contribute to the polarizability.
+    pub transitions: Vec<DipoleTransition>,
+}
+
+impl AtomicLevel {
+    /// Calculates the scalar, vector and tensor polarizabilities of the level for light of the given wavelength.
+    ///
+    /// Each part is returned in the units of `Polarizability::prefactor`.
+    pub fn polarizability_components(&self, wavelength: f64) -> (f64, f64, f64) {
+        let omega = 2.0 * constant::PI * constant::C / wavelength;
+        let j = self.j;
+        let mut reduced = [0.0; 3];
+        for transition in self.transitions.iter() {
+            let omega_transition = 2.0 * constant::PI * constant::C / transition.wavelength;
+            let j_upper = if transition.upper { transition.j } else { j };
+            // Squared reduced dipole matrix element, in units of 2 hbar epsilon_0 c.
+            let dipole_squared = 3.0
+                * constant::PI
+                * constant::C.powi(2)
+                * 2.0
+                * constant::PI
+                * transition.linewidth
+                * (2.0 * j_upper + 1.0)
+                / (2.0 * omega_transition.powi(3));
+            let resonance = if transition.upper {
+                omega_transition
+            } else {
+                -omega_transition
+            };
+            for (k, value) in reduced.iter_mut().enumerate() {
+                let kf = k as f64;
+                let parity = if k % 2 == 0 { 1.0 } else { -1.0 };
+                let sign = (-1.0_f64).powf(kf + j + 1.0 + transition.j);
+                *value += sign
+                    * (2.0 * kf + 1.0).sqrt()
+                    * wigner_6j(1.0, kf, 1.0, j, transition.j, j)
+                    * dipole_squared
+                    * (1.0 / (resonance - omega) + parity / (resonance + omega));
+            }
+        }
+
+        let scalar = reduced[0] / (3.0 * (2.0 * j + 1.0)).sqrt();
+        let vector = if j > 0.0 {
+            -reduced[1] * (2.0 * j / ((j + 1.0) * (2.0 * j + 1.0))).sqrt()
+        } else {
+            0.0
+        };
+        let tensor = if j > 0.5 {
+            -reduced[2]
+                * (2.0 * j * (2.0 * j - 1.0)
+                    / (3.0 * (j + 1.0) * (2.0 * j + 1.0) * (2.0 * j + 3.0)))
+                    .sqrt()
+        } else {
+            0.0
+        };
+        (scalar, vector, tensor)
+    }
+
+    /// Calculates the polarizability of the state `m_j` in light of the given wavelength and polarization.
+    ///
+    /// # Arguments
+    ///
+    /// `wavelength`: wavelength of the light, in m.
+    ///
+    /// `m_j`: projection of the angular momentum onto the quantisation axis.
+    ///
+    /// `polarization`: complex polarization vector of the light, or `None` to use only the scalar part.
+    ///
+    /// `axis`: unit vector along the quantisation axis.
+    pub fn polarizability(
+        &self,
+        wavelength: f64,
+        m_j: f64,
+        polarization: Option<&Polarization>,
+        axis: &Vector3<f64>,
+    ) -> f64 {
+        let (scalar, vector, tensor) = self.polarizability_components(wavelength);
+        let polarization = match polarization {
+            Some(polarization) => polarization,
+            None => return scalar,
+        };
+        let u = polarization.vector;
+        let axis_c = axis.map(|x| Complex::new(x, 0.0));
+        // Degree of circular polarization along the axis, C = Im((u* x u) . axis).
+        let circularity = u.map(|c| c.conj()).cross(&u).dot(&axis_c).im;
+        let longitudinal = u.dot(&axis_c).norm_sqr();
+        let alignment = (1.0 - 3.0 * longitudinal) / 2.0;
+
+        let mut total = scalar;
+        if self.j > 0.0 {
+            total += circularity * vector * m_j / (2.0 * self.j);
+        }
+        if self.j > 0.5 {
+            total -= alignment * tensor * (3.0 * m_j * m_j - self.j * (self.j + 1.0))
+                / (self.j * (2.0 * self.j - 1.0));
+        }
+        total
+    }
+}
+
+/// An atom component which describes the polarizability of an atom in a specific `m_J` state of an `AtomicLevel`.
+///
+/// Atoms with a `StatePolarizability` have their dipole force calculated per beam, using
+/// the `DipolePrefactors` computed by `CalculateDipolePrefactorsSystem`. This replaces
+/// the scalar `Polarizability` of the atom.
+#[derive(Clone)]
+pub struct StatePolarizability {
+    /// The level occupied by the atom.
+    pub level: Arc<AtomicLevel>,
+    /// Projection of the angular momentum onto the quantisation axis.
+    pub m_j: f64,
+}
+impl Component for StatePolarizability {
+    type Storage = VecStorage<Self>;
+}
+
+/// The polarizability prefactor of an atom with respect to each dipole beam.
+#[derive(Clone, Copy)]
+pub struct DipolePrefactors<const N: usize> {
+    /// Prefactor with respect to each beam, in the units of `Polarizability::prefactor`.
+    pub contents: [f64; N],
+}
+impl<const N: usize> Default for DipolePrefactors<N> {
+    fn default() -> Self {
+        DipolePrefactors { contents: [0.0; N] }
+    }
+}
+impl<const N: usize> Component for DipolePrefactors<N> {
+    type Storage = VecStorage<Self>;
+}
+
+/// Attaches `DipolePrefactors` to newly created atoms that have a `StatePolarizability`.
+pub struct AttachDipolePrefactorsSystem<const N: usize>;
+impl<'a, const N: usize> System<'a> for AttachDipolePrefactorsSystem<N> {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, NewlyCreated>,
+        ReadStorage<'a, StatePolarizability>,
+        Read<'a, LazyUpdate>,
+    );
+    fn run(&mut self, (ent, newly_created, polarizability, updater): Self::SystemData) {
+        for (ent, _, _) in (&ent, &newly_created, &polarizability).join() {
+            updater.insert(ent, DipolePrefactors::<N>::default());
+        }
+    }
+}
+
+/// Calculates the `DipolePrefactors` of each atom for all `DipoleLight` beams.
+///
+/// The quantisation axis is the direction of the local magnetic field, or the z axis if the
+/// atom has no `MagneticFieldSampler` or the field vanishes. Beams without a `Polarization`
+/// only contribute through the scalar polarizability. For beams in a `CoherentBeam` group, the
+/// polarization of the beam holding the group total is used.
+pub struct CalculateDipolePrefactorsSystem<const N: usize>;
+impl<'a, const N: usize> System<'a> for CalculateDipolePrefactorsSystem<N> {
+    type SystemData = (
+        ReadStorage<'a, DipoleLight>,
+        ReadStorage<'a, LaserIndex>,
+        ReadStorage<'a, Polarization>,
+        ReadStorage<'a, StatePolarizability>,
+        ReadStorage<'a, MagneticFieldSampler>,
+        WriteStorage<'a, DipolePrefactors<N>>,
+    );
+
+    fn run(
+        &mut self,
+        (dipole_light, indices, polarizations, state_polarizabilities, fields, mut prefactors): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        let beams: Vec<(usize, DipoleLight, Option<Polarization>)> =
+            (&dipole_light, &indices, polarizations.maybe())
+                .join()
+                .map(|(light, index, polarization)| (index.index, *light, polarization.cloned()))
+                .collect();
+
+        (&state_polarizabilities, fields.maybe(), &mut prefactors)
+            .par_join()
+            .for_each(|(state, field, prefactors)| {
+                let axis = match field {
+                    Some(field) if field.magnitude > 0.0 => field.field / field.magnitude,
+                    _ => Vector3::z(),
+                };
+                for (index, light, polarization) in beams.iter() {
+                    prefactors.contents[*index] = state.level.polarizability(
+                        light.wavelength,
+                        state.m_j,
+                        polarization.as_ref(),
+                        &axis,
+                    );
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::dipole::Polarizability;
+    use crate::species::rubidium87_ground_level;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_scalar_polarizability_matches_two_level() {
+        let level = AtomicLevel {
+            j: 0.0,
+            transitions: vec![DipoleTransition {
+                wavelength: 461e-9,
+                linewidth: 32e6,
+                j: 1.0,
+                upper: true,
+            }],
+        };
+        let (scalar, vector, tensor) = level.polarizability_components(1064e-9);
+        let two_level = Polarizability::calculate_for(1064e-9, 461e-9, 32e6);
+        assert_approx_eq!(scalar / two_level.prefactor, 1.0, 1e-9);
+        assert_eq!(vector, 0.0);
+        assert_eq!(tensor, 0.0);
+    }
+
+    #[test]
+    fn test_vector_polarizability_in_circular_light() {
+        let level = rubidium87_ground_level();
+        let axis = Vector3::z();
+        let linear = Polarization::linear(Vector3::x());
+        let circular = Polarization::circular(Vector3::z(), 1);
+
+        // Linear light does not split the m_J states.
+        assert_approx_eq!(
+            level.polarizability(880e-9, 0.5, Some(&linear), &axis)
+                / level.polarizability(880e-9, -0.5, Some(&linear), &axis),
+            1.0,
+            1e-9
+        );
+
+        // Circular light does, symmetrically about the scalar part.
+        let (scalar, _, _) = level.polarizability_components(880e-9);
+        let plus = level.polarizability(880e-9, 0.5, Some(&circular), &axis);
+        let minus = level.polarizability(880e-9, -0.5, Some(&circular), &axis);
+        assert!((plus - minus).abs() > 1e-3 * scalar.abs());
+        assert_approx_eq!((plus + minus) / (2.0 * scalar), 1.0, 1e-9);
+
+        // Between the D1 and D2 lines, σ+ light only couples m_J = +1/2 to the red-detuned D2 line,
+        // so this state is more strongly trapped.
+        let tune_out = level.polarizability(790e-9, 0.5, Some(&circular), &axis);
+        assert!(tune_out > level.polarizability(790e-9, -0.5, Some(&circular), &axis));
+    }
+
+    #[test]
+    fn test_calculate_dipole_prefactors_system() {
+        let mut test_world = World::new();
+        test_world.register::<DipoleLight>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<Polarization>();
+        test_world.register::<StatePolarizability>();
+        test_world.register::<MagneticFieldSampler>();
+        test_world.register::<DipolePrefactors<2>>();
+
+        test_world
+            .create_entity()
+            .with(DipoleLight {
+                wavelength: 1064e-9,
+            })
+            .with(LaserIndex {
+                index: 1,
+                initiated: true,
+            })
+            .with(Polarization::linear(Vector3::x()))
+            .build();
+
+        let level = Arc::new(rubidium87_ground_level());
+        let atom = test_world
+            .create_entity()
+            .with(StatePolarizability {
+                level: level.clone(),
+                m_j: 0.5,
+            })
+            .with(MagneticFieldSampler::tesla(Vector3::new(0.0, 0.0, 1e-4)))
+            .with(DipolePrefactors::<2>::default())
+            .build();
+
+        let mut system = CalculateDipolePrefactorsSystem::<2>;
+        system.run_now(&test_world);
+        test_world.maintain();
+
+        let prefactors = test_world.read_storage::<DipolePrefactors<2>>();
+        let contents = prefactors.get(atom).unwrap().contents;
+        assert_eq!(contents[0], 0.0);
+        assert_approx_eq!(
+            contents[1] / level.polarizability_components(1064e-9).0,
+            1.0,
+            1e-9
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Off-resonant photon scattering from `DipoleLight` beams.
+//!
+//! The Rayleigh scattering rate follows from the same polarizability that produces the dipole force.
+//! For an atom with polarizability prefactor `p` (see `Polarizability`) in light of angular frequency
+//! `omega` and intensity `I`, the scattering rate is `2 omega^3 p^2 I / (3 pi hbar c^2)`. Each
+//! scattered photon gives a recoil kick along the beam on absorption, and an isotropic kick on
+//! emission, which heats atoms in the trap. A fraction of the scattering events may be Raman
+//! events that change the internal state, which removes the atom from the cooling cycle by marking it `Dark`.
+
+use super::polarizability::DipolePrefactors;
+use super::{DipoleLight, Polarizability};
+use crate::atom::Force;
+use crate::constant;
+use crate::integrator::Timestep;
+use crate::laser::gaussian::GaussianBeam;
+use crate::laser::index::LaserIndex;
+use crate::laser::intensity::LaserIntensitySamplers;
+use crate::laser_cooling::repump::Dark;
+use nalgebra::Vector3;
+use rand::Rng;
+use rand_distr::{Distribution, Normal, Poisson, UnitSphere};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+/// Above this number of photons, the emission recoil is drawn from a normal distribution instead of an explicit random walk.
+const EXPLICIT_EMISSION_THRESHOLD: u64 = 20;
+
+/// An atom component which enables photon scattering from `DipoleLight` beams.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct DipoleScattering {
+    /// Chance in the range [0,1] that a scattering event is a Raman event, which changes the internal state of the atom.
+    pub raman_fraction: f64,
+}
+impl Component for DipoleScattering {
+    type Storage = VecStorage<Self>;
+}
+
+/// Calculates the photon scattering rate per unit intensity, in units of 1/s per W/m^2.
+///
+/// # Arguments
+///
+/// `prefactor`: polarizability prefactor of the atom, see `Polarizability`.
+///
+/// `wavelength`: wavelength of the light, in m.
+pub fn scattering_rate_per_intensity(prefactor: f64, wavelength: f64) -> f64 {
+    let omega = 2.0 * constant::PI * constant::C / wavelength;
+    2.0 * omega.powi(3) * prefactor.powi(2)
+        / (3.0 * constant::PI * constant::HBAR * constant::C.powi(2))
+}
+
+/// Draws the number of photons scattered from each dipole beam and applies the recoil to the atoms.
+///
+/// Atoms which undergo a Raman event are marked as `Dark`. Atoms which are already `Dark` still scatter light.
+pub struct DipoleScatteringSystem<const N: usize>;
+impl<'a, const N: usize> System<'a> for DipoleScatteringSystem<N> {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, DipoleLight>,
+        ReadStorage<'a, LaserIndex>,
+        ReadStorage<'a, GaussianBeam>,
+        ReadStorage<'a, Polarizability>,
+        ReadStorage<'a, DipolePrefactors<N>>,
+        ReadStorage<'a, LaserIntensitySamplers<N>>,
+        ReadStorage<'a, DipoleScattering>,
+        ReadStorage<'a, Dark>,
+        WriteStorage<'a, Force>,
+        ReadExpect<'a, Timestep>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            dipole_light,
+            indices,
+            gaussian_beams,
+            polarizabilities,
+            prefactors,
+            intensity_samplers,
+            scattering,
+            darks,
+            mut forces,
+            timestep,
+            updater,
+        ): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        let beams: Vec<(usize, f64, Vector3<f64>)> = (&dipole_light, &indices, &gaussian_beams)
+            .join()
+            .map(|(light, index, beam)| (index.index, light.wavelength, beam.direction.normalize()))
+            .collect();
+
+        (
+            &entities,
+            polarizabilities.maybe(),
+            prefactors.maybe(),
+            &intensity_samplers,
+            &scattering,
+            darks.maybe(),
+            &mut forces,
+        )
+            .par_join()
+            .for_each(
+                |(entity, polarizability, prefactors, intensities, scattering, dark, force)| {
+                    let mut rng = rand::thread_rng();
+                    let mut raman = false;
+                    for (index, wavelength, direction) in beams.iter() {
+                        let prefactor = match (prefactors, polarizability) {
+                            (Some(prefactors), _) => prefactors.contents[*index],
+                            (None, Some(polarizability)) => polarizability.prefactor,
+                            (None, None) => continue,
+                        };
+                        let expected = scattering_rate_per_intensity(prefactor, *wavelength)
+                            * intensities.contents[*index].intensity
+                            * timestep.delta;
+                        if !expected.is_finite() || expected <= 0.0 {
+                            continue;
+                        }
+                        let scattered = match Poisson::new(expected) {
+                            Ok(poisson) => poisson.sample(&mut rng) as u64,
+                            Err(_) => continue,
+                        };
+                        if scattered == 0 {
+                            continue;
+                        }
+
+                        let force_one_kick =
+                            constant::HBAR * 2.0 * constant::PI / wavelength / timestep.delta;
+                        force.force += scattered as f64 * force_one_kick * direction;
+                        if scattered > EXPLICIT_EMISSION_THRESHOLD {
+                            let normal = Normal::new(
+                                0.0,
+                                (scattered as f64 * force_one_kick.powi(2) / 3.0).sqrt(),
+                            )
+                            .unwrap();
+                            force.force += Vector3::new(
+                                normal.sample(&mut rng),
+                                normal.sample(&mut rng),
+                                normal.sample(&mut rng),
+                            );
+                        } else {
+                            for _ in 0..scattered {
+                                let v: [f64; 3] = UnitSphere.sample(&mut rng);
+                                force.force += force_one_kick * Vector3::new(v[0], v[1], v[2]);
+                            }
+                        }
+
+                        let survival = (1.0 - scattering.raman_fraction).powf(scattered as f64);
+                        if rng.gen_range(0.0..1.0) >= survival {
+                            raman = true;
+                        }
+                    }
+                    if raman && dark.is_none() {
+                        updater.insert(entity, Dark {});
+                    }
+                },
+            );
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_scattering_rate_matches_two_level() {
+        // Compare to the two-level expression, see Grimm et al., Adv. At. Mol. Opt. Phys. 42, 95 (2000).
+        let linewidth = 32e6;
+        let transition_wavelength = 461e-9;
+        let wavelength = 1064e-9;
+        let polarizability =
+            Polarizability::calculate_for(wavelength, transition_wavelength, linewidth);
+
+        let omega_0 = 2.0 * constant::PI * constant::C / transition_wavelength;
+        let omega = 2.0 * constant::PI * constant::C / wavelength;
+        let gamma = 2.0 * constant::PI * linewidth;
+        let expected = 3.0 * constant::PI * constant::C.powi(2)
+            / (2.0 * constant::HBAR * omega_0.powi(3))
+            * (omega / omega_0).powi(3)
+            * (gamma / (omega_0 - omega) + gamma / (omega_0 + omega)).powi(2);
+
+        assert_approx_eq!(
+            scattering_rate_per_intensity(polarizability.prefactor, wavelength) / expected,
+            1.0,
+            1e-9
+        );
+    }
+
+    #[test]
+    fn test_dipole_scattering_system() {
+        let mut test_world = World::new();
+        test_world.register::<DipoleLight>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Polarizability>();
+        test_world.register::<DipolePrefactors<1>>();
+        test_world.register::<LaserIntensitySamplers<1>>();
+        test_world.register::<DipoleScattering>();
+        test_world.register::<Force>();
+        test_world.register::<Dark>();
+
+        let wavelength = 1064e-9;
+        test_world
+            .create_entity()
+            .with(DipoleLight { wavelength })
+            .with(LaserIndex {
+                index: 0,
+                initiated: true,
+            })
+            .with(GaussianBeam {
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius: 1.0,
+                power: 1.0,
+                direction: Vector3::x(),
+                rayleigh_range: f64::INFINITY,
+                ellipticity: 0.0,
+            })
+            .build();
+
+        let dt = 1.0;
+        test_world.insert(Timestep { delta: dt });
+        let polarizability = Polarizability::calculate_for(wavelength, 461e-9, 32e6);
+        let intensity = 1.0e14;
+        let atom = test_world
+            .create_entity()
+            .with(polarizability)
+            .with(LaserIntensitySamplers {
+                contents: [crate::laser::intensity::LaserIntensitySampler { intensity }],
+            })
+            .with(DipoleScattering {
+                raman_fraction: 1.0,
+            })
+            .with(Force::new())
+            .build();
+
+        let mut system = DipoleScatteringSystem::<1>;
+        system.run_now(&test_world);
+        test_world.maintain();
+
+        // Many photons are scattered, so the absorption recoil along x dominates.
+        let expected_photons =
+            scattering_rate_per_intensity(polarizability.prefactor, wavelength) * intensity * dt;
+        assert!(expected_photons > 1e3);
+        let kick = constant::HBAR * 2.0 * constant::PI / wavelength / dt;
+        let force = test_world.read_storage::<Force>().get(atom).unwrap().force;
+        assert_approx_eq!(force[0] / (expected_photons * kick), 1.0, 0.2);
+
+        // Every event is a Raman event, so the atom must be dark.
+        assert!(test_world.read_storage::<Dark>().get(atom).is_some());
+    }
+
+    #[test]
+    fn test_unsampled_intensity_does_not_scatter() {
+        let mut test_world = World::new();
+        test_world.register::<DipoleLight>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Polarizability>();
+        test_world.register::<DipolePrefactors<1>>();
+        test_world.register::<LaserIntensitySamplers<1>>();
+        test_world.register::<DipoleScattering>();
+        test_world.register::<Force>();
+        test_world.register::<Dark>();
+
+        let wavelength = 1064e-9;
+        test_world
+            .create_entity()
+            .with(DipoleLight { wavelength })
+            .with(LaserIndex {
+                index: 0,
+                initiated: true,
+            })
+            .with(GaussianBeam {
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius: 1.0,
+                power: 1.0,
+                direction: Vector3::x(),
+                rayleigh_range: f64::INFINITY,
+                ellipticity: 0.0,
+            })
+            .build();
+        test_world.insert(Timestep { delta: 1.0 });
+
+        // The default intensity sampler is NaN until the intensity has been calculated.
+        let atom = test_world
+            .create_entity()
+            .with(Polarizability::calculate_for(wavelength, 461e-9, 32e6))
+            .with(LaserIntensitySamplers {
+                contents: [crate::laser::intensity::LaserIntensitySampler::default()],
+            })
+            .with(DipoleScattering {
+                raman_fraction: 1.0,
+            })
+            .with(Force::new())
+            .build();
+
+        DipoleScatteringSystem::<1>.run_now(&test_world);
+        test_world.maintain();
+
+        let force = test_world.read_storage::<Force>().get(atom).unwrap().force;
+        assert_eq!(force, Vector3::zeros());
+        assert!(test_world.read_storage::<Dark>().get(atom).is_none());
+    }
+}
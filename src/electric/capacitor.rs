@@ -0,0 +1,103 @@
+//! Electric field between the plates of a parallel plate capacitor.
+
+extern crate nalgebra;
+use super::{current_time, oscillation_factor, ElectricFieldOscillation, ElectricFieldSampler};
+use crate::atom::Position;
+use crate::integrator::{Step, Timestep};
+use crate::ramp::Lerp;
+use nalgebra::Vector3;
+use specs::prelude::*;
+
+/// A component representing a capacitor of two parallel circular plates.
+///
+/// The field is uniform between the plates and zero outside of them, so fringing fields are neglected.
+/// This is a good approximation when the separation of the plates is small compared to their radius.
+#[derive(Clone, Lerp)]
+pub struct PlateCapacitor {
+    /// Position of the centre of the capacitor, midway between the plates, in m.
+    pub centre: Vector3<f64>,
+    /// Unit vector normal to the plates.
+    pub normal: Vector3<f64>,
+    /// Distance between the plates, in m.
+    pub separation: f64,
+    /// Radius of the plates, in m.
+    pub radius: f64,
+    /// Voltage of the plate at `centre + normal * separation / 2` with respect to the other plate, in V.
+    pub voltage: f64,
+}
+
+impl PlateCapacitor {
+    /// The electric field at the given position, in V/m.
+    pub fn field(&self, pos: &Vector3<f64>) -> Vector3<f64> {
+        let delta = pos - self.centre;
+        let axial = delta.dot(&self.normal);
+        let radial = (delta - axial * self.normal).norm();
+        if axial.abs() > self.separation / 2.0 || radial > self.radius {
+            return Vector3::new(0.0, 0.0, 0.0);
+        }
+        -self.voltage / self.separation * self.normal
+    }
+}
+
+impl Component for PlateCapacitor {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Updates the values of electric field samplers to include the field of `PlateCapacitor`s.
+pub struct PlateCapacitorSystem;
+
+impl<'a> System<'a> for PlateCapacitorSystem {
+    type SystemData = (
+        WriteStorage<'a, ElectricFieldSampler>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, PlateCapacitor>,
+        ReadStorage<'a, ElectricFieldOscillation>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+    fn run(
+        &mut self,
+        (mut samplers, positions, capacitors, oscillations, timestep, step): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        let time = current_time(&timestep, &step);
+        for (capacitor, oscillation) in (&capacitors, oscillations.maybe()).join() {
+            let factor = oscillation_factor(oscillation, time);
+            (&mut samplers, &positions)
+                .par_join()
+                .for_each(|(sampler, pos)| {
+                    sampler.field += factor * capacitor.field(&pos.pos);
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_plate_capacitor_field() {
+        let capacitor = PlateCapacitor {
+            centre: Vector3::new(0.0, 0.0, 1.0e-3),
+            normal: Vector3::z(),
+            separation: 2.0e-3,
+            radius: 5.0e-3,
+            voltage: 10.0,
+        };
+        // The field points from the high voltage plate to the low voltage plate.
+        let inside = capacitor.field(&Vector3::new(1.0e-3, 0.0, 1.5e-3));
+        assert_approx_eq!(inside[2], -5.0e3, 1e-9);
+        assert_eq!(inside[0], 0.0);
+        assert_eq!(
+            capacitor.field(&Vector3::new(0.0, 0.0, 2.5e-3)),
+            Vector3::zeros()
+        );
+        assert_eq!(
+            capacitor.field(&Vector3::new(6.0e-3, 0.0, 1.0e-3)),
+            Vector3::zeros()
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Electric field of a small spherical electrode, such as the tip of a wire or needle.
+
+extern crate nalgebra;
+use super::{current_time, oscillation_factor, ElectricFieldOscillation, ElectricFieldSampler};
+use crate::atom::Position;
+use crate::integrator::{Step, Timestep};
+use crate::ramp::Lerp;
+use nalgebra::{Matrix3, Vector3};
+use specs::prelude::*;
+
+/// A component representing a spherical electrode held at a voltage with respect to distant ground.
+///
+/// Outside of the electrode, the field is that of a point charge `Q = 4π ε0 a V` at its centre, where
+/// `a` is the radius of the electrode. There is no field inside the electrode.
+#[derive(Clone, Lerp)]
+pub struct PointElectrode {
+    /// Position of the centre of the electrode, in m.
+    pub position: Vector3<f64>,
+    /// Radius of the electrode, in m.
+    pub radius: f64,
+    /// Voltage of the electrode, in V.
+    pub voltage: f64,
+}
+
+impl PointElectrode {
+    /// The electric field at the given position, in V/m, and its jacobian, in V/m^2.
+    pub fn field(&self, pos: &Vector3<f64>) -> (Vector3<f64>, Matrix3<f64>) {
+        let delta = pos - self.position;
+        let r = delta.norm();
+        if r < self.radius {
+            return (Vector3::new(0.0, 0.0, 0.0), Matrix3::zeros());
+        }
+        let strength = self.voltage * self.radius;
+        let field = strength * delta / r.powi(3);
+        let jacobian = strength
+            * (Matrix3::identity() / r.powi(3) - 3.0 * delta * delta.transpose() / r.powi(5));
+        (field, jacobian)
+    }
+}
+
+impl Component for PointElectrode {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Updates the values of electric field samplers to include the field of `PointElectrode`s.
+pub struct PointElectrodeSystem;
+
+impl<'a> System<'a> for PointElectrodeSystem {
+    type SystemData = (
+        WriteStorage<'a, ElectricFieldSampler>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, PointElectrode>,
+        ReadStorage<'a, ElectricFieldOscillation>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+    fn run(
+        &mut self,
+        (mut samplers, positions, electrodes, oscillations, timestep, step): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        let time = current_time(&timestep, &step);
+        for (electrode, oscillation) in (&electrodes, oscillations.maybe()).join() {
+            let factor = oscillation_factor(oscillation, time);
+            (&mut samplers, &positions)
+                .par_join()
+                .for_each(|(sampler, pos)| {
+                    let (field, jacobian) = electrode.field(&pos.pos);
+                    sampler.field += factor * field;
+                    sampler.jacobian += factor * jacobian;
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_point_electrode_field() {
+        let electrode = PointElectrode {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            radius: 1.0e-4,
+            voltage: 100.0,
+        };
+        let pos = Vector3::new(1.0e-3, 2.0e-3, -0.5e-3);
+        let (field, jacobian) = electrode.field(&pos);
+        let r = pos.norm();
+        assert_approx_eq!(field.norm(), 100.0 * 1.0e-4 / r.powi(2), 1e-6);
+        assert_approx_eq!(field.normalize().dot(&pos.normalize()), 1.0, 1e-12);
+
+        // Compare the jacobian to a finite difference of the field.
+        let delta = 1.0e-9;
+        for i in 0..3 {
+            let mut displaced = pos;
+            displaced[i] += delta;
+            let derivative = (electrode.field(&displaced).0 - field) / delta;
+            for j in 0..3 {
+                assert_approx_eq!(jacobian[(j, i)], derivative[j], 1.0);
+            }
+        }
+
+        let (inside, _) = electrode.field(&Vector3::new(0.5e-4, 0.0, 0.0));
+        assert_eq!(inside, Vector3::zeros());
+    }
+}
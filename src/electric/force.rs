@@ -0,0 +1,158 @@
+//! Forces on particles in an inhomogeneous electric field.
+//!
+//! A particle with a static polarizability `α` has a quadratic Stark shift of `-α E²/2`, and a polar
+//! particle with a dipole moment `d` oriented by the field has a linear Stark shift of `-d E`. The
+//! force is the negative gradient of the Stark shift, so particles are pulled towards strong fields
+//! when `α` or `d` are positive. Molecules in low-field seeking states are described by a negative `d`.
+
+use super::ElectricFieldSampler;
+use crate::atom::Force;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+/// One atomic unit of polarizability, in SI units of C m^2/V.
+const ATOMIC_UNIT_POLARIZABILITY: f64 = 1.64877727436e-41;
+
+/// One Debye, in SI units of C m.
+const DEBYE: f64 = 3.33564e-30;
+
+/// Component that represents the static (DC) polarizability of an atom.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct StaticPolarizability {
+    /// Polarizability in SI units of C m^2/V.
+    pub polarizability: f64,
+}
+
+impl StaticPolarizability {
+    /// Create a StaticPolarizability from a polarizability in atomic units.
+    pub fn atomic_units(polarizability: f64) -> Self {
+        StaticPolarizability {
+            polarizability: polarizability * ATOMIC_UNIT_POLARIZABILITY,
+        }
+    }
+
+    /// The Stark shift of the energy in a field of the given magnitude, in J.
+    pub fn energy(&self, field: f64) -> f64 {
+        -0.5 * self.polarizability * field.powi(2)
+    }
+}
+
+impl Component for StaticPolarizability {
+    type Storage = VecStorage<Self>;
+}
+
+/// Component that represents the electric dipole moment of a polar particle, oriented by the field.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct ElectricDipole {
+    /// Projection of the dipole moment onto the field, in SI units of C m.
+    pub moment: f64,
+}
+
+impl ElectricDipole {
+    /// Create an ElectricDipole from a dipole moment in Debye.
+    pub fn debye(moment: f64) -> Self {
+        ElectricDipole {
+            moment: moment * DEBYE,
+        }
+    }
+
+    /// The Stark shift of the energy in a field of the given magnitude, in J.
+    pub fn energy(&self, field: f64) -> f64 {
+        -self.moment * field
+    }
+}
+
+impl Component for ElectricDipole {
+    type Storage = VecStorage<Self>;
+}
+
+/// Applies the force from the gradient of the Stark shift to atoms with a `StaticPolarizability`
+/// or an `ElectricDipole`.
+pub struct ApplyElectricForceSystem;
+impl<'a> System<'a> for ApplyElectricForceSystem {
+    type SystemData = (
+        WriteStorage<'a, Force>,
+        ReadStorage<'a, ElectricFieldSampler>,
+        ReadStorage<'a, StaticPolarizability>,
+        ReadStorage<'a, ElectricDipole>,
+    );
+
+    fn run(&mut self, (mut forces, samplers, polarizabilities, dipoles): Self::SystemData) {
+        use rayon::prelude::*;
+
+        (
+            &mut forces,
+            &samplers,
+            polarizabilities.maybe(),
+            dipoles.maybe(),
+        )
+            .par_join()
+            .for_each(|(force, sampler, polarizability, dipole)| {
+                // -dU/dE, where U is the Stark shift.
+                let coefficient = polarizability
+                    .map_or(0.0, |p| p.polarizability * sampler.magnitude)
+                    + dipole.map_or(0.0, |d| d.moment);
+                force.force += coefficient * sampler.gradient;
+            });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use nalgebra::{Matrix3, Vector3};
+
+    #[test]
+    fn test_apply_electric_force_system() {
+        let mut test_world = World::new();
+        test_world.register::<ElectricFieldSampler>();
+        test_world.register::<StaticPolarizability>();
+        test_world.register::<ElectricDipole>();
+        test_world.register::<Force>();
+
+        let sampler = ElectricFieldSampler {
+            field: Vector3::new(0.0, 0.0, 1.0e6),
+            magnitude: 1.0e6,
+            gradient: Vector3::new(0.0, 0.0, 1.0e8),
+            jacobian: Matrix3::zeros(),
+        };
+        let atom = test_world
+            .create_entity()
+            .with(sampler)
+            .with(StaticPolarizability::atomic_units(318.8))
+            .with(Force::new())
+            .build();
+        let molecule = test_world
+            .create_entity()
+            .with(sampler)
+            .with(ElectricDipole::debye(-3.07))
+            .with(Force::new())
+            .build();
+
+        ApplyElectricForceSystem.run_now(&test_world);
+        let forces = test_world.read_storage::<Force>();
+        // Polarizable atoms are high field seekers.
+        assert_approx_eq!(
+            forces.get(atom).unwrap().force[2],
+            318.8 * ATOMIC_UNIT_POLARIZABILITY * 1.0e6 * 1.0e8,
+            1e-30
+        );
+        assert_approx_eq!(
+            forces.get(molecule).unwrap().force[2],
+            -3.07 * DEBYE * 1.0e8,
+            1e-30
+        );
+
+        // The force is the negative gradient of the energy.
+        let polarizability = StaticPolarizability::atomic_units(318.8);
+        let delta = 1.0;
+        let derivative =
+            (polarizability.energy(1.0e6 + delta) - polarizability.energy(1.0e6)) / delta;
+        assert_approx_eq!(
+            -derivative,
+            polarizability.polarizability * 1.0e6,
+            1e-6 * polarizability.polarizability * 1.0e6
+        );
+    }
+}
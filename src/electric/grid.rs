@@ -0,0 +1,201 @@
+//! Define electric fields using grids, for example imported from a finite element calculation.
+
+extern crate nalgebra;
+use super::{current_time, oscillation_factor, ElectricFieldOscillation, ElectricFieldSampler};
+use crate::atom::Position;
+use crate::integrator::{Step, Timestep};
+use nalgebra::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Defines an electric field using a grid-based representation.
+///
+/// The grid uses the same layout as [crate::magnetic::grid::PrecalculatedMagneticFieldGrid]: it is
+/// ordered as a linear array, with elements ordered in priority z,y,x; items with dz=1 are adjacent in memory.
+/// The field is taken from the cell containing the atom, and the jacobian is calculated from the
+/// difference in field between neighbouring cells.
+///
+/// # Fields
+///
+/// `extent_spatial`: Size of the grid, in units of m.
+///
+/// `position`: Position of the grid center, in units of m.
+///
+/// `extent_cells`: Size of the grid in cells, along the (x,y,z) axes.
+///
+/// `grid`: `Vec<Vector3<f64>>` containing the field at each grid cell, in V/m.
+#[derive(Serialize, Deserialize)]
+pub struct PrecalculatedElectricFieldGrid {
+    pub extent_spatial: Vector3<f64>,
+    pub position: Vector3<f64>,
+    pub extent_cells: Vector3<i32>,
+    pub grid: Vec<Vector3<f64>>,
+}
+
+impl PrecalculatedElectricFieldGrid {
+    /// Loads a grid from a JSON file, in the format written by `serde_json`.
+    ///
+    /// Returns an error if the number of grid values does not match `extent_cells`.
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let grid: Self = serde_json::from_str(&contents)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        grid.validate()?;
+        Ok(grid)
+    }
+
+    /// Checks that the grid has a positive number of cells along each axis, and one value for each cell.
+    fn validate(&self) -> io::Result<()> {
+        if self.extent_cells.iter().any(|&cells| cells <= 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Electric field grid must have at least one cell along each axis, found {:?}.",
+                    self.extent_cells
+                ),
+            ));
+        }
+        let cells: usize = self
+            .extent_cells
+            .iter()
+            .map(|&cells| cells as usize)
+            .product();
+        if self.grid.len() != cells {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Electric field grid has {} values, but extent_cells requires {}.",
+                    self.grid.len(),
+                    cells
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The (x,y,z) indices of the cell containing the position, clamped to the grid.
+    fn cell(&self, pos: &Vector3<f64>) -> Vector3<i32> {
+        let delta = pos - (self.position - self.extent_spatial / 2.0);
+        let fraction = delta.component_div(&self.extent_spatial);
+        let mut cell = Vector3::new(0, 0, 0);
+        for i in 0..3 {
+            cell[i] = ((fraction[i] * self.extent_cells[i] as f64) as i32)
+                .max(0)
+                .min(self.extent_cells[i] - 1);
+        }
+        cell
+    }
+
+    fn value(&self, cell: &Vector3<i32>) -> Vector3<f64> {
+        let index = self.extent_cells[2] * (self.extent_cells[1] * cell[0] + cell[1]) + cell[2];
+        self.grid[index as usize]
+    }
+
+    pub fn get_field(&self, pos: &Vector3<f64>) -> Vector3<f64> {
+        self.value(&self.cell(pos))
+    }
+
+    /// The jacobian of the field, calculated by differencing the neighbouring cells of the position.
+    pub fn get_jacobian(&self, pos: &Vector3<f64>) -> Matrix3<f64> {
+        let cell = self.cell(pos);
+        let mut jacobian = Matrix3::zeros();
+        for i in 0..3 {
+            let mut lower = cell;
+            let mut upper = cell;
+            lower[i] = (cell[i] - 1).max(0);
+            upper[i] = (cell[i] + 1).min(self.extent_cells[i] - 1);
+            if upper[i] == lower[i] {
+                continue;
+            }
+            let width = self.extent_spatial[i] / self.extent_cells[i] as f64;
+            let derivative =
+                (self.value(&upper) - self.value(&lower)) / (width * (upper[i] - lower[i]) as f64);
+            jacobian.set_column(i, &derivative);
+        }
+        jacobian
+    }
+}
+
+impl Component for PrecalculatedElectricFieldGrid {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Samples from the `PrecalculatedElectricFieldGrid` at a `Position` and stores
+/// result in `ElectricFieldSampler`
+pub struct SampleElectricGridSystem;
+impl<'a> System<'a> for SampleElectricGridSystem {
+    type SystemData = (
+        WriteStorage<'a, ElectricFieldSampler>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, PrecalculatedElectricFieldGrid>,
+        ReadStorage<'a, ElectricFieldOscillation>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+    fn run(
+        &mut self,
+        (mut samplers, positions, grids, oscillations, timestep, step): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        let time = current_time(&timestep, &step);
+        for (grid, oscillation) in (&grids, oscillations.maybe()).join() {
+            let factor = oscillation_factor(oscillation, time);
+            (&mut samplers, &positions)
+                .par_join()
+                .for_each(|(sampler, pos)| {
+                    sampler.field += factor * grid.get_field(&pos.pos);
+                    sampler.jacobian += factor * grid.get_jacobian(&pos.pos);
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_electric_grid() {
+        // A 4x1x1 grid of the field E = (0, 0, 1e4 x) V/m, from x = -2mm to 2mm.
+        let grid = PrecalculatedElectricFieldGrid {
+            extent_spatial: Vector3::new(4.0e-3, 1.0e-3, 1.0e-3),
+            position: Vector3::new(0.0, 0.0, 0.0),
+            extent_cells: Vector3::new(4, 1, 1),
+            grid: (0..4)
+                .map(|i| Vector3::new(0.0, 0.0, 1.0e4 * (i as f64 - 1.5) * 1.0e-3))
+                .collect(),
+        };
+        let pos = Vector3::new(-0.5e-3, 0.0, 0.0);
+        assert_approx_eq!(grid.get_field(&pos)[2], -5.0, 1e-9);
+        let jacobian = grid.get_jacobian(&pos);
+        assert_approx_eq!(jacobian[(2, 0)], 1.0e4, 1e-6);
+        assert_eq!(jacobian[(0, 1)], 0.0);
+
+        // At the edge of the grid, the jacobian is the one-sided difference.
+        let edge = grid.get_jacobian(&Vector3::new(-1.9e-3, 0.0, 0.0));
+        assert_approx_eq!(edge[(2, 0)], 1.0e4, 1e-6);
+    }
+
+    #[test]
+    fn test_malformed_grid_file_is_rejected() {
+        let grid = PrecalculatedElectricFieldGrid {
+            extent_spatial: Vector3::new(4.0e-3, 1.0e-3, 1.0e-3),
+            position: Vector3::new(0.0, 0.0, 0.0),
+            extent_cells: Vector3::new(4, 2, 1),
+            grid: vec![Vector3::zeros(); 4],
+        };
+        let path = std::env::temp_dir().join(format!(
+            "atomecs_malformed_electric_grid_{}.json",
+            std::process::id()
+        ));
+        fs::write(&path, serde_json::to_string(&grid).unwrap()).unwrap();
+        let result = PrecalculatedElectricFieldGrid::from_json_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
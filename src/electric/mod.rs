@@ -0,0 +1,282 @@
+//! Electric fields, Stark shifts and the forces on polarizable or polar particles.
+//!
+//! Electric fields are created by source entities, such as a [uniform::UniformElectricField], a
+//! [capacitor::PlateCapacitor], a [electrode::PointElectrode] or a [grid::PrecalculatedElectricFieldGrid].
+//! The fields of all sources are summed into the `ElectricFieldSampler` of each atom, together with the
+//! jacobian of the field. Sources can be switched or ramped using [crate::ramp], and sources with an
+//! `ElectricFieldOscillation` oscillate in time.
+//!
+//! Atoms with a [force::StaticPolarizability] or an [force::ElectricDipole] are pushed by the gradient
+//! of the field, see [force]. The Stark shift of a laser cooling transition is described by
+//! [crate::laser_cooling::stark].
+
+extern crate nalgebra;
+
+use crate::constant::PI;
+use crate::initiate::NewlyCreated;
+use crate::integrator::{Step, Timestep, INTEGRATE_POSITION_SYSTEM_NAME};
+use crate::laser_cooling::stark::StarkShiftOmitted;
+use crate::simulation::Plugin;
+use nalgebra::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+pub mod capacitor;
+pub mod electrode;
+pub mod force;
+pub mod grid;
+pub mod uniform;
+
+/// A component that stores the electric field at an entity's location.
+#[derive(Copy, Clone)]
+pub struct ElectricFieldSampler {
+    /// Vector representing the electric field components along x,y,z in units of V/m.
+    pub field: Vector3<f64>,
+
+    /// Magnitude of the electric field in units of V/m.
+    pub magnitude: f64,
+
+    /// Local gradient of the magnitude of the electric field in V/m^2.
+    pub gradient: Vector3<f64>,
+
+    /// Local jacobian of the electric field, with column `i` the derivative of the field along axis `i`.
+    pub jacobian: Matrix3<f64>,
+}
+
+impl ElectricFieldSampler {
+    pub fn volts_per_metre(field: Vector3<f64>) -> Self {
+        ElectricFieldSampler {
+            field,
+            magnitude: field.norm(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for ElectricFieldSampler {
+    fn default() -> Self {
+        ElectricFieldSampler {
+            field: Vector3::new(0.0, 0.0, 0.0),
+            magnitude: 0.0,
+            gradient: Vector3::new(0.0, 0.0, 0.0),
+            jacobian: Matrix3::zeros(),
+        }
+    }
+}
+
+impl Component for ElectricFieldSampler {
+    type Storage = VecStorage<Self>;
+}
+
+/// A component that makes the field of an electric field source oscillate in time.
+///
+/// The field of the source is multiplied by `cos(2π f t + φ)`.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct ElectricFieldOscillation {
+    /// Frequency of the oscillation, in Hz.
+    pub frequency: f64,
+    /// Phase of the oscillation at `t = 0`, in rad.
+    pub phase: f64,
+}
+
+impl ElectricFieldOscillation {
+    /// The factor multiplying the field of the source at the given time, in s.
+    pub fn factor(&self, time: f64) -> f64 {
+        (2.0 * PI * self.frequency * time + self.phase).cos()
+    }
+}
+
+impl Component for ElectricFieldOscillation {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// The factor multiplying the field of a source, which is one for sources that do not oscillate.
+fn oscillation_factor(oscillation: Option<&ElectricFieldOscillation>, time: f64) -> f64 {
+    oscillation.map_or(1.0, |oscillation| oscillation.factor(time))
+}
+
+/// The current time of the simulation, in s.
+fn current_time(timestep: &Timestep, step: &Step) -> f64 {
+    timestep.delta * step.n as f64
+}
+
+/// System that clears the electric field samplers each frame.
+pub struct ClearElectricFieldSamplerSystem;
+
+impl<'a> System<'a> for ClearElectricFieldSamplerSystem {
+    type SystemData = WriteStorage<'a, ElectricFieldSampler>;
+    fn run(&mut self, mut samplers: Self::SystemData) {
+        use rayon::prelude::*;
+
+        (&mut samplers).par_join().for_each(|sampler| {
+            *sampler = ElectricFieldSampler::default();
+        });
+    }
+}
+
+/// System that calculates the magnitude of the electric field and the gradient of the magnitude.
+///
+/// This system runs after all other electric field systems. The gradient is zero where the field vanishes.
+pub struct CalculateElectricFieldMagnitudeSystem;
+
+impl<'a> System<'a> for CalculateElectricFieldMagnitudeSystem {
+    type SystemData = WriteStorage<'a, ElectricFieldSampler>;
+    fn run(&mut self, mut samplers: Self::SystemData) {
+        use rayon::prelude::*;
+
+        (&mut samplers).par_join().for_each(|sampler| {
+            sampler.magnitude = sampler.field.norm();
+            if sampler.magnitude > 0.0 {
+                let jacobian = sampler.jacobian;
+                let field = sampler.field;
+                let magnitude = sampler.magnitude;
+                for i in 0..3 {
+                    sampler.gradient[i] = field.dot(&jacobian.column(i)) / magnitude;
+                }
+            } else {
+                sampler.gradient = Vector3::new(0.0, 0.0, 0.0);
+            }
+        });
+    }
+}
+
+/// Attaches the ElectricFieldSampler component to newly created atoms.
+pub struct AttachElectricFieldSamplersToNewlyCreatedAtomsSystem;
+
+impl<'a> System<'a> for AttachElectricFieldSamplersToNewlyCreatedAtomsSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, NewlyCreated>,
+        Read<'a, LazyUpdate>,
+    );
+    fn run(&mut self, (ent, newly_created, updater): Self::SystemData) {
+        for (ent, _nc) in (&ent, &newly_created).join() {
+            updater.insert(ent, ElectricFieldSampler::default());
+        }
+    }
+}
+
+/// Adds the systems required by electric fields to the dispatcher.
+///
+/// #Arguments
+///
+/// `builder`: the dispatch builder to modify
+///
+/// `deps`: any dependencies that must be completed before the systems run.
+fn add_systems_to_dispatch(builder: &mut DispatcherBuilder<'static, 'static>, deps: &[&str]) {
+    builder.add(ClearElectricFieldSamplerSystem, "electric_clear", deps);
+    builder.add(
+        uniform::UniformElectricFieldSystem,
+        "electric_uniform",
+        &["electric_clear"],
+    );
+    builder.add(
+        capacitor::PlateCapacitorSystem,
+        "electric_capacitor",
+        &["electric_uniform", INTEGRATE_POSITION_SYSTEM_NAME],
+    );
+    builder.add(
+        electrode::PointElectrodeSystem,
+        "electric_electrode",
+        &["electric_capacitor"],
+    );
+    builder.add(
+        grid::SampleElectricGridSystem,
+        "electric_grid",
+        &["electric_electrode"],
+    );
+    builder.add(
+        CalculateElectricFieldMagnitudeSystem,
+        "electric_magnitude",
+        &["electric_grid"],
+    );
+    builder.add(
+        force::ApplyElectricForceSystem,
+        "electric_force",
+        &["electric_magnitude", "clear"],
+    );
+    builder.add(
+        AttachElectricFieldSamplersToNewlyCreatedAtomsSystem,
+        "add_electric_field_samplers",
+        &[],
+    );
+}
+
+/// Registers resources required by electric fields to the ecs world.
+fn register_components(world: &mut World) {
+    world.register::<ElectricFieldSampler>();
+    world.register::<ElectricFieldOscillation>();
+    world.register::<uniform::UniformElectricField>();
+    world.register::<capacitor::PlateCapacitor>();
+    world.register::<electrode::PointElectrode>();
+    world.register::<grid::PrecalculatedElectricFieldGrid>();
+    world.register::<force::StaticPolarizability>();
+    world.register::<force::ElectricDipole>();
+}
+
+/// A plugin responsible for calculating electric fields and the forces they exert.
+///
+/// See the [crate::electric] module for more information.
+///
+/// To include the Stark shift of laser cooling transitions, this plugin must be added before any
+/// [crate::laser_cooling::LaserCoolingPlugin]; it panics otherwise.
+pub struct ElectricFieldPlugin;
+impl Plugin for ElectricFieldPlugin {
+    fn build(&self, builder: &mut crate::simulation::SimulationBuilder) {
+        if let Some(omitted) = builder.world.try_fetch::<StarkShiftOmitted>() {
+            panic!(
+                "The ElectricFieldPlugin must be added before any LaserCoolingPlugin, otherwise the Stark shift of {} is not calculated.",
+                omitted.transitions.join(", ")
+            );
+        }
+        add_systems_to_dispatch(&mut builder.dispatcher_builder, &[]);
+        register_components(&mut builder.world);
+    }
+
+    fn deps(&self) -> Vec<Box<dyn Plugin>> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_field_magnitude_gradient() {
+        let mut test_world = World::new();
+        test_world.register::<ElectricFieldSampler>();
+
+        // E = (x, 0, 0) * 1e3 V/m^2, sampled at x = 2.
+        let atom = test_world
+            .create_entity()
+            .with(ElectricFieldSampler {
+                field: Vector3::new(2.0e3, 0.0, 0.0),
+                jacobian: Matrix3::new(1.0e3, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+                ..Default::default()
+            })
+            .build();
+        let zero = test_world
+            .create_entity()
+            .with(ElectricFieldSampler::default())
+            .build();
+
+        CalculateElectricFieldMagnitudeSystem.run_now(&test_world);
+        let samplers = test_world.read_storage::<ElectricFieldSampler>();
+        let sampler = samplers.get(atom).unwrap();
+        assert_approx_eq!(sampler.magnitude, 2.0e3, 1e-9);
+        assert_approx_eq!(sampler.gradient[0], 1.0e3, 1e-9);
+        assert_eq!(samplers.get(zero).unwrap().gradient, Vector3::zeros());
+    }
+
+    #[test]
+    fn test_oscillation_factor() {
+        let oscillation = ElectricFieldOscillation {
+            frequency: 1.0e3,
+            phase: 0.0,
+        };
+        assert_approx_eq!(oscillation_factor(Some(&oscillation), 0.5e-3), -1.0, 1e-12);
+        assert_eq!(oscillation_factor(None, 0.5e-3), 1.0);
+    }
+}
@@ -0,0 +1,102 @@
+//! Uniform electric fields
+
+extern crate nalgebra;
+use super::{current_time, oscillation_factor, ElectricFieldOscillation, ElectricFieldSampler};
+use crate::integrator::{Step, Timestep};
+use crate::ramp::Lerp;
+use nalgebra::Vector3;
+use specs::prelude::*;
+
+/// A component representing a uniform electric field, of the form `E = [ E_x, E_y, E_z ]`
+#[derive(Clone, Lerp)]
+pub struct UniformElectricField {
+    /// Vector field components with respect to the x,y,z cartesian axes, in units of V/m.
+    pub field: Vector3<f64>,
+}
+
+impl Component for UniformElectricField {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl UniformElectricField {
+    /// Create a UniformElectricField with components specified in units of V/cm.
+    pub fn volts_per_cm(components: Vector3<f64>) -> UniformElectricField {
+        UniformElectricField {
+            field: components * 1.0e2,
+        }
+    }
+
+    /// Create a UniformElectricField with components specified in units of V/m.
+    pub fn volts_per_metre(components: Vector3<f64>) -> UniformElectricField {
+        UniformElectricField { field: components }
+    }
+}
+
+/// Updates the values of electric field samplers to include uniform electric fields in the world.
+pub struct UniformElectricFieldSystem;
+
+impl<'a> System<'a> for UniformElectricFieldSystem {
+    type SystemData = (
+        WriteStorage<'a, ElectricFieldSampler>,
+        ReadStorage<'a, UniformElectricField>,
+        ReadStorage<'a, ElectricFieldOscillation>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+    fn run(&mut self, (mut samplers, fields, oscillations, timestep, step): Self::SystemData) {
+        use rayon::prelude::*;
+
+        let time = current_time(&timestep, &step);
+        for (field, oscillation) in (&fields, oscillations.maybe()).join() {
+            let field = field.field * oscillation_factor(oscillation, time);
+            (&mut samplers).par_join().for_each(|sampler| {
+                sampler.field += field;
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::electric::ElectricFieldOscillation;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_uniform_electric_field() {
+        let mut test_world = World::new();
+        test_world.register::<ElectricFieldSampler>();
+        test_world.register::<UniformElectricField>();
+        test_world.register::<ElectricFieldOscillation>();
+        test_world.insert(Timestep { delta: 1.0e-6 });
+        test_world.insert(Step { n: 250 });
+
+        test_world
+            .create_entity()
+            .with(UniformElectricField::volts_per_cm(Vector3::new(
+                1.0, 0.0, 0.0,
+            )))
+            .build();
+        // A 1kHz field, a quarter of a period after its maximum.
+        test_world
+            .create_entity()
+            .with(UniformElectricField::volts_per_metre(Vector3::new(
+                0.0, 50.0, 0.0,
+            )))
+            .with(ElectricFieldOscillation {
+                frequency: 1.0e3,
+                phase: 0.0,
+            })
+            .build();
+        let atom = test_world
+            .create_entity()
+            .with(ElectricFieldSampler::default())
+            .build();
+
+        UniformElectricFieldSystem.run_now(&test_world);
+        let samplers = test_world.read_storage::<ElectricFieldSampler>();
+        let field = samplers.get(atom).unwrap().field;
+        assert_approx_eq!(field[0], 100.0, 1e-9);
+        assert_approx_eq!(field[1], 0.0, 1e-9);
+    }
+}
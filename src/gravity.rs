@@ -1,15 +1,65 @@
-//! Implements the force of gravity.
+//! Implements the force of gravity, and the fictitious forces of non-inertial reference frames.
+//!
+//! Gravity is applied when either the [ApplyGravityOption] or the [Gravity] resource is present. The
+//! [Gravity] resource sets the gravitational acceleration, for example to model a tilted apparatus or
+//! microgravity; without it, standard gravity acts along `-z`.
+//!
+//! When the simulation is performed in an accelerating or rotating frame, such as the laboratory frame on
+//! the rotating Earth, the [NonInertialFrame] resource adds the linear, centrifugal, Coriolis and Euler forces.
 
-use crate::atom::{Force, Mass};
+use crate::atom::{Force, Mass, Position, Velocity};
 use crate::constant;
 use crate::integrator::INTEGRATE_POSITION_SYSTEM_NAME;
 use crate::simulation::Plugin;
-use nalgebra::Vector3;
+use nalgebra::{Rotation3, Unit, Vector3};
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 
+/// Angular velocity of the rotation of the Earth, in rad/s.
+const EARTH_ANGULAR_VELOCITY: f64 = 7.2921159e-5;
+
 /// A resource that indicates that the simulation should apply the force of gravity.
 pub struct ApplyGravityOption;
 
+/// A resource that sets the gravitational acceleration.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct Gravity {
+    /// Gravitational acceleration, in m/s^2.
+    pub acceleration: Vector3<f64>,
+}
+
+impl Gravity {
+    /// Standard gravity, along `-z`.
+    pub fn standard() -> Self {
+        Gravity {
+            acceleration: Vector3::new(0., 0., -constant::GC),
+        }
+    }
+
+    /// Residual gravity in a microgravity environment, as a fraction of standard gravity along `-z`.
+    pub fn microgravity(fraction: f64) -> Self {
+        Gravity {
+            acceleration: fraction * Gravity::standard().acceleration,
+        }
+    }
+
+    /// Standard gravity in an apparatus tilted by `angle` (rad) about `axis`.
+    ///
+    /// The simulation axes are fixed to the apparatus, so gravity is rotated by `-angle`.
+    pub fn tilted(axis: Vector3<f64>, angle: f64) -> Self {
+        let rotation = Rotation3::from_axis_angle(&Unit::new_normalize(axis), -angle);
+        Gravity {
+            acceleration: rotation * Gravity::standard().acceleration,
+        }
+    }
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity::standard()
+    }
+}
+
 /// This system adds the gravitational force to all entities with [Mass](struct.Mass.html).
 pub struct ApplyGravitationalForceSystem;
 impl<'a> System<'a> for ApplyGravitationalForceSystem {
@@ -17,25 +67,121 @@ impl<'a> System<'a> for ApplyGravitationalForceSystem {
         WriteStorage<'a, Force>,
         ReadStorage<'a, Mass>,
         Option<Read<'a, ApplyGravityOption>>,
+        Option<Read<'a, Gravity>>,
+    );
+
+    fn run(&mut self, (mut force, mass, gravity_option, gravity): Self::SystemData) {
+        use rayon::prelude::*;
+
+        let acceleration = match (gravity, gravity_option) {
+            (Some(gravity), _) => gravity.acceleration,
+            (None, Some(_)) => Gravity::standard().acceleration,
+            (None, None) => return,
+        };
+        (&mut force, &mass)
+            .par_join()
+            .for_each(|(force, mass)| {
+                force.force += mass.value * constant::AMU * acceleration;
+            });
+    }
+}
+
+/// A resource that describes the motion of the simulation frame relative to an inertial frame.
+///
+/// All quantities are expressed in the axes of the simulation frame.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct NonInertialFrame {
+    /// Linear acceleration of the frame, in m/s^2.
+    pub acceleration: Vector3<f64>,
+    /// Angular velocity of the frame, in rad/s.
+    pub angular_velocity: Vector3<f64>,
+    /// Rate of change of the angular velocity, in rad/s^2.
+    pub angular_acceleration: Vector3<f64>,
+    /// Point about which the frame rotates, in m.
+    pub origin: Vector3<f64>,
+}
+
+impl NonInertialFrame {
+    /// A frame rotating with constant angular velocity (rad/s) about the origin.
+    pub fn rotating(angular_velocity: Vector3<f64>) -> Self {
+        NonInertialFrame {
+            angular_velocity,
+            ..Default::default()
+        }
+    }
+
+    /// A frame moving with constant linear acceleration, in m/s^2.
+    pub fn accelerating(acceleration: Vector3<f64>) -> Self {
+        NonInertialFrame {
+            acceleration,
+            ..Default::default()
+        }
+    }
+
+    /// The laboratory frame on the rotating Earth, at a latitude given in rad.
+    ///
+    /// The axes point east (x), north (y) and up (z). The centrifugal force of the Earth's rotation about
+    /// its axis is already included in the measured value of `g`, so only the rotation about the origin is
+    /// considered.
+    pub fn earth(latitude: f64) -> Self {
+        NonInertialFrame::rotating(
+            EARTH_ANGULAR_VELOCITY * Vector3::new(0., latitude.cos(), latitude.sin()),
+        )
+    }
+
+    /// The acceleration of a particle at `position` with `velocity` caused by the motion of the frame.
+    pub fn fictitious_acceleration(
+        &self,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> Vector3<f64> {
+        let r = position - self.origin;
+        let omega = self.angular_velocity;
+        -self.acceleration
+            - omega.cross(&omega.cross(&r))
+            - 2.0 * omega.cross(velocity)
+            - self.angular_acceleration.cross(&r)
+    }
+}
+
+impl Default for NonInertialFrame {
+    fn default() -> Self {
+        NonInertialFrame {
+            acceleration: Vector3::zeros(),
+            angular_velocity: Vector3::zeros(),
+            angular_acceleration: Vector3::zeros(),
+            origin: Vector3::zeros(),
+        }
+    }
+}
+
+/// This system adds the fictitious forces of a [NonInertialFrame] to all entities with [Mass](struct.Mass.html).
+pub struct ApplyFictitiousForceSystem;
+impl<'a> System<'a> for ApplyFictitiousForceSystem {
+    type SystemData = (
+        WriteStorage<'a, Force>,
+        ReadStorage<'a, Mass>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Velocity>,
+        Option<Read<'a, NonInertialFrame>>,
     );
 
-    fn run(&mut self, (mut force, mass, gravity_option): Self::SystemData) {
+    fn run(&mut self, (mut force, mass, positions, velocities, frame): Self::SystemData) {
         use rayon::prelude::*;
 
-        match gravity_option {
-            None => (),
-            Some(_) => {
-                (&mut force, &mass)
-                    .par_join()
-                    .for_each(|(force, mass)| {
-                        force.force += mass.value * constant::AMU * constant::GC * Vector3::new(0., 0., -1.);
-                    });
-            }
+        if let Some(frame) = frame {
+            (&mut force, &mass, &positions, &velocities)
+                .par_join()
+                .for_each(|(force, mass, position, velocity)| {
+                    force.force += mass.value
+                        * constant::AMU
+                        * frame.fictitious_acceleration(&position.pos, &velocity.vel);
+                });
         }
     }
 }
 
-/// This plugin implements the force of gravity.
+/// This plugin implements the force of gravity, and the fictitious forces of non-inertial frames.
 /// 
 /// See also [crate::gravity].
 pub struct GravityPlugin;
@@ -45,7 +191,12 @@ impl Plugin for GravityPlugin {
             ApplyGravitationalForceSystem,
             "add_gravity",
             &["clear", INTEGRATE_POSITION_SYSTEM_NAME],
-        );  
+        );
+        builder.dispatcher_builder.add(
+            ApplyFictitiousForceSystem,
+            "add_fictitious_forces",
+            &["add_gravity"],
+        );
     }
     fn deps(&self) -> Vec::<Box<dyn Plugin>> {
         Vec::new()
@@ -90,4 +241,84 @@ pub mod tests {
             1e-30_f64
         );
     }
+
+    /// Tests that the `Gravity` resource sets the direction and magnitude of gravity.
+    #[test]
+    fn test_configurable_gravity() {
+        let mut test_world = World::new();
+
+        test_world.register::<Mass>();
+        test_world.register::<Force>();
+        test_world.insert(Gravity::tilted(Vector3::new(1.0, 0.0, 0.0), constant::PI / 2.0));
+
+        let atom1 = test_world
+            .create_entity()
+            .with(Mass { value: 1.0 })
+            .with(Force::new())
+            .build();
+        ApplyGravitationalForceSystem.run_now(&test_world);
+        {
+            let force = test_world.read_storage::<Force>().get(atom1).unwrap().force;
+            // Tilting the apparatus about x by 90 degrees brings gravity along -y.
+            assert_approx_eq!(force[1], -constant::AMU * constant::GC, 1e-35_f64);
+            assert_approx_eq!(force[2], 0.0, 1e-35_f64);
+        }
+
+        test_world.insert(Gravity::microgravity(1.0e-6));
+        test_world.write_storage::<Force>().get_mut(atom1).unwrap().force = Vector3::zeros();
+        ApplyGravitationalForceSystem.run_now(&test_world);
+        let force = test_world.read_storage::<Force>().get(atom1).unwrap().force;
+        assert_approx_eq!(
+            force[2],
+            -1.0e-6 * constant::AMU * constant::GC,
+            1e-40_f64
+        );
+    }
+
+    /// Tests the fictitious forces applied by the `ApplyFictitiousForceSystem`
+    #[test]
+    fn test_apply_fictitious_force_system() {
+        let mut test_world = World::new();
+
+        test_world.register::<Mass>();
+        test_world.register::<Force>();
+        test_world.register::<Position>();
+        test_world.register::<Velocity>();
+        let omega = 10.0;
+        test_world.insert(NonInertialFrame {
+            acceleration: Vector3::new(0.0, 0.0, 2.0),
+            ..NonInertialFrame::rotating(Vector3::new(0.0, 0.0, omega))
+        });
+
+        let atom1 = test_world
+            .create_entity()
+            .with(Mass { value: 1.0 })
+            .with(Force::new())
+            .with(Position {
+                pos: Vector3::new(1.0e-3, 0.0, 0.0),
+            })
+            .with(Velocity {
+                vel: Vector3::new(0.0, 1.0, 0.0),
+            })
+            .build();
+        ApplyFictitiousForceSystem.run_now(&test_world);
+        let force = test_world.read_storage::<Force>().get(atom1).unwrap().force / constant::AMU;
+
+        // centrifugal force outwards along x, Coriolis force -2 omega x v along x, and the linear term along -z.
+        assert_approx_eq!(force[0], omega.powi(2) * 1.0e-3 + 2.0 * omega, 1e-9);
+        assert_approx_eq!(force[1], 0.0, 1e-9);
+        assert_approx_eq!(force[2], -2.0, 1e-9);
+    }
+
+    #[test]
+    fn test_earth_rotation() {
+        // At the north pole, the Earth rotates about the vertical.
+        let frame = NonInertialFrame::earth(constant::PI / 2.0);
+        assert_approx_eq!(frame.angular_velocity[2], EARTH_ANGULAR_VELOCITY, 1e-12);
+        // An atom falling at the equator is deflected towards the east.
+        let equator = NonInertialFrame::earth(0.0);
+        let acceleration =
+            equator.fictitious_acceleration(&Vector3::zeros(), &Vector3::new(0.0, 0.0, -1.0));
+        assert!(acceleration[0] > 0.0);
+    }
 }
@@ -0,0 +1,571 @@
+//! Light-pulse atom interferometry with Raman or Bragg pulses.
+//!
+//! Laser entities with an [InterferometerLight] component apply a sequence of short light pulses to the atoms,
+//! such as the beam splitter and mirror pulses of a Mach-Zehnder interferometer. Each pulse couples two momentum
+//! states, which differ by the effective momentum `ħk_eff` of the two-photon (Raman) or multi-photon (Bragg)
+//! transition. The wavefunction of each atom is described by a set of [Arm]s, wavepackets that follow the
+//! simulated trajectory of the atom, displaced by the momentum transferred by the pulses. Each pulse splits every
+//! arm in two, with amplitudes given by the Rabi frequency at the position of the arm.
+//!
+//! The phase of each arm accumulates:
+//!  * the laser phase imprinted by each pulse, calculated from the `GaussianBeam` wavefront at the position of
+//!    the arm, together with the phase, detuning and frequency chirp of the pulse.
+//!  * the propagation phase, the classical action of the arm relative to the simulated trajectory. The
+//!    acceleration of the atom under gravity, magnetic or other forces is taken from the simulated trajectory.
+//!  * the separation phase, from the residual displacement of the arms which are recombined at the output.
+//!
+//! After the last pulse, the [readout::InterferometerOutput] of each atom gives the population of the output
+//! ports, and the phase and contrast of the interferometer.
+//!
+//! Pulses are treated as short compared to the interferometer time, so the laser phase is imprinted at the start
+//! of each pulse, but the duration of each pulse is used to calculate its area and velocity selectivity. All pulses
+//! should share the same effective wavevector.
+
+use crate::atom::{Mass, Position, Velocity};
+use crate::constant::{AMU, HBAR, PI};
+use crate::initiate::NewlyCreated;
+use crate::integrator::{Step, Timestep, INTEGRATE_POSITION_SYSTEM_NAME};
+use crate::laser::coherent::get_gaussian_beam_phase;
+use crate::laser::frame::Frame;
+use crate::laser::gaussian::{get_gaussian_beam_intensity, GaussianBeam};
+use crate::simulation::Plugin;
+use nalgebra::{Complex, Vector3};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+pub mod readout;
+
+/// Arms with a smaller probability than this are discarded after each pulse.
+const MINIMUM_ARM_PROBABILITY: f64 = 1.0e-8;
+
+/// The multi-photon process that transfers momentum to the atom.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LightPulseKind {
+    /// Two-photon Raman transition between hyperfine ground states, transferring two photon momenta.
+    Raman,
+    /// Bragg transition of the given order, transferring `2 * order` photon momenta without changing the internal state.
+    Bragg { order: u32 },
+}
+
+impl LightPulseKind {
+    /// The number of photon momenta transferred by the transition.
+    pub fn photon_momenta(&self) -> f64 {
+        match self {
+            LightPulseKind::Raman => 2.0,
+            LightPulseKind::Bragg { order } => 2.0 * *order as f64,
+        }
+    }
+}
+
+/// A single light pulse of an `InterferometerLight`.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct LightPulse {
+    /// Time at which the pulse starts, in s.
+    pub start: f64,
+    /// Duration of the pulse, in s.
+    pub duration: f64,
+    /// Phase of the pulse, in rad.
+    pub phase: f64,
+}
+
+/// A component that marks a laser beam as driving the light pulses of an atom interferometer.
+///
+/// The beam is assumed to be retro-reflected, so that the effective wavefront is that of the `GaussianBeam`
+/// scaled by the number of photon momenta transferred by each pulse, and the effective wavevector points along
+/// the beam direction.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct InterferometerLight {
+    /// The multi-photon transition driven by the beam.
+    pub kind: LightPulseKind,
+    /// Wavelength of the light, in m.
+    pub wavelength: f64,
+    /// Constant relating the effective Rabi frequency to the intensity of the beam, in rad/s/(W/m^2).
+    pub rabi_coefficient: f64,
+    /// Detuning of the light from the resonance of an atom at rest, in rad/s.
+    pub detuning: f64,
+    /// Rate at which the detuning is swept to follow the Doppler shift of accelerating atoms, in rad/s^2.
+    pub chirp: f64,
+    /// The pulses applied by the beam.
+    pub pulses: Vec<LightPulse>,
+}
+
+impl InterferometerLight {
+    /// Creates the `π/2 - π - π/2` pulse sequence of a Mach-Zehnder interferometer.
+    ///
+    /// # Arguments
+    ///
+    /// `kind`: the transition driven by the pulses.
+    ///
+    /// `wavelength`: wavelength of the light, in m.
+    ///
+    /// `start`: time of the first pulse, in s.
+    ///
+    /// `interrogation_time`: time `T` between successive pulses, in s.
+    ///
+    /// `pi_duration`: duration of the `π` pulse, in s.
+    ///
+    /// `peak_intensity`: intensity at which the pulse areas are correct, in W/m^2.
+    pub fn mach_zehnder(
+        kind: LightPulseKind,
+        wavelength: f64,
+        start: f64,
+        interrogation_time: f64,
+        pi_duration: f64,
+        peak_intensity: f64,
+    ) -> Self {
+        let pulse = |start: f64, duration: f64| LightPulse {
+            start,
+            duration,
+            phase: 0.0,
+        };
+        InterferometerLight {
+            kind,
+            wavelength,
+            rabi_coefficient: PI / (pi_duration * peak_intensity),
+            detuning: 0.0,
+            chirp: 0.0,
+            pulses: vec![
+                pulse(start, pi_duration / 2.0),
+                pulse(start + interrogation_time, pi_duration),
+                pulse(start + 2.0 * interrogation_time, pi_duration / 2.0),
+            ],
+        }
+    }
+
+    /// Magnitude of the effective wavevector, in rad/m.
+    pub fn effective_wavenumber(&self) -> f64 {
+        self.kind.photon_momenta() * 2.0 * PI / self.wavelength
+    }
+
+    /// The time at which the last pulse ends, in s.
+    pub fn end(&self) -> f64 {
+        self.pulses
+            .iter()
+            .map(|pulse| pulse.start + pulse.duration)
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+}
+
+impl Component for InterferometerLight {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// A wavepacket of an atom, following one path through the interferometer.
+#[derive(Clone)]
+pub struct Arm {
+    /// Momentum state of the arm, in units of `ħk_eff`: either 0 or 1.
+    pub momentum: i32,
+    /// Momentum state of the arm after each pulse.
+    pub path: Vec<i32>,
+    /// Complex amplitude of the arm, including its phase.
+    pub amplitude: Complex<f64>,
+    /// Displacement of the arm from the simulated position of the atom, in m.
+    pub offset: Vector3<f64>,
+    /// Velocity of the arm relative to the simulated velocity of the atom, in m/s.
+    pub velocity_offset: Vector3<f64>,
+}
+
+impl Arm {
+    /// Moves the arm for the given duration, accumulating its propagation phase.
+    ///
+    /// The phase advances by the difference between the Lagrangian of the arm and that of the simulated
+    /// trajectory, `m v·δv + m |δv|²/2 + m a·δr`, where `δr` and `δv` are the offsets of the arm and `v` and
+    /// `a` the velocity and acceleration of the atom. A negative duration moves the arm back in time.
+    fn propagate(
+        &mut self,
+        mass: f64,
+        velocity: &Vector3<f64>,
+        acceleration: &Vector3<f64>,
+        duration: f64,
+    ) {
+        let midpoint = self.offset + self.velocity_offset * duration / 2.0;
+        let lagrangian = mass * velocity.dot(&self.velocity_offset)
+            + 0.5 * mass * self.velocity_offset.norm_squared()
+            + mass * acceleration.dot(&midpoint);
+        self.amplitude *= phasor(lagrangian * duration / HBAR);
+        self.offset += self.velocity_offset * duration;
+    }
+}
+
+impl Default for Arm {
+    fn default() -> Self {
+        Arm {
+            momentum: 0,
+            path: Vec::new(),
+            amplitude: Complex::new(1.0, 0.0),
+            offset: Vector3::zeros(),
+            velocity_offset: Vector3::zeros(),
+        }
+    }
+}
+
+/// The state of an atom in the interferometer, described by the amplitudes of its arms.
+#[derive(Clone)]
+pub struct InterferometerState {
+    pub arms: Vec<Arm>,
+    /// Velocity of the atom in the previous step, used to calculate its acceleration.
+    last_velocity: Option<Vector3<f64>>,
+    /// Acceleration of the atom during the current step, in m/s^2.
+    acceleration: Vector3<f64>,
+}
+
+impl Default for InterferometerState {
+    fn default() -> Self {
+        InterferometerState {
+            arms: vec![Arm::default()],
+            last_velocity: None,
+            acceleration: Vector3::zeros(),
+        }
+    }
+}
+
+impl Component for InterferometerState {
+    type Storage = VecStorage<Self>;
+}
+
+/// The complex number of unit magnitude with the given argument, in rad.
+pub(crate) fn phasor(angle: f64) -> Complex<f64> {
+    Complex::new(angle.cos(), angle.sin())
+}
+
+/// The two-level propagator of a pulse, for the given Rabi frequency, detuning (both rad/s) and duration (s).
+///
+/// Returns the amplitudes `(U00, U01, U10, U11)` without the laser phase, where state 0 is the lower momentum state.
+fn pulse_propagator(
+    rabi: f64,
+    detuning: f64,
+    duration: f64,
+) -> (Complex<f64>, Complex<f64>, Complex<f64>, Complex<f64>) {
+    let i = Complex::new(0.0, 1.0);
+    let generalised = (rabi.powi(2) + detuning.powi(2)).sqrt();
+    if generalised == 0.0 {
+        let one = Complex::new(1.0, 0.0);
+        return (one, Complex::new(0.0, 0.0), Complex::new(0.0, 0.0), one);
+    }
+    let angle = generalised * duration / 2.0;
+    let (sin, cos) = angle.sin_cos();
+    let global = phasor(detuning * duration / 2.0);
+    let diagonal = cos - i * detuning / generalised * sin;
+    let off_diagonal = -i * rabi / generalised * sin;
+    (
+        global * diagonal,
+        global * off_diagonal,
+        global * off_diagonal,
+        global * diagonal.conj(),
+    )
+}
+
+/// Attaches an `InterferometerState` to newly created atoms.
+pub struct AttachInterferometerStatesSystem;
+impl<'a> System<'a> for AttachInterferometerStatesSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, NewlyCreated>,
+        ReadStorage<'a, Mass>,
+        Read<'a, LazyUpdate>,
+    );
+    fn run(&mut self, (ent, newly_created, masses, updater): Self::SystemData) {
+        for (ent, _, _) in (&ent, &newly_created, &masses).join() {
+            updater.insert(ent, InterferometerState::default());
+        }
+    }
+}
+
+/// Moves the arms of each atom along with the atom, and accumulates their propagation phase.
+///
+/// The acceleration of each atom is calculated from the change in its velocity over the previous step.
+pub struct PropagateArmsSystem;
+impl<'a> System<'a> for PropagateArmsSystem {
+    type SystemData = (
+        ReadStorage<'a, Velocity>,
+        ReadStorage<'a, Mass>,
+        WriteStorage<'a, InterferometerState>,
+        ReadExpect<'a, Timestep>,
+    );
+    fn run(&mut self, (velocities, masses, mut states, timestep): Self::SystemData) {
+        use rayon::prelude::*;
+
+        let dt = timestep.delta;
+        (&velocities, &masses, &mut states)
+            .par_join()
+            .for_each(|(velocity, mass, state)| {
+                let acceleration = state
+                    .last_velocity
+                    .map_or(Vector3::zeros(), |last| (velocity.vel - last) / dt);
+                state.last_velocity = Some(velocity.vel);
+                state.acceleration = acceleration;
+                for arm in state.arms.iter_mut() {
+                    arm.propagate(mass.value * AMU, &velocity.vel, &acceleration, dt);
+                }
+            });
+    }
+}
+
+/// Applies the light pulses of `InterferometerLight`s which start during the current step.
+pub struct ApplyLightPulsesSystem;
+impl<'a> System<'a> for ApplyLightPulsesSystem {
+    type SystemData = (
+        ReadStorage<'a, InterferometerLight>,
+        ReadStorage<'a, GaussianBeam>,
+        ReadStorage<'a, Frame>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Velocity>,
+        ReadStorage<'a, Mass>,
+        WriteStorage<'a, InterferometerState>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+    fn run(
+        &mut self,
+        (lights, beams, frames, positions, velocities, masses, mut states, timestep, step): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        // Both ends of the step are calculated the same way, so that no pulse is applied twice.
+        let time = step.n as f64 * timestep.delta;
+        let next = (step.n + 1) as f64 * timestep.delta;
+        for (light, beam, frame) in (&lights, &beams, frames.maybe()).join() {
+            for pulse in light
+                .pulses
+                .iter()
+                .filter(|pulse| pulse.start >= time && pulse.start < next)
+            {
+                let k_eff = light.effective_wavenumber();
+                let k_vector = k_eff * beam.direction.normalize();
+                let wavenumber = 2.0 * PI / light.wavelength;
+                let laser_phase = |pos: &Vector3<f64>| {
+                    light.kind.photon_momenta() * get_gaussian_beam_phase(beam, wavenumber, pos).0
+                        - 0.5 * light.chirp * pulse.start.powi(2)
+                        - light.detuning * pulse.start
+                        + pulse.phase
+                };
+
+                (&positions, &velocities, &masses, &mut states)
+                    .par_join()
+                    .for_each(|(position, velocity, mass, state)| {
+                        let m = mass.value * AMU;
+                        let recoil = HBAR * k_vector / m;
+                        let acceleration = state.acceleration;
+                        // Positions have already been integrated to the end of the step, so the atom and its
+                        // arms are moved back to the start of the pulse, and forward again afterwards.
+                        let remaining = next - pulse.start;
+                        let atom_position = position.pos - velocity.vel * remaining
+                            + 0.5
+                                * acceleration
+                                * (remaining.powi(2) - 2.0 * remaining * timestep.delta);
+                        let atom_velocity =
+                            velocity.vel + acceleration * (timestep.delta - remaining);
+                        let mut arms = Vec::with_capacity(2 * state.arms.len());
+                        for arm in state.arms.iter() {
+                            let mut arm = arm.clone();
+                            arm.propagate(m, &velocity.vel, &acceleration, -remaining);
+                            let pos = atom_position + arm.offset;
+                            let intensity =
+                                get_gaussian_beam_intensity(beam, &Position { pos }, None, frame);
+                            let rabi = light.rabi_coefficient * intensity;
+                            // The Doppler shift is that of the lower momentum state of the coupled pair.
+                            let lower_velocity =
+                                atom_velocity + arm.velocity_offset - arm.momentum as f64 * recoil;
+                            let detuning = light.detuning + light.chirp * pulse.start
+                                - k_vector.dot(&lower_velocity);
+                            let (u00, u01, u10, u11) =
+                                pulse_propagator(rabi, detuning, pulse.duration);
+                            let imprint = phasor(laser_phase(&pos));
+                            let (stay, transfer) = if arm.momentum == 0 {
+                                (u00, u10 * imprint)
+                            } else {
+                                (u11, u01 * imprint.conj())
+                            };
+
+                            let mut unchanged = arm.clone();
+                            unchanged.amplitude *= stay;
+                            unchanged.path.push(arm.momentum);
+                            let mut transferred = arm.clone();
+                            transferred.amplitude *= transfer;
+                            transferred.momentum = 1 - arm.momentum;
+                            transferred.path.push(transferred.momentum);
+                            transferred.velocity_offset +=
+                                if arm.momentum == 0 { recoil } else { -recoil };
+                            for mut arm in [unchanged, transferred] {
+                                arm.propagate(m, &velocity.vel, &acceleration, remaining);
+                                arms.push(arm);
+                            }
+                        }
+                        arms.retain(|arm| arm.amplitude.norm_sqr() > MINIMUM_ARM_PROBABILITY);
+                        state.arms = arms;
+                    });
+            }
+        }
+    }
+}
+
+/// This plugin implements light-pulse atom interferometry.
+///
+/// See also [crate::interferometry]
+pub struct InterferometryPlugin;
+impl Plugin for InterferometryPlugin {
+    fn build(&self, builder: &mut crate::simulation::SimulationBuilder) {
+        add_systems_to_dispatch(&mut builder.dispatcher_builder, &[]);
+        register_components(&mut builder.world);
+    }
+    fn deps(&self) -> Vec<Box<dyn Plugin>> {
+        Vec::new()
+    }
+}
+
+/// Adds the systems required by the module to the dispatcher.
+///
+/// #Arguments
+///
+/// `builder`: the dispatch builder to modify
+///
+/// `deps`: any dependencies that must be completed before the systems run.
+fn add_systems_to_dispatch(builder: &mut DispatcherBuilder<'static, 'static>, deps: &[&str]) {
+    builder.add(
+        AttachInterferometerStatesSystem,
+        "attach_interferometer_states",
+        deps,
+    );
+    builder.add(
+        PropagateArmsSystem,
+        "propagate_interferometer_arms",
+        &[INTEGRATE_POSITION_SYSTEM_NAME],
+    );
+    builder.add(
+        ApplyLightPulsesSystem,
+        "apply_light_pulses",
+        &["propagate_interferometer_arms"],
+    );
+    builder.add(
+        readout::InterferometerReadoutSystem,
+        "interferometer_readout",
+        &["apply_light_pulses"],
+    );
+}
+
+fn register_components(world: &mut World) {
+    world.register::<InterferometerLight>();
+    world.register::<InterferometerState>();
+    world.register::<readout::InterferometerOutput>();
+    world.register::<GaussianBeam>();
+    world.register::<Frame>();
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_pulse_propagator() {
+        // A resonant pi pulse transfers all population.
+        let (u00, _, u10, _) = pulse_propagator(1.0e5, 0.0, PI / 1.0e5);
+        assert_approx_eq!(u00.norm_sqr().sqrt(), 0.0, 1e-12);
+        assert_approx_eq!(u10.norm_sqr().sqrt(), 1.0, 1e-12);
+
+        // The propagator is unitary when detuned.
+        let (u00, u01, u10, u11) = pulse_propagator(1.0e5, 3.0e4, 2.0e-5);
+        assert_approx_eq!(u00.norm_sqr() + u10.norm_sqr(), 1.0, 1e-12);
+        assert_approx_eq!((u00 * u01.conj() + u10 * u11.conj()).norm_sqr(), 0.0, 1e-12);
+    }
+
+    #[test]
+    fn test_mach_zehnder_pulse_areas() {
+        let light = InterferometerLight::mach_zehnder(
+            LightPulseKind::Raman,
+            780.0e-9,
+            1.0e-3,
+            10.0e-3,
+            20.0e-6,
+            100.0,
+        );
+        assert_approx_eq!(
+            light.rabi_coefficient * 100.0 * light.pulses[1].duration,
+            PI,
+            1e-12
+        );
+        assert_approx_eq!(light.end(), 21.01e-3, 1e-12);
+        assert_approx_eq!(light.effective_wavenumber(), 4.0 * PI / 780.0e-9, 1e-3);
+    }
+
+    /// Runs a Mach-Zehnder interferometer for a single atom falling under gravity, returning its output.
+    fn run_mach_zehnder(chirp: f64) -> readout::InterferometerOutput {
+        use crate::species::registry::SpeciesRegistry;
+
+        let mut test_world = World::new();
+        test_world.register::<InterferometerLight>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<Position>();
+        test_world.register::<Velocity>();
+        test_world.register::<Mass>();
+        test_world.register::<InterferometerState>();
+        test_world.register::<readout::InterferometerOutput>();
+        let dt = 1.0e-5;
+        test_world.insert(Timestep { delta: dt });
+        test_world.insert(Step { n: 0 });
+
+        let beam = GaussianBeam {
+            intersection: Vector3::zeros(),
+            direction: Vector3::new(0.0, 0.0, -1.0),
+            e_radius: 1.0,
+            power: 1.0,
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        };
+        let peak = get_gaussian_beam_intensity(&beam, &Position::new(), None, None);
+        let mut light = InterferometerLight::mach_zehnder(
+            LightPulseKind::Raman,
+            780.0e-9,
+            1.0e-3,
+            5.0e-3,
+            10.0e-6,
+            peak,
+        );
+        light.chirp = chirp;
+        test_world.create_entity().with(light).with(beam).build();
+
+        let registry = SpeciesRegistry::builtin();
+        let atom = test_world
+            .create_entity()
+            .with(Position::new())
+            .with(Velocity {
+                vel: Vector3::zeros(),
+            })
+            .with(registry.get("Rb87").unwrap().mass())
+            .with(InterferometerState::default())
+            .build();
+
+        let gravity = Vector3::new(0.0, 0.0, -crate::constant::GC);
+        for _ in 0..1200 {
+            {
+                let mut positions = test_world.write_storage::<Position>();
+                let velocity = test_world.read_storage::<Velocity>().get(atom).unwrap().vel;
+                positions.get_mut(atom).unwrap().pos += velocity * dt + 0.5 * gravity * dt * dt;
+            }
+            PropagateArmsSystem.run_now(&test_world);
+            ApplyLightPulsesSystem.run_now(&test_world);
+            readout::InterferometerReadoutSystem.run_now(&test_world);
+            test_world
+                .write_storage::<Velocity>()
+                .get_mut(atom)
+                .unwrap()
+                .vel += gravity * dt;
+            test_world.write_resource::<Step>().n += 1;
+        }
+        let outputs = test_world.read_storage::<readout::InterferometerOutput>();
+        *outputs.get(atom).expect("Interferometer was not read out.")
+    }
+
+    #[test]
+    fn test_mach_zehnder_gravity_phase() {
+        let k_eff = 4.0 * PI / 780.0e-9;
+        let t: f64 = 5.0e-3;
+        // Compensating the Doppler shift with the chirp leaves a phase of (k_eff g - chirp) T^2.
+        let offset = 0.5;
+        let output = run_mach_zehnder(k_eff * crate::constant::GC - offset / t.powi(2));
+        assert_approx_eq!(output.phase(), offset, 1e-3);
+        assert_approx_eq!(output.contrast(), 1.0, 1e-3);
+        assert_approx_eq!(output.population, 0.5 * (1.0 - offset.cos()), 1e-3);
+    }
+}
@@ -0,0 +1,139 @@
+//! Readout of the interferometer phase and contrast after the last light pulse.
+//!
+//! The output port of interest is the upper momentum state. The two arms which reach this port after being
+//! split by the first pulse are recombined to give the interference signal, so that the population of the port
+//! is `P = P0 (1 - C cos Δφ)`, where `Δφ` is the interferometer phase and `C` the contrast. The phase of each arm
+//! is evaluated at the mean position of the two arms, which adds the separation phase of an interferometer that
+//! is not perfectly closed. Any other arms reaching the port, for example from imperfect mirror pulses, are
+//! assumed to be spatially separated and only add to the population.
+
+use super::{phasor, InterferometerLight, InterferometerState};
+use crate::atom::{Mass, Velocity};
+use crate::constant::{AMU, HBAR};
+use crate::integrator::{Step, Timestep};
+use nalgebra::Complex;
+use serde::Serialize;
+use specs::prelude::*;
+
+/// The output of the interferometer for a single atom.
+#[derive(Clone, Copy, Serialize)]
+pub struct InterferometerOutput {
+    /// Probability that the atom is found in the upper momentum port.
+    pub population: f64,
+    /// Combined probability of the two interfering arms which reach the upper port.
+    pub arm_population: f64,
+    /// Interference term `a_u a_l*` between the upper and lower arms, including the separation phase.
+    #[serde(skip)]
+    pub interference: Complex<f64>,
+}
+
+impl InterferometerOutput {
+    /// The interferometer phase, in rad.
+    pub fn phase(&self) -> f64 {
+        (-self.interference.im).atan2(-self.interference.re)
+    }
+
+    /// The fringe contrast, between 0 and 1.
+    pub fn contrast(&self) -> f64 {
+        if self.arm_population > 0.0 {
+            2.0 * self.interference.norm_sqr().sqrt() / self.arm_population
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Component for InterferometerOutput {
+    type Storage = VecStorage<Self>;
+}
+
+/// Calculates the phase and contrast of the interferometer for an ensemble of atoms.
+///
+/// The interference terms of the atoms are summed, so that the contrast is reduced by the spread of
+/// phases across the ensemble. Returns `(phase, contrast)`.
+pub fn ensemble_phase_and_contrast<'a, I>(outputs: I) -> (f64, f64)
+where
+    I: IntoIterator<Item = &'a InterferometerOutput>,
+{
+    let (interference, arm_population) = outputs.into_iter().fold(
+        (Complex::new(0.0, 0.0), 0.0),
+        |(interference, population), output| {
+            (
+                interference + output.interference,
+                population + output.arm_population,
+            )
+        },
+    );
+    let output = InterferometerOutput {
+        population: 0.0,
+        arm_population,
+        interference,
+    };
+    (output.phase(), output.contrast())
+}
+
+/// Calculates the `InterferometerOutput` of each atom once all light pulses have been applied.
+pub struct InterferometerReadoutSystem;
+impl<'a> System<'a> for InterferometerReadoutSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, InterferometerLight>,
+        ReadStorage<'a, InterferometerState>,
+        ReadStorage<'a, Velocity>,
+        ReadStorage<'a, Mass>,
+        WriteStorage<'a, InterferometerOutput>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+    fn run(
+        &mut self,
+        (entities, lights, states, velocities, masses, mut outputs, timestep, step): Self::SystemData,
+    ) {
+        let end = (step.n + 1) as f64 * timestep.delta;
+        let mut pulses = lights
+            .join()
+            .flat_map(|light| light.pulses.iter())
+            .peekable();
+        if pulses.peek().is_none() || pulses.any(|pulse| pulse.start >= end) {
+            return;
+        }
+
+        for (entity, state, velocity, mass) in (&entities, &states, &velocities, &masses).join() {
+            let port = state.arms.iter().filter(|arm| arm.momentum == 1);
+            let strongest = |first: i32| {
+                port.clone()
+                    .filter(|arm| arm.path.first() == Some(&first))
+                    .max_by(|a, b| {
+                        a.amplitude
+                            .norm_sqr()
+                            .partial_cmp(&b.amplitude.norm_sqr())
+                            .unwrap()
+                    })
+            };
+            let total: f64 = port.clone().map(|arm| arm.amplitude.norm_sqr()).sum();
+            let output = match (strongest(1), strongest(0)) {
+                (Some(upper), Some(lower)) => {
+                    let m = mass.value * AMU;
+                    let momenta =
+                        m * (2.0 * velocity.vel + upper.velocity_offset + lower.velocity_offset);
+                    let separation = momenta.dot(&(lower.offset - upper.offset)) / (2.0 * HBAR);
+                    let interference =
+                        upper.amplitude * lower.amplitude.conj() * phasor(separation);
+                    InterferometerOutput {
+                        population: total + 2.0 * interference.re,
+                        arm_population: upper.amplitude.norm_sqr() + lower.amplitude.norm_sqr(),
+                        interference,
+                    }
+                }
+                _ => InterferometerOutput {
+                    population: total,
+                    arm_population: 0.0,
+                    interference: Complex::new(0.0, 0.0),
+                },
+            };
+            outputs
+                .insert(entity, output)
+                .expect("Could not insert interferometer output.");
+        }
+    }
+}
@@ -0,0 +1,495 @@
+//! Coherent summation of the fields of mutually coherent laser beams.
+//!
+//! By default, the intensities of all beams are summed incoherently. Beams that share a
+//! `CoherentBeam` group are instead summed as complex field amplitudes, including their phase
+//! and `Polarization`, so that interfering beams form standing waves and optical lattices.
+//!
+//! Coherent summation is only supported for `DipoleLight` beams, whose scalar potential depends
+//! on the total intensity alone. The total intensity and intensity gradient of each group are
+//! stored in the samplers of the beam in the group with the lowest `LaserIndex`, and the samplers
+//! of the other beams in the group are set to zero. Systems that sum over beams, such as
+//! `ApplyDipoleForceSystem`, then see the correct total.
+
+use super::frame::Frame;
+use super::gaussian::GaussianBeam;
+use super::index::LaserIndex;
+use super::intensity::LaserIntensitySamplers;
+use super::intensity_gradient::LaserIntensityGradientSamplers;
+use super::polarization::Polarization;
+use crate::atom::Position;
+use crate::constant::PI;
+use crate::dipole::DipoleLight;
+use crate::integrator::{Step, Timestep};
+use nalgebra::{Complex, Vector3};
+use specs::prelude::*;
+
+/// A component that marks a laser beam as coherent with all other beams of the same `group`.
+///
+/// The beam must also have a `DipoleLight` component, which defines its wavelength;
+/// `SampleCoherentBeamsSystem` panics on a `CoherentBeam` without one. Beams without a `Polarization` component are treated as linearly polarized
+/// along the x axis of their `Frame`, or of `Frame::default_for_direction` if they have none.
+#[derive(Clone, Copy)]
+pub struct CoherentBeam {
+    /// Beams with equal `group` interfere with each other.
+    pub group: u32,
+    /// Phase of the beam at its `intersection` point, in units of rad.
+    pub phase: f64,
+    /// Frequency offset of the beam relative to the other beams of the group, in units of Hz.
+    ///
+    /// A frequency difference between two counter-propagating beams produces a moving lattice.
+    pub frequency_offset: f64,
+}
+impl Component for CoherentBeam {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// The field of a single beam at a point in space.
+pub struct BeamField {
+    /// Intensity of the beam, in units of W/m^2.
+    pub intensity: f64,
+    /// Intensity gradient of the beam, in units of W/m^3.
+    pub intensity_gradient: Vector3<f64>,
+    /// Phase of the field, in units of rad.
+    pub phase: f64,
+    /// Gradient of the phase, in units of rad/m.
+    pub phase_gradient: Vector3<f64>,
+    /// Normalised polarization vector.
+    pub polarization: Vector3<Complex<f64>>,
+}
+
+/// Calculates the phase of a gaussian beam, and its gradient, at a given position.
+///
+/// The phase includes the plane wave, Gouy and wavefront curvature terms,
+/// relative to the phase at the beam's `intersection` point.
+pub fn get_gaussian_beam_phase(
+    beam: &GaussianBeam,
+    wavenumber: f64,
+    pos: &Vector3<f64>,
+) -> (f64, Vector3<f64>) {
+    let direction = beam.direction.normalize();
+    let relative = pos - beam.intersection;
+    let z = relative.dot(&direction);
+    let rho = relative - z * direction;
+    let rho_squared = rho.norm_squared();
+    if !beam.rayleigh_range.is_finite() {
+        return (wavenumber * z, wavenumber * direction);
+    }
+    let zr = beam.rayleigh_range;
+    let denominator = z * z + zr * zr;
+    let phase =
+        wavenumber * z - (z / zr).atan() + wavenumber * rho_squared * z / (2.0 * denominator);
+    let dphase_dz = wavenumber - zr / denominator
+        + wavenumber * rho_squared / 2.0 * (zr * zr - z * z) / denominator.powi(2);
+    let gradient = dphase_dz * direction + wavenumber * z / denominator * rho;
+    (phase, gradient)
+}
+
+/// Sums the fields of mutually coherent beams, returning the total intensity and its gradient.
+pub fn sum_coherent_fields(fields: &[BeamField]) -> (f64, Vector3<f64>) {
+    let i = Complex::new(0.0, 1.0);
+    let mut field = Vector3::<Complex<f64>>::zeros();
+    // derivative of each field component along each spatial axis.
+    let mut field_derivatives = [Vector3::<Complex<f64>>::zeros(); 3];
+    for beam in fields.iter() {
+        if beam.intensity <= 0.0 {
+            continue;
+        }
+        let amplitude = beam.intensity.sqrt();
+        let phasor = Complex::new(beam.phase.cos(), beam.phase.sin());
+        field += beam.polarization * (phasor * amplitude);
+        for (axis, derivative) in field_derivatives.iter_mut().enumerate() {
+            let scalar = phasor
+                * (beam.intensity_gradient[axis] / (2.0 * amplitude)
+                    + i * amplitude * beam.phase_gradient[axis]);
+            *derivative += beam.polarization * scalar;
+        }
+    }
+    let intensity = field.iter().map(|c| c.norm_sqr()).sum();
+    let mut gradient = Vector3::zeros();
+    for (axis, derivative) in field_derivatives.iter().enumerate() {
+        gradient[axis] = 2.0
+            * field
+                .iter()
+                .zip(derivative.iter())
+                .map(|(e, de)| (e.conj() * de).re)
+                .sum::<f64>();
+    }
+    (intensity, gradient)
+}
+
+/// Replaces the incoherently sampled intensities and gradients of `CoherentBeam`s by the coherent sum over each group.
+pub struct SampleCoherentBeamsSystem<const N: usize>;
+
+impl<'a, const N: usize> System<'a> for SampleCoherentBeamsSystem<N> {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, CoherentBeam>,
+        ReadStorage<'a, LaserIndex>,
+        ReadStorage<'a, GaussianBeam>,
+        ReadStorage<'a, Polarization>,
+        ReadStorage<'a, DipoleLight>,
+        ReadStorage<'a, Frame>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Step>,
+        ReadExpect<'a, Timestep>,
+        WriteStorage<'a, LaserIntensitySamplers<N>>,
+        WriteStorage<'a, LaserIntensityGradientSamplers<N>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            coherent,
+            indices,
+            gaussian,
+            polarizations,
+            dipole,
+            frames,
+            positions,
+            step,
+            timestep,
+            mut intensity_samplers,
+            mut gradient_samplers,
+        ): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        struct CachedBeam {
+            index: usize,
+            beam: GaussianBeam,
+            wavenumber: f64,
+            phase: f64,
+            polarization: Vector3<Complex<f64>>,
+        }
+
+        let time = step.n as f64 * timestep.delta;
+        let mut groups: Vec<(u32, Vec<CachedBeam>)> = Vec::new();
+        if let Some((entity, _, _)) = (&entities, &coherent, !&dipole).join().next() {
+            panic!(
+                "CoherentBeam {:?} has no DipoleLight; coherent summation is only supported for DipoleLight beams.",
+                entity
+            );
+        }
+        for (coherent, index, beam, dipole, polarization, frame) in (
+            &coherent,
+            &indices,
+            &gaussian,
+            &dipole,
+            polarizations.maybe(),
+            frames.maybe(),
+        )
+            .join()
+        {
+            let polarization = polarization.map(|p| p.vector).unwrap_or_else(|| {
+                let frame = frame
+                    .copied()
+                    .unwrap_or_else(|| Frame::default_for_direction(beam.direction));
+                Polarization::linear(frame.x_vector).vector
+            });
+            let cached = CachedBeam {
+                index: index.index,
+                beam: *beam,
+                wavenumber: 2.0 * PI / dipole.wavelength,
+                phase: coherent.phase + 2.0 * PI * coherent.frequency_offset * time,
+                polarization,
+            };
+            match groups
+                .iter_mut()
+                .find(|(group, _)| *group == coherent.group)
+            {
+                Some((_, beams)) => beams.push(cached),
+                None => groups.push((coherent.group, vec![cached])),
+            }
+        }
+        for (_, beams) in groups.iter_mut() {
+            beams.sort_by_key(|b| b.index);
+        }
+
+        (&mut intensity_samplers, &mut gradient_samplers, &positions)
+            .par_join()
+            .for_each(|(intensities, gradients, pos)| {
+                for (_, beams) in groups.iter() {
+                    let fields: Vec<BeamField> = beams
+                        .iter()
+                        .map(|cached| {
+                            let (phase, phase_gradient) =
+                                get_gaussian_beam_phase(&cached.beam, cached.wavenumber, &pos.pos);
+                            BeamField {
+                                intensity: intensities.contents[cached.index].intensity,
+                                intensity_gradient: gradients.contents[cached.index].gradient,
+                                phase: phase + cached.phase,
+                                phase_gradient,
+                                polarization: cached.polarization,
+                            }
+                        })
+                        .collect();
+                    let (intensity, gradient) = sum_coherent_fields(&fields);
+                    for (i, cached) in beams.iter().enumerate() {
+                        let first = i == 0;
+                        intensities.contents[cached.index].intensity =
+                            if first { intensity } else { 0.0 };
+                        gradients.contents[cached.index].gradient =
+                            if first { gradient } else { Vector3::zeros() };
+                    }
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn plane_wave(direction: Vector3<f64>) -> GaussianBeam {
+        GaussianBeam {
+            direction,
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 1e-3,
+            power: 1.0,
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        }
+    }
+
+    fn field_at(
+        beam: &GaussianBeam,
+        wavenumber: f64,
+        pos: Vector3<f64>,
+        polarization: Vector3<f64>,
+    ) -> BeamField {
+        let (phase, phase_gradient) = get_gaussian_beam_phase(beam, wavenumber, &pos);
+        BeamField {
+            intensity: 1.0,
+            intensity_gradient: Vector3::zeros(),
+            phase,
+            phase_gradient,
+            polarization: Polarization::linear(polarization).vector,
+        }
+    }
+
+    #[test]
+    fn test_standing_wave() {
+        let wavelength = 1064e-9;
+        let k = 2.0 * PI / wavelength;
+        let forward = plane_wave(Vector3::z());
+        let backward = plane_wave(-Vector3::z());
+
+        // antinode
+        let pos = Vector3::new(0.0, 0.0, 0.0);
+        let (intensity, _) = sum_coherent_fields(&[
+            field_at(&forward, k, pos, Vector3::x()),
+            field_at(&backward, k, pos, Vector3::x()),
+        ]);
+        assert_approx_eq!(intensity, 4.0);
+
+        // node
+        let pos = Vector3::new(0.0, 0.0, wavelength / 4.0);
+        let (intensity, _) = sum_coherent_fields(&[
+            field_at(&forward, k, pos, Vector3::x()),
+            field_at(&backward, k, pos, Vector3::x()),
+        ]);
+        assert_approx_eq!(intensity, 0.0, 1e-12);
+
+        // I = 4 cos^2(kz), so dI/dz = -4 k sin(2kz)
+        let pos = Vector3::new(0.0, 0.0, wavelength / 16.0);
+        let (intensity, gradient) = sum_coherent_fields(&[
+            field_at(&forward, k, pos, Vector3::x()),
+            field_at(&backward, k, pos, Vector3::x()),
+        ]);
+        assert_approx_eq!(intensity, 4.0 * (k * pos[2]).cos().powi(2));
+        assert_approx_eq!(gradient[2], -4.0 * k * (2.0 * k * pos[2]).sin(), 1e-6 * k);
+
+        // orthogonal polarizations do not interfere
+        let (intensity, gradient) = sum_coherent_fields(&[
+            field_at(&forward, k, pos, Vector3::x()),
+            field_at(&backward, k, pos, Vector3::y()),
+        ]);
+        assert_approx_eq!(intensity, 2.0);
+        assert_approx_eq!(gradient.norm(), 0.0, 1e-6);
+    }
+
+    #[test]
+    fn test_gaussian_phase_gradient() {
+        let beam = GaussianBeam {
+            direction: Vector3::new(1.0, 1.0, 0.0).normalize(),
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 20e-6,
+            power: 1.0,
+            rayleigh_range: 1e-3,
+            ellipticity: 0.0,
+        };
+        let k = 2.0 * PI / 1064e-9;
+        let pos = Vector3::new(3e-4, 1e-4, 2e-5);
+        let (_, gradient) = get_gaussian_beam_phase(&beam, k, &pos);
+        let step = 1e-9;
+        for axis in 0..3 {
+            let mut offset = Vector3::zeros();
+            offset[axis] = step;
+            let (forward, _) = get_gaussian_beam_phase(&beam, k, &(pos + offset));
+            let (backward, _) = get_gaussian_beam_phase(&beam, k, &(pos - offset));
+            assert_approx_eq!(
+                gradient[axis],
+                (forward - backward) / (2.0 * step),
+                1e-3 * k
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_coherent_beams_system() {
+        let mut test_world = World::new();
+        test_world.register::<CoherentBeam>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Polarization>();
+        test_world.register::<DipoleLight>();
+        test_world.register::<Frame>();
+        test_world.register::<Position>();
+        test_world.register::<LaserIntensitySamplers<2>>();
+        test_world.register::<LaserIntensityGradientSamplers<2>>();
+        test_world.insert(Step { n: 0 });
+        test_world.insert(Timestep { delta: 1e-6 });
+
+        let wavelength = 1064e-9;
+        for (index, direction) in [Vector3::z(), -Vector3::z()].iter().enumerate() {
+            test_world
+                .create_entity()
+                .with(plane_wave(*direction))
+                .with(LaserIndex {
+                    index,
+                    initiated: true,
+                })
+                .with(DipoleLight { wavelength })
+                .with(Polarization::linear(Vector3::x()))
+                .with(CoherentBeam {
+                    group: 0,
+                    phase: 0.0,
+                    frequency_offset: 0.0,
+                })
+                .build();
+        }
+
+        let intensity = 3.0;
+        let atom = test_world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(0.0, 0.0, wavelength / 8.0),
+            })
+            .with(LaserIntensitySamplers {
+                contents: [crate::laser::intensity::LaserIntensitySampler { intensity }; 2],
+            })
+            .with(LaserIntensityGradientSamplers {
+                contents: [crate::laser::intensity_gradient::LaserIntensityGradientSampler {
+                    gradient: Vector3::zeros(),
+                }; 2],
+            })
+            .build();
+
+        let mut system = SampleCoherentBeamsSystem::<2>;
+        system.run_now(&test_world);
+        test_world.maintain();
+
+        let samplers = test_world.read_storage::<LaserIntensitySamplers<2>>();
+        let gradients = test_world.read_storage::<LaserIntensityGradientSamplers<2>>();
+        let k = 2.0 * PI / wavelength;
+        // I = 4 I0 cos^2(k z), at kz = pi/4.
+        assert_approx_eq!(
+            samplers.get(atom).unwrap().contents[0].intensity,
+            2.0 * intensity,
+            1e-9
+        );
+        assert_approx_eq!(samplers.get(atom).unwrap().contents[1].intensity, 0.0);
+        assert_approx_eq!(
+            gradients.get(atom).unwrap().contents[0].gradient[2],
+            -4.0 * intensity * k,
+            1e-6 * intensity * k
+        );
+    }
+
+    #[test]
+    fn test_default_polarization_is_transverse() {
+        let mut test_world = World::new();
+        test_world.register::<CoherentBeam>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Polarization>();
+        test_world.register::<DipoleLight>();
+        test_world.register::<Frame>();
+        test_world.register::<Position>();
+        test_world.register::<LaserIntensitySamplers<2>>();
+        test_world.register::<LaserIntensityGradientSamplers<2>>();
+        test_world.insert(Step { n: 0 });
+        test_world.insert(Timestep { delta: 1e-6 });
+
+        // Two co-propagating beams along x, without a Polarization, interfere constructively.
+        for index in 0..2 {
+            test_world
+                .create_entity()
+                .with(plane_wave(Vector3::x()))
+                .with(LaserIndex {
+                    index,
+                    initiated: true,
+                })
+                .with(DipoleLight {
+                    wavelength: 1064e-9,
+                })
+                .with(CoherentBeam {
+                    group: 0,
+                    phase: 0.0,
+                    frequency_offset: 0.0,
+                })
+                .build();
+        }
+        let atom = test_world
+            .create_entity()
+            .with(Position::new())
+            .with(LaserIntensitySamplers {
+                contents: [crate::laser::intensity::LaserIntensitySampler { intensity: 1.0 }; 2],
+            })
+            .with(LaserIntensityGradientSamplers {
+                contents: [crate::laser::intensity_gradient::LaserIntensityGradientSampler {
+                    gradient: Vector3::zeros(),
+                }; 2],
+            })
+            .build();
+
+        SampleCoherentBeamsSystem::<2>.run_now(&test_world);
+        let samplers = test_world.read_storage::<LaserIntensitySamplers<2>>();
+        assert_approx_eq!(samplers.get(atom).unwrap().contents[0].intensity, 4.0, 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "has no DipoleLight")]
+    fn test_coherent_beam_without_dipole_light_panics() {
+        let mut test_world = World::new();
+        test_world.register::<CoherentBeam>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Polarization>();
+        test_world.register::<DipoleLight>();
+        test_world.register::<Frame>();
+        test_world.register::<Position>();
+        test_world.register::<LaserIntensitySamplers<2>>();
+        test_world.register::<LaserIntensityGradientSamplers<2>>();
+        test_world.insert(Step { n: 0 });
+        test_world.insert(Timestep { delta: 1e-6 });
+
+        test_world
+            .create_entity()
+            .with(plane_wave(Vector3::x()))
+            .with(LaserIndex {
+                index: 0,
+                initiated: true,
+            })
+            .with(CoherentBeam {
+                group: 0,
+                phase: 0.0,
+                frequency_offset: 0.0,
+            })
+            .build();
+
+        SampleCoherentBeamsSystem::<2>.run_now(&test_world);
+    }
+}
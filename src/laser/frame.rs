@@ -3,7 +3,9 @@
 extern crate nalgebra;
 extern crate rayon;
 extern crate specs;
+use super::gaussian::GaussianBeam;
 use nalgebra::Vector3;
+use specs::prelude::*;
 use specs::Component;
 use specs::VecStorage;
 
@@ -33,4 +35,119 @@ impl Frame {
             y_vector: orth_vector,
         }
     }
+
+    /// Creates a frame orthogonal to the given beam direction.
+    ///
+    /// The x axis is chosen perpendicular to both the beam and the cartesian x axis
+    /// (or the y axis, for beams close to the x axis), so the same direction always
+    /// gives the same frame.
+    pub fn default_for_direction(beam_direction: Vector3<f64>) -> Self {
+        let direction = beam_direction.normalize();
+        let trial = if direction[0].abs() < 0.9 {
+            Vector3::x()
+        } else {
+            Vector3::y()
+        };
+        let x_vector = direction.cross(&trial).normalize();
+        Frame {
+            x_vector,
+            y_vector: direction.cross(&x_vector),
+        }
+    }
+}
+
+/// Marks a `Frame` which was attached by [AttachDefaultFrameSystem], rather than given by the user.
+#[derive(Default)]
+pub struct DefaultFrame;
+impl Component for DefaultFrame {
+    type Storage = NullStorage<Self>;
+}
+
+/// Attaches the frame given by `Frame::default_for_direction` to each `GaussianBeam` without a `Frame`.
+///
+/// This ensures all beams have a consistent frame, which defines the axes for ellipticity,
+/// astigmatism and beam profiles. Frames attached by this system are marked with a [DefaultFrame],
+/// and are rebuilt from the direction of the beam each step so that they stay orthogonal to beams
+/// whose direction changes.
+pub struct AttachDefaultFrameSystem;
+impl<'a> System<'a> for AttachDefaultFrameSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, GaussianBeam>,
+        WriteStorage<'a, Frame>,
+        WriteStorage<'a, DefaultFrame>,
+    );
+
+    fn run(&mut self, (entities, beams, mut frames, mut default_frames): Self::SystemData) {
+        for (beam, frame, _) in (&beams, &mut frames, &default_frames).join() {
+            *frame = Frame::default_for_direction(beam.direction);
+        }
+        let unframed: Vec<(Entity, Vector3<f64>)> = (&entities, &beams, !&frames)
+            .join()
+            .map(|(entity, beam, _)| (entity, beam.direction))
+            .collect();
+        for (entity, direction) in unframed {
+            frames
+                .insert(entity, Frame::default_for_direction(direction))
+                .expect("Could not insert Frame.");
+            default_frames
+                .insert(entity, DefaultFrame)
+                .expect("Could not insert DefaultFrame.");
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_frame_follows_beam_direction() {
+        let mut test_world = World::new();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<DefaultFrame>();
+
+        let beam = GaussianBeam {
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 1.0e-3,
+            power: 1.0,
+            direction: Vector3::z(),
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        };
+        let default = test_world.create_entity().with(beam).build();
+        let user = test_world
+            .create_entity()
+            .with(beam)
+            .with(Frame {
+                x_vector: Vector3::x(),
+                y_vector: Vector3::y(),
+            })
+            .build();
+
+        let mut system = AttachDefaultFrameSystem;
+        system.run_now(&test_world);
+        for entity in [default, user].iter() {
+            test_world
+                .write_storage::<GaussianBeam>()
+                .get_mut(*entity)
+                .expect("entity not found")
+                .direction = Vector3::y();
+        }
+        system.run_now(&test_world);
+
+        let frames = test_world.read_storage::<Frame>();
+        let frame = frames.get(default).expect("entity not found");
+        assert_eq!(frame.x_vector.dot(&Vector3::y()), 0.0);
+        assert_eq!(frame.y_vector.dot(&Vector3::y()), 0.0);
+        assert!(test_world
+            .read_storage::<DefaultFrame>()
+            .get(user)
+            .is_none());
+        assert_eq!(
+            frames.get(user).expect("entity not found").y_vector,
+            Vector3::y()
+        );
+    }
 }
@@ -19,8 +19,8 @@ use serde::{Deserialize, Serialize};
 /// The beam will propagate in vacuum. Inhomogenous media, gravitational lensing, refractions and
 /// reflections (other than through a `CircularMask` are not implemented.
 ///
-/// Also, attenuation effects are not yet implemented but they might come in a version
-/// that accounts for atom-atom intereactions in the future.
+/// Attenuation of cooling beams by the atom cloud is handled separately, see
+/// [crate::laser_cooling::attenuation].
 #[derive(Deserialize, Serialize, Clone, Copy, Lerp)]
 pub struct GaussianBeam {
     /// A point that the laser beam intersects
@@ -44,7 +44,10 @@ pub struct GaussianBeam {
     ///  waist to the place where the area of the cross section is doubled in units of metres
     pub rayleigh_range: f64,
 
-    /// ellipticity
+    /// ellipticity, sqrt(1-(b/a)^2), with the major axis along the x axis of the beam's `Frame`.
+    ///
+    /// Beams without a `Frame` are given the one from `Frame::default_for_direction`. For
+    /// independent waists and waist positions along each axis, see `AstigmaticBeam`.
     pub ellipticity: f64,
 }
 impl Component for GaussianBeam {
@@ -214,6 +217,141 @@ pub fn calculate_rayleigh_range(wavelength: &f64, e_radius: &f64) -> f64 {
     2.0 * PI * e_radius.powf(2.0) / wavelength
 }
 
+/// A component that gives a `GaussianBeam` independent waists along the x and y axes of its `Frame`.
+///
+/// The waists along each axis may also be located at different positions along the beam, as
+/// produced by astigmatic optics. When present, this component replaces the `e_radius`,
+/// `ellipticity` and `rayleigh_range` of the `GaussianBeam`.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct AstigmaticBeam {
+    /// Radius of the waist along the frame x axis at which the intensity is 1/e of the peak value, in m.
+    pub e_radius_x: f64,
+    /// Radius of the waist along the frame y axis at which the intensity is 1/e of the peak value, in m.
+    pub e_radius_y: f64,
+    /// Position of the x waist along the beam direction, relative to the beam `intersection`, in m.
+    pub waist_offset_x: f64,
+    /// Position of the y waist along the beam direction, relative to the beam `intersection`, in m.
+    pub waist_offset_y: f64,
+    /// Rayleigh range of the beam along the frame x axis, in m.
+    pub rayleigh_range_x: f64,
+    /// Rayleigh range of the beam along the frame y axis, in m.
+    pub rayleigh_range_y: f64,
+}
+impl Component for AstigmaticBeam {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl AstigmaticBeam {
+    /// Creates an `AstigmaticBeam`, calculating the rayleigh ranges from the waists and the wavelength.
+    pub fn new(
+        e_radius_x: f64,
+        e_radius_y: f64,
+        waist_offset_x: f64,
+        waist_offset_y: f64,
+        wavelength: f64,
+    ) -> Self {
+        AstigmaticBeam {
+            e_radius_x,
+            e_radius_y,
+            waist_offset_x,
+            waist_offset_y,
+            rayleigh_range_x: calculate_rayleigh_range(&wavelength, &e_radius_x),
+            rayleigh_range_y: calculate_rayleigh_range(&wavelength, &e_radius_y),
+        }
+    }
+
+    /// The `AstigmaticBeam` equivalent to the `e_radius`, `ellipticity` and `rayleigh_range` of a `GaussianBeam`.
+    pub fn from_gaussian(beam: &GaussianBeam) -> Self {
+        let semi_major_axis = 1.0 / (1.0 - beam.ellipticity.powf(2.0)).powf(0.5);
+        AstigmaticBeam {
+            e_radius_x: beam.e_radius * semi_major_axis.powf(0.5),
+            e_radius_y: beam.e_radius / semi_major_axis.powf(0.5),
+            waist_offset_x: 0.0,
+            waist_offset_y: 0.0,
+            rayleigh_range_x: beam.rayleigh_range,
+            rayleigh_range_y: beam.rayleigh_range,
+        }
+    }
+
+    /// Returns the squared 1/e radius along one axis and its derivative with respect to z.
+    fn radius_squared(e_radius: f64, waist_offset: f64, rayleigh_range: f64, z: f64) -> (f64, f64) {
+        let dz = z - waist_offset;
+        (
+            e_radius.powi(2) * (1.0 + (dz / rayleigh_range).powi(2)),
+            e_radius.powi(2) * 2.0 * dz / rayleigh_range.powi(2),
+        )
+    }
+}
+
+/// Returns the intensity of an astigmatic gaussian laser beam at the specified position.
+///
+/// The transverse axes of the beam are given by `frame`. The power and direction are taken from the `GaussianBeam`.
+pub fn get_astigmatic_beam_intensity(
+    beam: &GaussianBeam,
+    astigmatism: &AstigmaticBeam,
+    pos: &Position,
+    mask: Option<&CircularMask>,
+    frame: &Frame,
+) -> f64 {
+    let (x, y, z) = maths::get_relative_coordinates_line_point(
+        &pos.pos,
+        &beam.intersection,
+        &beam.direction,
+        frame,
+    );
+    if let Some(mask) = mask {
+        if (x * x + y * y).powf(0.5) < mask.radius {
+            return 0.0;
+        }
+    }
+    let (wx2, _) = AstigmaticBeam::radius_squared(
+        astigmatism.e_radius_x,
+        astigmatism.waist_offset_x,
+        astigmatism.rayleigh_range_x,
+        z,
+    );
+    let (wy2, _) = AstigmaticBeam::radius_squared(
+        astigmatism.e_radius_y,
+        astigmatism.waist_offset_y,
+        astigmatism.rayleigh_range_y,
+        z,
+    );
+    beam.power / (PI * (wx2 * wy2).powf(0.5)) * EXP.powf(-x * x / wx2 - y * y / wy2)
+}
+
+/// Computes the intensity gradient of an astigmatic gaussian laser beam at the specified position.
+pub fn get_astigmatic_beam_intensity_gradient(
+    beam: &GaussianBeam,
+    astigmatism: &AstigmaticBeam,
+    pos: &Position,
+    frame: &Frame,
+) -> Vector3<f64> {
+    let direction = beam.direction.normalize();
+    let (x, y, z) =
+        maths::get_relative_coordinates_line_point(&pos.pos, &beam.intersection, &direction, frame);
+    let (wx2, dwx2) = AstigmaticBeam::radius_squared(
+        astigmatism.e_radius_x,
+        astigmatism.waist_offset_x,
+        astigmatism.rayleigh_range_x,
+        z,
+    );
+    let (wy2, dwy2) = AstigmaticBeam::radius_squared(
+        astigmatism.e_radius_y,
+        astigmatism.waist_offset_y,
+        astigmatism.rayleigh_range_y,
+        z,
+    );
+    let intensity =
+        beam.power / (PI * (wx2 * wy2).powf(0.5)) * EXP.powf(-x * x / wx2 - y * y / wy2);
+
+    let d_dx = -2.0 * x / wx2;
+    let d_dy = -2.0 * y / wy2;
+    let d_dz = -0.5 * dwx2 / wx2 - 0.5 * dwy2 / wy2
+        + x * x * dwx2 / wx2.powi(2)
+        + y * y * dwy2 / wy2.powi(2);
+    intensity * (frame.x_vector * d_dx + frame.y_vector * d_dy + direction * d_dz)
+}
+
 /// Computes the intensity gradient of a given beam and returns it as
 /// a three-dimensional vector
 pub fn get_gaussian_beam_intensity_gradient(
@@ -221,24 +359,12 @@ pub fn get_gaussian_beam_intensity_gradient(
     pos: &Position,
     reference_frame: &Frame,
 ) -> Vector3<f64> {
-    let rela_coord = pos.pos - beam.intersection;
-
-    // ellipticity treatment
-    let semi_major_axis = 1.0 / (1.0 - beam.ellipticity.powf(2.0)).powf(0.5);
-
-    let x = rela_coord.dot(&reference_frame.x_vector) / semi_major_axis.powf(0.5);
-    let y = rela_coord.dot(&reference_frame.y_vector) * semi_major_axis.powf(0.5);
-    let z = rela_coord.dot(&beam.direction);
-
-    let spot_size_squared =
-        2.0 * beam.e_radius.powf(2.0) * (1. + (z / beam.rayleigh_range).powf(2.0));
-    let vector = -4. * (reference_frame.x_vector * x + reference_frame.y_vector * y)
-        + beam.direction * z / (beam.rayleigh_range.powf(2.0) + z.powf(2.0))
-            * (-spot_size_squared + 4. * (x.powf(2.0) + y.powf(2.0)));
-    let intensity = 2. * beam.power / PI / spot_size_squared
-        * EXP.powf(-2. * (x.powf(2.0) + y.powf(2.0)) / spot_size_squared);
-
-    intensity / spot_size_squared * vector
+    get_astigmatic_beam_intensity_gradient(
+        beam,
+        &AstigmaticBeam::from_gaussian(beam),
+        pos,
+        reference_frame,
+    )
 }
 
 #[cfg(test)]
@@ -274,7 +400,75 @@ pub mod tests {
         let gradient = get_gaussian_beam_intensity_gradient(&beam, &pos1, &grf);
         assert_approx_eq!(gradient[0], -2.49605032e+13, 1e+8_f64);
         assert_approx_eq!(gradient[1], 0.0, 1e+9_f64);
-        assert_approx_eq!(gradient[2], -2.06143366e+08, 1e+6_f64);
+        assert_approx_eq!(gradient[2], -4.20876503e+08, 1e+6_f64);
+    }
+
+    #[test]
+    fn test_astigmatic_beam() {
+        let beam = GaussianBeam {
+            direction: Vector3::z(),
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 1.0,
+            power: 1.0,
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        };
+        let frame = Frame {
+            x_vector: Vector3::x(),
+            y_vector: Vector3::y(),
+        };
+        let astigmatism = AstigmaticBeam::new(20.0e-6, 10.0e-6, 100.0e-6, -50.0e-6, 1064.0e-9);
+
+        // At the x waist, the x radius equals the waist.
+        let on_axis = get_astigmatic_beam_intensity(
+            &beam,
+            &astigmatism,
+            &Position {
+                pos: Vector3::new(0.0, 0.0, 100.0e-6),
+            },
+            None,
+            &frame,
+        );
+        let off_axis = get_astigmatic_beam_intensity(
+            &beam,
+            &astigmatism,
+            &Position {
+                pos: Vector3::new(20.0e-6, 0.0, 100.0e-6),
+            },
+            None,
+            &frame,
+        );
+        assert_approx_eq!(off_axis / on_axis, (-1.0_f64).exp(), 1e-9);
+
+        // Compare the gradient to a numerical derivative.
+        let pos = Vector3::new(7.0e-6, -4.0e-6, 300.0e-6);
+        let gradient = get_astigmatic_beam_intensity_gradient(
+            &beam,
+            &astigmatism,
+            &Position { pos },
+            &frame,
+        );
+        let step = 1.0e-9;
+        for axis in 0..3 {
+            let mut delta = Vector3::new(0.0, 0.0, 0.0);
+            delta[axis] = step;
+            let forward = get_astigmatic_beam_intensity(
+                &beam,
+                &astigmatism,
+                &Position { pos: pos + delta },
+                None,
+                &frame,
+            );
+            let backward = get_astigmatic_beam_intensity(
+                &beam,
+                &astigmatism,
+                &Position { pos: pos - delta },
+                None,
+                &frame,
+            );
+            let numerical = (forward - backward) / (2.0 * step);
+            assert_approx_eq!(gradient[axis] / numerical, 1.0, 1e-4);
+        }
     }
 
     #[test]
@@ -0,0 +1,615 @@
+//! Diffraction gratings, which generate the beams of a grating MOT from a single incident beam.
+//!
+//! Add a `Grating` to the entity of the incident `GaussianBeam`. The grating is divided into
+//! `sectors` wedges around its centre, each with grating lines perpendicular to the radial
+//! direction. Each sector diffracts a first order beam back towards the axis of the grating,
+//! and the diffracted beams overlap with the incident beam to form the MOT.
+//!
+//! The direction of each diffracted beam follows from the grating equation: the component of the
+//! wavevector in the plane of the grating changes by one grating vector, `lambda / period` in units
+//! of the wavenumber. A diffracted beam is compressed by the ratio of the cosines of the diffraction
+//! and incidence angles in the plane of diffraction, which increases its intensity. As for a mirror,
+//! the helicity of circularly polarized light is reversed on diffraction. The light in each diffracted
+//! beam only fills the region above its sector, given by a `GratingFootprint`.
+//!
+//! Only the inward first order of each sector is included; the zeroth and outward orders do not
+//! overlap with the atoms for typical gratings.
+
+use super::frame::Frame;
+use super::gaussian::GaussianBeam;
+use super::index::LaserIndex;
+use super::polarization::Polarization;
+use super::update_component;
+use crate::constant::PI;
+use crate::dipole::DipoleLight;
+use crate::laser_cooling::CoolingLight;
+use nalgebra::{Complex, Vector3};
+use specs::prelude::*;
+
+/// A component that diffracts the `GaussianBeam` of the same entity into the first orders of a segmented grating.
+#[derive(Clone)]
+pub struct Grating {
+    /// Position of the centre of the grating, in m.
+    pub centre: Vector3<f64>,
+    /// Unit vector normal to the grating surface, pointing towards the atoms.
+    pub normal: Vector3<f64>,
+    /// Period of the grating lines, in m.
+    pub period: f64,
+    /// Number of sectors of the grating, eg 3 for a tri-grating.
+    pub sectors: usize,
+    /// Fraction of the incident power diffracted into the inward first order of each sector.
+    pub efficiency: f64,
+    /// Radius of the grating, in m.
+    pub radius: f64,
+    diffracted: Vec<Entity>,
+}
+impl Component for Grating {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl Grating {
+    pub fn new(
+        centre: Vector3<f64>,
+        normal: Vector3<f64>,
+        period: f64,
+        sectors: usize,
+        efficiency: f64,
+        radius: f64,
+    ) -> Self {
+        Grating {
+            centre,
+            normal: normal.normalize(),
+            period,
+            sectors,
+            efficiency,
+            radius,
+            diffracted: Vec::new(),
+        }
+    }
+
+    /// The diffracted beam entities, one for each sector.
+    pub fn diffracted_beams(&self) -> &[Entity] {
+        &self.diffracted
+    }
+
+    /// Unit vector in the plane of the grating pointing through the middle of the given sector.
+    pub fn sector_axis(&self, sector: usize) -> Vector3<f64> {
+        let frame = Frame::default_for_direction(self.normal);
+        let phi = 2.0 * PI * sector as f64 / self.sectors as f64;
+        phi.cos() * frame.x_vector + phi.sin() * frame.y_vector
+    }
+}
+
+/// A component that marks a beam diffracted by a `Grating`.
+#[derive(Clone, Copy)]
+pub struct DiffractedBeam {
+    /// The entity of the incident beam, which has the `Grating`.
+    pub incident: Entity,
+    /// Sector of the grating which diffracts this beam.
+    pub sector: usize,
+}
+impl Component for DiffractedBeam {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// A component that restricts a beam to the light leaving one sector of a grating.
+///
+/// Points are traced back along the beam direction to the plane of the grating, and are lit if
+/// they land within the sector.
+#[derive(Clone, Copy)]
+pub struct GratingFootprint {
+    /// Position of the centre of the grating, in m.
+    pub centre: Vector3<f64>,
+    /// Unit vector normal to the grating surface, pointing towards the atoms.
+    pub normal: Vector3<f64>,
+    /// Unit vector in the plane of the grating pointing through the middle of the sector.
+    pub sector_axis: Vector3<f64>,
+    /// Half of the angle subtended by the sector, in rad.
+    pub half_angle: f64,
+    /// Radius of the grating, in m.
+    pub radius: f64,
+    /// Direction of the beam leaving the grating.
+    pub direction: Vector3<f64>,
+}
+impl Component for GratingFootprint {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl GratingFootprint {
+    /// True if light leaving the sector reaches the given position.
+    pub fn contains(&self, pos: &Vector3<f64>) -> bool {
+        let height = (pos - self.centre).dot(&self.normal);
+        if height < 0.0 {
+            return false;
+        }
+        let origin = pos - height / self.direction.dot(&self.normal) * self.direction - self.centre;
+        if origin.norm() > self.radius {
+            return false;
+        }
+        let across = self.normal.cross(&self.sector_axis);
+        origin
+            .dot(&across)
+            .atan2(origin.dot(&self.sector_axis))
+            .abs()
+            <= self.half_angle
+    }
+}
+
+/// Parameters of a beam diffracted by a `Grating`.
+#[derive(Clone, Copy)]
+pub struct DiffractedBeamParameters {
+    pub beam: GaussianBeam,
+    pub frame: Frame,
+    pub footprint: GratingFootprint,
+}
+
+/// Calculates the beams diffracted by each sector of a grating.
+///
+/// Returns one entry for each sector. The entry is `None` if the first diffraction order of the
+/// sector does not propagate, because the grating period is too small, or if the incident beam
+/// does not propagate towards the grating surface.
+///
+/// # Arguments
+///
+/// `beam`: the incident beam.
+///
+/// `wavelength`: wavelength of the light, in m.
+///
+/// `grating`: the grating.
+pub fn diffract_beam(
+    beam: &GaussianBeam,
+    wavelength: f64,
+    grating: &Grating,
+) -> Vec<Option<DiffractedBeamParameters>> {
+    let normal = grating.normal.normalize();
+    let incident = beam.direction.normalize();
+    let cos_incidence = -incident.dot(&normal);
+    if cos_incidence.is_nan() || cos_incidence <= 0.0 {
+        return vec![None; grating.sectors];
+    }
+    let in_plane = incident + cos_incidence * normal;
+
+    // The diffracted beams all leave from the point where the incident beam axis meets the grating.
+    let hit = beam.intersection
+        + (grating.centre - beam.intersection).dot(&normal) / incident.dot(&normal) * incident;
+
+    (0..grating.sectors)
+        .map(|sector| {
+            let sector_axis = grating.sector_axis(sector);
+            let parallel = in_plane - wavelength / grating.period * sector_axis;
+            if parallel.norm() >= 1.0 {
+                return None;
+            }
+            let cos_diffraction = (1.0 - parallel.norm_squared()).sqrt();
+            let direction = parallel + cos_diffraction * normal;
+
+            // The width in the plane of diffraction is scaled by `compression`. The major axis
+            // of the elliptical beam lies along the x axis of the frame.
+            let compression = cos_diffraction / cos_incidence;
+            let perpendicular = normal.cross(&direction).normalize();
+            let x_vector = if compression <= 1.0 {
+                perpendicular
+            } else {
+                direction.cross(&perpendicular)
+            };
+            let frame = Frame {
+                x_vector,
+                y_vector: direction.cross(&x_vector),
+            };
+            Some(DiffractedBeamParameters {
+                beam: GaussianBeam {
+                    intersection: hit,
+                    e_radius: beam.e_radius * compression.sqrt(),
+                    power: grating.efficiency * beam.power,
+                    direction,
+                    rayleigh_range: beam.rayleigh_range,
+                    ellipticity: (1.0 - compression.min(1.0 / compression).powi(2)).sqrt(),
+                },
+                frame,
+                footprint: GratingFootprint {
+                    centre: grating.centre,
+                    normal,
+                    sector_axis,
+                    half_angle: PI / grating.sectors as f64,
+                    radius: grating.radius,
+                    direction,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Reflects a polarization vector in the grating surface and projects it onto the plane transverse to the diffracted beam.
+fn diffract_polarization(
+    polarization: &Polarization,
+    normal: &Vector3<f64>,
+    direction: &Vector3<f64>,
+) -> Polarization {
+    let n = normal.map(|x| Complex::new(x, 0.0));
+    let d = direction.map(|x| Complex::new(x, 0.0));
+    let reflected = polarization.vector - n * (n.dot(&polarization.vector) * 2.0);
+    let mut diffracted = Polarization::new(reflected - d * d.dot(&reflected));
+    diffracted.degree = polarization.degree;
+    diffracted
+}
+
+/// Creates and updates the diffracted beams of each incident beam with a `Grating`.
+///
+/// Diffracted beams copy the `CoolingLight` or `DipoleLight` of the incident beam, with the helicity
+/// of a `CoolingLight` reversed. The components of existing diffracted beams are modified in place,
+/// and diffracted beams are deleted when their incident beam, or its `Grating`, is removed.
+pub struct UpdateGratingBeamsSystem;
+impl<'a> System<'a> for UpdateGratingBeamsSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Grating>,
+        WriteStorage<'a, GaussianBeam>,
+        WriteStorage<'a, Frame>,
+        WriteStorage<'a, CoolingLight>,
+        WriteStorage<'a, DipoleLight>,
+        WriteStorage<'a, Polarization>,
+        WriteStorage<'a, GratingFootprint>,
+        WriteStorage<'a, DiffractedBeam>,
+        WriteStorage<'a, LaserIndex>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut gratings,
+            mut beams,
+            mut frames,
+            mut cooling_lights,
+            mut dipole_lights,
+            mut polarizations,
+            mut footprints,
+            mut diffracted_beams,
+            mut indices,
+        ): Self::SystemData,
+    ) {
+        // Remove diffracted beams which no longer belong to the grating of their incident beam.
+        for (entity, diffracted) in (&entities, &diffracted_beams).join() {
+            let current = match gratings.get(diffracted.incident) {
+                Some(grating) => grating.diffracted.contains(&entity),
+                None => false,
+            };
+            if !current {
+                let _ = entities.delete(entity);
+            }
+        }
+
+        for (incident, grating) in (&entities, &mut gratings).join() {
+            let beam = beams.get(incident).copied();
+            let cooling = cooling_lights.get(incident).copied();
+            let dipole = dipole_lights.get(incident).copied();
+            let (beam, wavelength) = match (beam, cooling, dipole) {
+                (Some(beam), Some(cooling), _) => (beam, cooling.wavelength),
+                (Some(beam), None, Some(dipole)) => (beam, dipole.wavelength),
+                _ => {
+                    for entity in grating.diffracted.drain(..) {
+                        let _ = entities.delete(entity);
+                    }
+                    continue;
+                }
+            };
+            let polarization = polarizations.get(incident).copied();
+
+            let diffracted = diffract_beam(&beam, wavelength, grating);
+            for entity in grating
+                .diffracted
+                .drain(diffracted.len().min(grating.diffracted.len())..)
+            {
+                let _ = entities.delete(entity);
+            }
+            for entity in grating.diffracted.iter_mut() {
+                if !entities.is_alive(*entity) {
+                    *entity = entities.create();
+                }
+            }
+            while grating.diffracted.len() < diffracted.len() {
+                grating.diffracted.push(entities.create());
+            }
+
+            for (sector, (&entity, parameters)) in
+                grating.diffracted.iter().zip(diffracted.iter()).enumerate()
+            {
+                if indices.get(entity).is_none() {
+                    update_component(&mut indices, entity, LaserIndex::default());
+                }
+                update_component(
+                    &mut diffracted_beams,
+                    entity,
+                    DiffractedBeam { incident, sector },
+                );
+                match parameters {
+                    Some(parameters) => {
+                        update_component(&mut beams, entity, parameters.beam);
+                        update_component(&mut frames, entity, parameters.frame);
+                        update_component(&mut footprints, entity, parameters.footprint);
+                        if let Some(polarization) = polarization {
+                            update_component(
+                                &mut polarizations,
+                                entity,
+                                diffract_polarization(
+                                    &polarization,
+                                    &grating.normal,
+                                    &parameters.beam.direction,
+                                ),
+                            );
+                        }
+                    }
+                    // Orders which do not propagate are kept, with zero power, so that they reappear
+                    // if the geometry changes.
+                    None => match beams.get_mut(entity) {
+                        Some(diffracted) => diffracted.power = 0.0,
+                        None => update_component(
+                            &mut beams,
+                            entity,
+                            GaussianBeam { power: 0.0, ..beam },
+                        ),
+                    },
+                }
+                if let Some(cooling) = cooling {
+                    update_component(
+                        &mut cooling_lights,
+                        entity,
+                        CoolingLight {
+                            polarization: -cooling.polarization,
+                            wavelength: cooling.wavelength,
+                        },
+                    );
+                }
+                if let Some(dipole) = dipole {
+                    update_component(&mut dipole_lights, entity, dipole);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn incident_beam() -> GaussianBeam {
+        GaussianBeam {
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 5.0e-3,
+            power: 0.1,
+            direction: -Vector3::z(),
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        }
+    }
+
+    fn tri_grating() -> Grating {
+        Grating::new(
+            Vector3::new(0.0, 0.0, -3.0e-3),
+            Vector3::z(),
+            1.2e-6,
+            3,
+            0.33,
+            1.0e-2,
+        )
+    }
+
+    #[test]
+    fn test_diffraction_angles() {
+        let wavelength = 780e-9;
+        let grating = tri_grating();
+        let diffracted = diffract_beam(&incident_beam(), wavelength, &grating);
+        assert_eq!(diffracted.len(), 3);
+
+        let sin_theta: f64 = wavelength / grating.period;
+        let cos_theta = (1.0 - sin_theta.powi(2)).sqrt();
+        let mut net_radial = Vector3::new(0.0, 0.0, 0.0);
+        for (sector, parameters) in diffracted.iter().enumerate() {
+            let beam = parameters.unwrap().beam;
+            assert_approx_eq!(beam.direction.norm(), 1.0, 1e-12);
+            assert_approx_eq!(beam.direction[2], cos_theta, 1e-12);
+            assert_approx_eq!(
+                beam.direction.dot(&grating.sector_axis(sector)),
+                -sin_theta,
+                1e-12
+            );
+            assert_approx_eq!(beam.power, 0.033, 1e-12);
+            assert_approx_eq!(beam.intersection[2], -3.0e-3, 1e-15);
+            net_radial += beam.direction - beam.direction[2] * Vector3::z();
+        }
+        assert_approx_eq!(net_radial.norm(), 0.0, 1e-12);
+    }
+
+    #[test]
+    fn test_diffracted_intensity_is_compressed() {
+        // The peak intensity increases by 1/cos(theta), and the width perpendicular to the plane of diffraction is unchanged.
+        let wavelength = 780e-9;
+        let incident = incident_beam();
+        let diffracted = diffract_beam(&incident, wavelength, &tri_grating());
+        let beam = diffracted[0].unwrap().beam;
+        let cos_theta = beam.direction[2];
+        let semi_major_axis = 1.0 / (1.0 - beam.ellipticity.powi(2)).sqrt();
+        assert_approx_eq!(
+            beam.e_radius.powi(2),
+            incident.e_radius.powi(2) * cos_theta,
+            1e-15
+        );
+        assert_approx_eq!(
+            beam.e_radius * semi_major_axis.sqrt(),
+            incident.e_radius,
+            1e-12
+        );
+    }
+
+    #[test]
+    fn test_footprint() {
+        let grating = tri_grating();
+        let diffracted = diffract_beam(&incident_beam(), 780e-9, &grating);
+        let footprint = diffracted[0].unwrap().footprint;
+
+        // Light from sector 0 travels inwards, so just above the grating it lies above sector 0.
+        let above_sector = grating.centre + 2.0e-3 * grating.sector_axis(0) + 1.0e-4 * Vector3::z();
+        let above_other_sector =
+            grating.centre + 2.0e-3 * grating.sector_axis(1) + 1.0e-4 * Vector3::z();
+        let below_grating =
+            grating.centre + 2.0e-3 * grating.sector_axis(0) - 1.0e-4 * Vector3::z();
+        let outside_grating =
+            grating.centre + 2.0e-2 * grating.sector_axis(0) + 1.0e-4 * Vector3::z();
+        assert!(footprint.contains(&above_sector));
+        assert!(!footprint.contains(&above_other_sector));
+        assert!(!footprint.contains(&below_grating));
+        assert!(!footprint.contains(&outside_grating));
+    }
+
+    #[test]
+    fn test_update_grating_beams_system() {
+        let mut test_world = World::new();
+        test_world.register::<Grating>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<DipoleLight>();
+        test_world.register::<Polarization>();
+        test_world.register::<GratingFootprint>();
+        test_world.register::<DiffractedBeam>();
+        test_world.register::<LaserIndex>();
+
+        let incident = test_world
+            .create_entity()
+            .with(incident_beam())
+            .with(CoolingLight {
+                polarization: 1,
+                wavelength: 780e-9,
+            })
+            .with(Polarization::circular(-Vector3::z(), 1))
+            .with(tri_grating())
+            .build();
+
+        let mut system = UpdateGratingBeamsSystem;
+        system.run_now(&test_world);
+        test_world.maintain();
+        let first = test_world
+            .read_storage::<Grating>()
+            .get(incident)
+            .unwrap()
+            .diffracted_beams()
+            .to_vec();
+        system.run_now(&test_world);
+        test_world.maintain();
+
+        let diffracted = test_world.read_storage::<DiffractedBeam>();
+        assert_eq!((&diffracted).join().count(), 3);
+        let gratings = test_world.read_storage::<Grating>();
+        // The diffracted beams are updated in place.
+        assert_eq!(
+            gratings.get(incident).unwrap().diffracted_beams(),
+            &first[..]
+        );
+        let entity = gratings.get(incident).unwrap().diffracted_beams()[1];
+        assert_eq!(diffracted.get(entity).unwrap().sector, 1);
+
+        let cooling = test_world.read_storage::<CoolingLight>();
+        assert_eq!(cooling.get(entity).unwrap().polarization, -1);
+
+        // The helicity of the diffracted light is reversed.
+        let beams = test_world.read_storage::<GaussianBeam>();
+        let polarizations = test_world.read_storage::<Polarization>();
+        let helicity = |entity: Entity| {
+            let u = polarizations.get(entity).unwrap().vector;
+            let cross = u.map(|c| c.conj()).cross(&u);
+            Vector3::new(cross[0].im, cross[1].im, cross[2].im)
+                .dot(&beams.get(entity).unwrap().direction)
+        };
+        assert!(helicity(incident) * helicity(entity) < 0.0);
+        assert!(test_world
+            .read_storage::<GratingFootprint>()
+            .get(entity)
+            .is_some());
+    }
+
+    #[test]
+    fn test_non_propagating_orders() {
+        let mut grating = tri_grating();
+        let mut away = incident_beam();
+        away.direction = Vector3::z();
+        assert!(diffract_beam(&away, 780e-9, &grating)
+            .iter()
+            .all(|parameters| parameters.is_none()));
+
+        grating.period = 700e-9;
+        assert!(diffract_beam(&incident_beam(), 780e-9, &grating)
+            .iter()
+            .all(|parameters| parameters.is_none()));
+
+        // The diffracted beams are kept, but carry no power.
+        let mut test_world = World::new();
+        test_world.register::<Grating>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<DipoleLight>();
+        test_world.register::<Polarization>();
+        test_world.register::<GratingFootprint>();
+        test_world.register::<DiffractedBeam>();
+        test_world.register::<LaserIndex>();
+        test_world
+            .create_entity()
+            .with(incident_beam())
+            .with(CoolingLight {
+                polarization: 1,
+                wavelength: 780e-9,
+            })
+            .with(grating)
+            .build();
+        UpdateGratingBeamsSystem.run_now(&test_world);
+        test_world.maintain();
+
+        let beams = test_world.read_storage::<GaussianBeam>();
+        let diffracted = test_world.read_storage::<DiffractedBeam>();
+        assert_eq!((&diffracted).join().count(), 3);
+        for (beam, _) in (&beams, &diffracted).join() {
+            assert_eq!(beam.power, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_diffracted_beams_are_deleted_with_grating() {
+        let mut test_world = World::new();
+        test_world.register::<Grating>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<DipoleLight>();
+        test_world.register::<Polarization>();
+        test_world.register::<GratingFootprint>();
+        test_world.register::<DiffractedBeam>();
+        test_world.register::<LaserIndex>();
+
+        let incident = test_world
+            .create_entity()
+            .with(incident_beam())
+            .with(DipoleLight { wavelength: 780e-9 })
+            .with(tri_grating())
+            .build();
+        let mut system = UpdateGratingBeamsSystem;
+        system.run_now(&test_world);
+        test_world.maintain();
+        assert_eq!(
+            (&test_world.read_storage::<DiffractedBeam>())
+                .join()
+                .count(),
+            3
+        );
+
+        test_world.delete_entity(incident).unwrap();
+        system.run_now(&test_world);
+        test_world.maintain();
+        assert_eq!(
+            (&test_world.read_storage::<DiffractedBeam>())
+                .join()
+                .count(),
+            0
+        );
+    }
+}
@@ -7,7 +7,12 @@ extern crate rayon;
 extern crate serde;
 
 use super::frame::Frame;
-use super::gaussian::{get_gaussian_beam_intensity, CircularMask, GaussianBeam};
+use super::gaussian::{
+    get_astigmatic_beam_intensity, get_gaussian_beam_intensity, AstigmaticBeam, CircularMask,
+    GaussianBeam,
+};
+use super::grating::GratingFootprint;
+use super::profile::{get_beam_profile_intensity, BeamProfile};
 use crate::atom::Position;
 use crate::laser::index::LaserIndex;
 use serde::Serialize;
@@ -61,10 +66,10 @@ impl<'a, const N: usize> System<'a> for InitialiseLaserIntensitySamplersSystem<N
 
 /// System that calculates the intensity of CoolingLight entities, for example those with `GaussianBeam` components.
 ///
-/// So far, the only intensity distribution implemented as a component for the use
-/// along with `CoolingLight` is `GaussianBeam`.
-/// However, in the future, other components will be implemented and this System can then be expanded
-/// to handle them as well.
+/// Beams with a `BeamProfile` component use that transverse profile instead of a gaussian, and
+/// beams with an `AstigmaticBeam` component use independent waists along each axis. Beams without
+/// a `Frame` use the one given by `Frame::default_for_direction`. Beams with a `GratingFootprint`
+/// have zero intensity outside of the footprint.
 pub struct SampleLaserIntensitySystem<const N: usize>;
 
 impl<'a, const N: usize> System<'a> for SampleLaserIntensitySystem<N> {
@@ -74,13 +79,27 @@ impl<'a, const N: usize> System<'a> for SampleLaserIntensitySystem<N> {
         ReadStorage<'a, GaussianBeam>,
         ReadStorage<'a, CircularMask>,
         ReadStorage<'a, Frame>,
+        ReadStorage<'a, BeamProfile>,
+        ReadStorage<'a, AstigmaticBeam>,
+        ReadStorage<'a, GratingFootprint>,
         ReadStorage<'a, Position>,
         WriteStorage<'a, LaserIntensitySamplers<N>>,
     );
 
     fn run(
         &mut self,
-        (entities, indices, gaussian, masks, frames, position, mut intensity_samplers): Self::SystemData,
+        (
+            entities,
+            indices,
+            gaussian,
+            masks,
+            frames,
+            profiles,
+            astigmatism,
+            footprints,
+            position,
+            mut intensity_samplers,
+        ): Self::SystemData,
     ) {
         use rayon::prelude::*;
 
@@ -91,7 +110,10 @@ impl<'a, const N: usize> System<'a> for SampleLaserIntensitySystem<N> {
             LaserIndex,
             GaussianBeam,
             Option<CircularMask>,
-            Option<Frame>,
+            Frame,
+            Option<BeamProfile>,
+            Option<AstigmaticBeam>,
+            Option<GratingFootprint>,
         );
         let laser_cache: Vec<CachedLaser> = (&entities, &indices, &gaussian)
             .join()
@@ -100,7 +122,13 @@ impl<'a, const N: usize> System<'a> for SampleLaserIntensitySystem<N> {
                     *index,
                     *gaussian,
                     masks.get(laser_entity).cloned(),
-                    frames.get(laser_entity).cloned(),
+                    frames
+                        .get(laser_entity)
+                        .cloned()
+                        .unwrap_or_else(|| Frame::default_for_direction(gaussian.direction)),
+                    profiles.get(laser_entity).cloned(),
+                    astigmatism.get(laser_entity).cloned(),
+                    footprints.get(laser_entity).cloned(),
                 )
             })
             .collect();
@@ -109,22 +137,44 @@ impl<'a, const N: usize> System<'a> for SampleLaserIntensitySystem<N> {
         for base_index in (0..laser_cache.len()).step_by(LASER_CACHE_SIZE) {
             let max_index = laser_cache.len().min(base_index + LASER_CACHE_SIZE);
             let slice = &laser_cache[base_index..max_index];
-            let mut laser_array = vec![laser_cache[0]; LASER_CACHE_SIZE];
-            laser_array[..max_index].copy_from_slice(slice);
+            let mut laser_array = vec![laser_cache[0].clone(); LASER_CACHE_SIZE];
+            laser_array[..slice.len()].clone_from_slice(slice);
             let number_in_iteration = slice.len();
 
             (&mut intensity_samplers, &position)
                 .par_join()
                 .for_each(|(samplers, pos)| {
-                    for (index, gaussian, mask, frame) in
+                    for (index, gaussian, mask, frame, profile, astigmatism, footprint) in
                         laser_array.iter().take(number_in_iteration)
                     {
-                        samplers.contents[index.index].intensity = get_gaussian_beam_intensity(
-                            gaussian,
-                            pos,
-                            mask.as_ref(),
-                            frame.as_ref(),
-                        );
+                        if let Some(footprint) = footprint {
+                            if !footprint.contains(&pos.pos) {
+                                samplers.contents[index.index].intensity = 0.0;
+                                continue;
+                            }
+                        }
+                        samplers.contents[index.index].intensity = match (profile, astigmatism) {
+                            (Some(profile), _) => get_beam_profile_intensity(
+                                gaussian,
+                                profile,
+                                pos,
+                                mask.as_ref(),
+                                frame,
+                            ),
+                            (None, Some(astigmatism)) => get_astigmatic_beam_intensity(
+                                gaussian,
+                                astigmatism,
+                                pos,
+                                mask.as_ref(),
+                                frame,
+                            ),
+                            (None, None) => get_gaussian_beam_intensity(
+                                gaussian,
+                                pos,
+                                mask.as_ref(),
+                                Some(frame),
+                            ),
+                        };
                     }
                 });
         }
@@ -150,6 +200,9 @@ pub mod tests {
         test_world.register::<GaussianBeam>();
         test_world.register::<CircularMask>();
         test_world.register::<Frame>();
+        test_world.register::<crate::laser::profile::BeamProfile>();
+        test_world.register::<crate::laser::gaussian::AstigmaticBeam>();
+        test_world.register::<GratingFootprint>();
         test_world.register::<Position>();
         test_world.register::<LaserIntensitySamplers<{ DEFAULT_BEAM_LIMIT }>>();
 
@@ -7,8 +7,13 @@ use specs::prelude::*;
 use crate::atom::Position;
 use crate::dipole::DipoleLight;
 use crate::laser::frame::Frame;
-use crate::laser::gaussian::{get_gaussian_beam_intensity_gradient, GaussianBeam};
+use crate::laser::gaussian::{
+    get_astigmatic_beam_intensity_gradient, get_gaussian_beam_intensity_gradient, AstigmaticBeam,
+    CircularMask, GaussianBeam,
+};
+use crate::laser::grating::GratingFootprint;
 use crate::laser::index::LaserIndex;
+use crate::laser::profile::{get_beam_profile_intensity_gradient, BeamProfile};
 use nalgebra::Vector3;
 use specs::{Component, Join, ReadStorage, System, VecStorage, WriteStorage};
 
@@ -43,6 +48,10 @@ impl<const N: usize> Component for LaserIntensityGradientSamplers<N> {
 /// So far, the only intensity distribution implemented is `GaussianBeam`. Additionally
 /// the system also uses `GaussianRayleighRange` for axial divergence and
 /// `Frame` to account for different ellipiticies in the future.
+/// Beams with a `BeamProfile` component use the gradient of that profile instead, and beams
+/// with an `AstigmaticBeam` use independent waists along each axis. Beams without a `Frame`
+/// use the one given by `Frame::default_for_direction`. Beams with a `GratingFootprint` have zero
+/// gradient outside of the footprint, matching the intensity.
 /// The result is stored in the `LaserIntensityGradientSamplers` component that each
 /// atom is associated with.
 pub struct SampleGaussianLaserIntensityGradientSystem<const N: usize>;
@@ -53,22 +62,63 @@ impl<'a, const N: usize> System<'a> for SampleGaussianLaserIntensityGradientSyst
         ReadStorage<'a, LaserIndex>,
         ReadStorage<'a, GaussianBeam>,
         ReadStorage<'a, Frame>,
+        ReadStorage<'a, BeamProfile>,
+        ReadStorage<'a, CircularMask>,
+        ReadStorage<'a, AstigmaticBeam>,
+        ReadStorage<'a, GratingFootprint>,
         ReadStorage<'a, Position>,
         WriteStorage<'a, LaserIntensityGradientSamplers<N>>,
     );
 
     fn run(
         &mut self,
-        (dipole, index, gaussian, reference_frame, pos, mut sampler): Self::SystemData,
+        (
+            dipole,
+            index,
+            gaussian,
+            reference_frame,
+            profiles,
+            masks,
+            astigmatism,
+            footprints,
+            pos,
+            mut sampler,
+        ): Self::SystemData,
     ) {
         use rayon::prelude::*;
 
-        for (_dipole, index, beam, reference) in
-            (&dipole, &index, &gaussian, &reference_frame).join()
+        for (_dipole, index, beam, reference, profile, mask, astigmatism, footprint) in (
+            &dipole,
+            &index,
+            &gaussian,
+            reference_frame.maybe(),
+            profiles.maybe(),
+            masks.maybe(),
+            astigmatism.maybe(),
+            footprints.maybe(),
+        )
+            .join()
         {
+            let reference = match reference {
+                Some(frame) => *frame,
+                None => Frame::default_for_direction(beam.direction),
+            };
             (&pos, &mut sampler).par_join().for_each(|(pos, sampler)| {
-                sampler.contents[index.index].gradient =
-                    get_gaussian_beam_intensity_gradient(beam, pos, reference);
+                if let Some(footprint) = footprint {
+                    if !footprint.contains(&pos.pos) {
+                        sampler.contents[index.index].gradient = Vector3::zeros();
+                        return;
+                    }
+                }
+                sampler.contents[index.index].gradient = match (profile, astigmatism) {
+                    (Some(profile), _) => {
+                        get_beam_profile_intensity_gradient(beam, profile, pos, mask, &reference)
+                    }
+                    (None, Some(astigmatism)) => {
+                        get_astigmatic_beam_intensity_gradient(beam, astigmatism, pos, &reference)
+                    }
+                    (None, None) => get_gaussian_beam_intensity_gradient(beam, pos, &reference),
+                };
             });
         }
     }
@@ -93,8 +143,12 @@ pub mod tests {
         test_world.register::<GaussianBeam>();
         test_world.register::<Position>();
         test_world.register::<LaserIntensityGradientSamplers<{ DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<AstigmaticBeam>();
         test_world.register::<Frame>();
+        test_world.register::<crate::laser::profile::BeamProfile>();
+        test_world.register::<crate::laser::gaussian::CircularMask>();
         test_world.register::<DipoleLight>();
+        test_world.register::<GratingFootprint>();
 
         let beam = GaussianBeam {
             direction: Vector3::z(),
@@ -181,8 +235,12 @@ pub mod tests {
         test_world.register::<GaussianBeam>();
         test_world.register::<Position>();
         test_world.register::<LaserIntensityGradientSamplers<{ DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<AstigmaticBeam>();
         test_world.register::<Frame>();
+        test_world.register::<crate::laser::profile::BeamProfile>();
+        test_world.register::<crate::laser::gaussian::CircularMask>();
         test_world.register::<DipoleLight>();
+        test_world.register::<GratingFootprint>();
 
         let beam = GaussianBeam {
             direction: Vector3::x(),
@@ -233,7 +291,7 @@ pub mod tests {
             .contents[0]
             .gradient;
 
-        assert_approx_eq!(-8.4628e+7, sim_result_gradient[0], 1e+5_f64);
+        assert_approx_eq!(-2.09081e+8, sim_result_gradient[0], 1e+5_f64);
         assert_approx_eq!(-4.33992902e+13, sim_result_gradient[1], 1e+8_f64);
         assert_approx_eq!(-4.33992902e+13, sim_result_gradient[2], 1e+8_f64);
     }
@@ -1,11 +1,18 @@
 //! Calculation and initialization of laser quantities, eg intensities and indexing.
 
+pub mod coherent;
 pub mod frame;
 pub mod gaussian;
+pub mod grating;
 pub mod index;
 pub mod intensity;
 pub mod intensity_gradient;
+pub mod noise;
+pub mod optics;
+pub mod polarization;
+pub mod profile;
 pub mod sampler;
+pub mod trajectory;
 
 use crate::initiate::NewlyCreated;
 use crate::integrator::INTEGRATE_POSITION_SYSTEM_NAME;
@@ -14,6 +21,23 @@ use specs::prelude::*;
 
 pub const DEFAULT_BEAM_LIMIT: usize = 16;
 
+/// Sets the component of an entity, modifying it in place if it already exists.
+///
+/// Used by systems that keep derived beam entities in step with their source beam.
+pub(crate) fn update_component<T: Component>(
+    storage: &mut WriteStorage<T>,
+    entity: Entity,
+    value: T,
+) {
+    match storage.get_mut(entity) {
+        Some(component) => *component = value,
+        None => {
+            // Inserting only fails for entities which have been deleted, which have nothing to update.
+            let _ = storage.insert(entity, value);
+        }
+    }
+}
+
 /// Attaches components used for optical force calculation to newly created atoms.
 ///
 /// They are recognized as newly created if they are associated with
@@ -86,7 +110,42 @@ fn add_systems_to_dispatch<const N: usize>(
         "attach_laser_components",
         deps,
     );
-    builder.add(index::IndexLasersSystem, "index_lasers", deps);
+    builder.add(noise::ClearBeamNoiseSystem, "clear_beam_noise", deps);
+    builder.add(
+        trajectory::UpdateBeamTrajectorySystem,
+        "update_beam_trajectories",
+        &["clear_beam_noise"],
+    );
+    builder.add(
+        trajectory::UpdatePowerModulationSystem,
+        "update_power_modulation",
+        &["clear_beam_noise"],
+    );
+    builder.add(
+        noise::ApplyBeamNoiseSystem,
+        "apply_beam_noise",
+        &["update_beam_trajectories", "update_power_modulation"],
+    );
+    builder.add(
+        optics::UpdateDerivedBeamsSystem,
+        "update_derived_beams",
+        &["apply_beam_noise"],
+    );
+    builder.add(
+        grating::UpdateGratingBeamsSystem,
+        "update_grating_beams",
+        &["apply_beam_noise"],
+    );
+    builder.add(
+        index::IndexLasersSystem,
+        "index_lasers",
+        &["update_derived_beams", "update_grating_beams"],
+    );
+    builder.add(
+        frame::AttachDefaultFrameSystem,
+        "attach_default_frames",
+        &["update_derived_beams", "update_grating_beams"],
+    );
     builder.add(
         sampler::InitialiseLaserSamplerMasksSystem::<N>,
         "initialise_laser_sampler_masks",
@@ -107,14 +166,27 @@ fn add_systems_to_dispatch<const N: usize>(
         "sample_laser_intensity",
         &[
             "index_lasers",
+            "attach_default_frames",
             "initialise_laser_intensity",
+            "update_beam_trajectories",
+            "update_power_modulation",
             INTEGRATE_POSITION_SYSTEM_NAME,
         ],
     );
     builder.add(
         intensity_gradient::SampleGaussianLaserIntensityGradientSystem::<N>,
         "sample_intensity_gradient",
-        &["index_lasers"],
+        &[
+            "index_lasers",
+            "attach_default_frames",
+            "update_beam_trajectories",
+            "update_power_modulation",
+        ],
+    );
+    builder.add(
+        coherent::SampleCoherentBeamsSystem::<N>,
+        "sample_coherent_beams",
+        &["sample_laser_intensity", "sample_intensity_gradient"],
     );
 }
 
@@ -122,5 +194,19 @@ fn add_systems_to_dispatch<const N: usize>(
 fn register_components(world: &mut World) {
     world.register::<gaussian::GaussianBeam>();
     world.register::<gaussian::CircularMask>();
+    world.register::<gaussian::AstigmaticBeam>();
     world.register::<frame::Frame>();
+    world.register::<frame::DefaultFrame>();
+    world.register::<profile::BeamProfile>();
+    world.register::<polarization::Polarization>();
+    world.register::<coherent::CoherentBeam>();
+    world.register::<trajectory::BeamTrajectory>();
+    world.register::<trajectory::PowerModulation>();
+    world.register::<noise::IntensityNoise>();
+    world.register::<noise::PointingJitter>();
+    world.register::<optics::BeamPath>();
+    world.register::<optics::DerivedBeam>();
+    world.register::<grating::Grating>();
+    world.register::<grating::GratingFootprint>();
+    world.register::<grating::DiffractedBeam>();
 }
@@ -0,0 +1,458 @@
+//! Technical noise on the power and pointing of laser beams.
+//!
+//! Add an `IntensityNoise` to a `GaussianBeam` entity to fluctuate its `power`, or a `PointingJitter` to
+//! fluctuate its `intersection` and `direction`. The spectrum of each noise source is described by a
+//! [NoiseSpectrum].
+//!
+//! Each noise component stores the nominal values of the beam. At the start of each step,
+//! [ClearBeamNoiseSystem] restores the beam to its nominal values; the nominal values may then be varied by
+//! a `PowerModulation` or `BeamTrajectory` (or a `Ramp`, if its system runs after `clear_beam_noise`), before
+//! [ApplyBeamNoiseSystem] stores them and applies the noise on top. To change the nominal values of a noisy
+//! beam between steps, modify those stored in the noise component rather than the beam itself.
+//!
+//! The heating rates expected in a dipole trap can be estimated using [intensity_noise_heating_rate] and
+//! [pointing_noise_heating_rate].
+
+use super::frame::Frame;
+use super::gaussian::GaussianBeam;
+use crate::constant::PI;
+use crate::integrator::Timestep;
+use crate::maths;
+use nalgebra::Vector3;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use specs::prelude::*;
+
+/// The spectrum of a noise source.
+#[derive(Clone, Copy)]
+pub enum NoiseSpectrum {
+    /// Uncorrelated noise, drawn independently each timestep.
+    ///
+    /// The spectrum is flat up to the Nyquist frequency of the simulation, `1 / (2 dt)`.
+    White,
+    /// Exponentially correlated noise, which has a Lorentzian spectrum.
+    Correlated {
+        /// Correlation time of the noise, in s.
+        correlation_time: f64,
+    },
+    /// Flicker noise, with a `1/f` spectrum between the two frequencies.
+    ///
+    /// The noise is generated as a sum of exponentially correlated processes, one per octave.
+    Flicker {
+        /// Lower frequency of the `1/f` region, in Hz.
+        min_frequency: f64,
+        /// Upper frequency of the `1/f` region, in Hz.
+        max_frequency: f64,
+    },
+}
+
+impl NoiseSpectrum {
+    /// Correlation times of the processes that make up the noise.
+    fn correlation_times(&self) -> Vec<f64> {
+        match self {
+            NoiseSpectrum::White => vec![0.0],
+            NoiseSpectrum::Correlated { correlation_time } => vec![*correlation_time],
+            NoiseSpectrum::Flicker {
+                min_frequency,
+                max_frequency,
+            } => {
+                let octaves = (max_frequency / min_frequency).log2().ceil().max(0.0) as i32;
+                (0..=octaves)
+                    .map(|i| 1.0 / (2.0 * PI * min_frequency * 2.0_f64.powi(i)))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A stochastic process with zero mean, a given root-mean-square value and a given spectrum.
+#[derive(Clone)]
+pub struct NoiseProcess {
+    /// Root-mean-square value of the noise.
+    pub rms: f64,
+    pub spectrum: NoiseSpectrum,
+    /// Correlation times and current values of the component processes.
+    components: Vec<(f64, f64)>,
+}
+
+impl NoiseProcess {
+    pub fn new(rms: f64, spectrum: NoiseSpectrum) -> Self {
+        NoiseProcess {
+            rms,
+            spectrum,
+            components: spectrum
+                .correlation_times()
+                .into_iter()
+                .map(|tau| (tau, 0.0))
+                .collect(),
+        }
+    }
+
+    /// Advances the noise by a time `dt`, and returns the new value.
+    pub fn sample<R: Rng>(&mut self, dt: f64, rng: &mut R) -> f64 {
+        let sigma = self.rms / (self.components.len() as f64).sqrt();
+        let mut total = 0.0;
+        for (tau, value) in self.components.iter_mut() {
+            let gaussian: f64 = rng.sample(StandardNormal);
+            *value = if *tau > 0.0 {
+                maths::ornstein_uhlenbeck_step(*value, sigma, *tau, dt, gaussian)
+            } else {
+                sigma * gaussian
+            };
+            total += *value;
+        }
+        total
+    }
+
+    /// The one-sided power spectral density of the noise at the given frequency, in units of `rms^2 / Hz`.
+    ///
+    /// # Arguments
+    ///
+    /// `frequency`: frequency, in Hz.
+    ///
+    /// `dt`: simulation timestep, in s, which sets the bandwidth of white noise.
+    pub fn power_spectral_density(&self, frequency: f64, dt: f64) -> f64 {
+        let variance = self.rms.powi(2) / self.components.len() as f64;
+        self.components
+            .iter()
+            .map(|(tau, _)| {
+                if *tau > 0.0 {
+                    4.0 * variance * tau / (1.0 + (2.0 * PI * frequency * tau).powi(2))
+                } else if frequency <= 1.0 / (2.0 * dt) {
+                    2.0 * variance * dt
+                } else {
+                    0.0
+                }
+            })
+            .sum()
+    }
+}
+
+/// Fluctuates the `power` of a `GaussianBeam` as `power * (1 + noise)`, where the noise is the relative intensity noise.
+#[derive(Clone)]
+pub struct IntensityNoise {
+    pub noise: NoiseProcess,
+    /// The nominal power of the beam, in W.
+    ///
+    /// This is `None` until the noise is first applied, when it is taken from the beam.
+    pub nominal_power: Option<f64>,
+}
+
+impl IntensityNoise {
+    /// Creates relative intensity noise with the given fractional rms and spectrum.
+    pub fn new(rms: f64, spectrum: NoiseSpectrum) -> Self {
+        IntensityNoise {
+            noise: NoiseProcess::new(rms, spectrum),
+            nominal_power: None,
+        }
+    }
+}
+
+impl Component for IntensityNoise {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Randomly displaces the `intersection` of a `GaussianBeam` in the plane transverse to the beam, and tilts
+/// its `direction` about the intersection.
+///
+/// The transverse axes are those of `Frame::default_for_direction` for the nominal direction. Angular
+/// jitter of a beam focused by a lens of focal length `f` moves the focus by `f` times the angle.
+#[derive(Clone)]
+pub struct PointingJitter {
+    /// Noise of the displacement along each transverse axis, in units of m.
+    pub displacement: [NoiseProcess; 2],
+    /// Noise of the tilt of the direction towards each transverse axis, in units of rad.
+    pub angle: [NoiseProcess; 2],
+    /// The nominal intersection of the beam, in m.
+    ///
+    /// This is `None` until the jitter is first applied, when it is taken from the beam.
+    pub nominal_intersection: Option<Vector3<f64>>,
+    /// The nominal direction of the beam.
+    ///
+    /// This is `None` until the jitter is first applied, when it is taken from the beam.
+    pub nominal_direction: Option<Vector3<f64>>,
+}
+
+impl PointingJitter {
+    /// Creates pointing jitter with the given spectrum.
+    ///
+    /// # Arguments
+    ///
+    /// `displacement_rms`: rms displacement along each transverse axis, in m.
+    ///
+    /// `angle_rms`: rms tilt towards each transverse axis, in rad.
+    ///
+    /// `spectrum`: spectrum of the displacement and tilt.
+    pub fn new(displacement_rms: f64, angle_rms: f64, spectrum: NoiseSpectrum) -> Self {
+        PointingJitter {
+            displacement: [
+                NoiseProcess::new(displacement_rms, spectrum),
+                NoiseProcess::new(displacement_rms, spectrum),
+            ],
+            angle: [
+                NoiseProcess::new(angle_rms, spectrum),
+                NoiseProcess::new(angle_rms, spectrum),
+            ],
+            nominal_intersection: None,
+            nominal_direction: None,
+        }
+    }
+}
+
+impl Component for PointingJitter {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Restores `GaussianBeam`s with `IntensityNoise` or `PointingJitter` to their nominal values.
+pub struct ClearBeamNoiseSystem;
+impl<'a> System<'a> for ClearBeamNoiseSystem {
+    type SystemData = (
+        WriteStorage<'a, GaussianBeam>,
+        ReadStorage<'a, IntensityNoise>,
+        ReadStorage<'a, PointingJitter>,
+    );
+
+    fn run(&mut self, (mut beams, intensity_noises, jitters): Self::SystemData) {
+        for (beam, intensity_noise) in (&mut beams, &intensity_noises).join() {
+            if let Some(power) = intensity_noise.nominal_power {
+                beam.power = power;
+            }
+        }
+        for (beam, jitter) in (&mut beams, &jitters).join() {
+            if let Some(intersection) = jitter.nominal_intersection {
+                beam.intersection = intersection;
+            }
+            if let Some(direction) = jitter.nominal_direction {
+                beam.direction = direction;
+            }
+        }
+    }
+}
+
+/// Stores the nominal values of `GaussianBeam`s, and applies `IntensityNoise` and `PointingJitter` to them.
+pub struct ApplyBeamNoiseSystem;
+impl<'a> System<'a> for ApplyBeamNoiseSystem {
+    type SystemData = (
+        WriteStorage<'a, GaussianBeam>,
+        WriteStorage<'a, IntensityNoise>,
+        WriteStorage<'a, PointingJitter>,
+        ReadExpect<'a, Timestep>,
+    );
+
+    fn run(&mut self, (mut beams, mut intensity_noises, mut jitters, timestep): Self::SystemData) {
+        let mut rng = rand::thread_rng();
+        for (beam, intensity_noise) in (&mut beams, &mut intensity_noises).join() {
+            intensity_noise.nominal_power = Some(beam.power);
+            let noise = intensity_noise.noise.sample(timestep.delta, &mut rng);
+            beam.power *= (1.0 + noise).max(0.0);
+        }
+        for (beam, jitter) in (&mut beams, &mut jitters).join() {
+            jitter.nominal_intersection = Some(beam.intersection);
+            jitter.nominal_direction = Some(beam.direction);
+            let frame = Frame::default_for_direction(beam.direction);
+            let [dx, dy] = &mut jitter.displacement;
+            beam.intersection += frame.x_vector * dx.sample(timestep.delta, &mut rng)
+                + frame.y_vector * dy.sample(timestep.delta, &mut rng);
+            let [ax, ay] = &mut jitter.angle;
+            let tilt = frame.x_vector * ax.sample(timestep.delta, &mut rng)
+                + frame.y_vector * ay.sample(timestep.delta, &mut rng);
+            beam.direction = (beam.direction.normalize() + tilt).normalize();
+        }
+    }
+}
+
+/// The rate at which relative intensity noise heats atoms in a harmonic trap, in s^-1.
+///
+/// The energy of the atoms grows exponentially at this rate, `Γ = π² ν² S(2ν)`, where `S` is the one-sided
+/// power spectral density of the relative intensity noise. See Savard, O'Hara and Thomas,
+/// [Phys. Rev. A 56, R1095 (1997)](https://doi.org/10.1103/PhysRevA.56.R1095).
+///
+/// # Arguments
+///
+/// `trap_frequency`: trap frequency along the axis considered, in Hz.
+///
+/// `noise`: the relative intensity noise.
+///
+/// `dt`: simulation timestep, in s.
+pub fn intensity_noise_heating_rate(trap_frequency: f64, noise: &NoiseProcess, dt: f64) -> f64 {
+    PI.powi(2) * trap_frequency.powi(2) * noise.power_spectral_density(2.0 * trap_frequency, dt)
+}
+
+/// The rate at which pointing noise heats atoms in a harmonic trap, in W.
+///
+/// The energy of the atoms grows linearly at this rate, `4 π⁴ M ν⁴ S(ν)`, where `S` is the one-sided
+/// power spectral density of the trap position. See Gehm et al.,
+/// [Phys. Rev. A 58, 3914 (1998)](https://doi.org/10.1103/PhysRevA.58.3914).
+///
+/// # Arguments
+///
+/// `mass`: mass of the atom, in kg.
+///
+/// `trap_frequency`: trap frequency along the axis considered, in Hz.
+///
+/// `noise`: the position noise along the axis.
+///
+/// `dt`: simulation timestep, in s.
+pub fn pointing_noise_heating_rate(
+    mass: f64,
+    trap_frequency: f64,
+    noise: &NoiseProcess,
+    dt: f64,
+) -> f64 {
+    4.0 * PI.powi(4)
+        * mass
+        * trap_frequency.powi(4)
+        * noise.power_spectral_density(trap_frequency, dt)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::integrator::Step;
+    use crate::laser::trajectory::{PowerModulation, UpdatePowerModulationSystem};
+    use assert_approx_eq::assert_approx_eq;
+
+    fn measured_rms(process: &mut NoiseProcess, dt: f64) -> f64 {
+        let mut rng = rand::thread_rng();
+        let samples: Vec<f64> = (0..200000).map(|_| process.sample(dt, &mut rng)).collect();
+        (samples[10000..].iter().map(|x| x * x).sum::<f64>() / 190000.0).sqrt()
+    }
+
+    #[test]
+    fn test_noise_process_rms() {
+        let dt = 1.0e-6;
+        for spectrum in [
+            NoiseSpectrum::White,
+            NoiseSpectrum::Correlated {
+                correlation_time: 1.0e-5,
+            },
+            NoiseSpectrum::Flicker {
+                min_frequency: 1.0e3,
+                max_frequency: 1.0e5,
+            },
+        ]
+        .iter()
+        {
+            let mut process = NoiseProcess::new(0.01, *spectrum);
+            assert_approx_eq!(measured_rms(&mut process, dt), 0.01, 1.5e-3);
+        }
+    }
+
+    #[test]
+    fn test_power_spectral_density() {
+        let dt = 1.0e-6;
+        let white = NoiseProcess::new(0.01, NoiseSpectrum::White);
+        assert_approx_eq!(white.power_spectral_density(1.0e3, dt), 2.0e-10, 1e-16);
+        assert_eq!(white.power_spectral_density(1.0e6, dt), 0.0);
+
+        // the flicker spectrum falls as 1/f between the corner frequencies.
+        let flicker = NoiseProcess::new(
+            0.01,
+            NoiseSpectrum::Flicker {
+                min_frequency: 1.0e2,
+                max_frequency: 1.0e6,
+            },
+        );
+        let ratio =
+            flicker.power_spectral_density(1.0e3, dt) / flicker.power_spectral_density(1.0e4, dt);
+        assert!((ratio - 10.0).abs() < 1.0, "{}", ratio);
+    }
+
+    #[test]
+    fn test_apply_beam_noise_system() {
+        let mut test_world = World::new();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<IntensityNoise>();
+        test_world.register::<PointingJitter>();
+        test_world.register::<PowerModulation>();
+        test_world.insert(Timestep { delta: 1.0e-6 });
+        test_world.insert(Step { n: 0 });
+
+        let beam = GaussianBeam {
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 1.0e-3,
+            power: 1.0,
+            direction: Vector3::z(),
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        };
+        let noisy = test_world
+            .create_entity()
+            .with(beam)
+            .with(IntensityNoise::new(0.01, NoiseSpectrum::White))
+            .with(PointingJitter::new(1.0e-6, 1.0e-6, NoiseSpectrum::White))
+            .build();
+        let modulated = test_world
+            .create_entity()
+            .with(beam)
+            .with(IntensityNoise::new(0.01, NoiseSpectrum::White))
+            .with(PowerModulation::Table(vec![(0.0, 2.0)]))
+            .build();
+
+        let mut clear = ClearBeamNoiseSystem;
+        let mut system = ApplyBeamNoiseSystem;
+        let mut modulation = UpdatePowerModulationSystem;
+        let mut powers = Vec::new();
+        let mut tilted = false;
+        for _ in 0..10000 {
+            clear.run_now(&test_world);
+            modulation.run_now(&test_world);
+            system.run_now(&test_world);
+            let beams = test_world.read_storage::<GaussianBeam>();
+            let noisy_beam = beams.get(noisy).expect("entity not found");
+            powers.push(noisy_beam.power);
+            // the displacement is transverse to the beam, and the beam is tilted by a small angle.
+            assert_eq!(noisy_beam.intersection[2], 0.0);
+            assert!(noisy_beam.intersection.norm() < 1.0e-5);
+            assert_approx_eq!(noisy_beam.direction.norm(), 1.0, 1e-12);
+            assert!(noisy_beam.direction.angle(&Vector3::z()) < 1.0e-5);
+            tilted |= noisy_beam.direction != Vector3::z();
+            let modulated_beam = beams.get(modulated).expect("entity not found");
+            assert!((modulated_beam.power - 2.0).abs() < 0.1);
+        }
+        assert!(tilted);
+
+        // the noise does not accumulate.
+        let mean = powers.iter().sum::<f64>() / powers.len() as f64;
+        let rms =
+            (powers.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / powers.len() as f64).sqrt();
+        assert_approx_eq!(mean, 1.0, 1e-3);
+        assert_approx_eq!(rms, 0.01, 1e-3);
+
+        // changing the stored nominal values moves the beam, and clearing the noise restores them.
+        {
+            let mut jitters = test_world.write_storage::<PointingJitter>();
+            let jitter = jitters.get_mut(noisy).expect("entity not found");
+            assert_eq!(jitter.nominal_intersection, Some(Vector3::zeros()));
+            assert_eq!(jitter.nominal_direction, Some(Vector3::z()));
+            jitter.nominal_direction = Some(Vector3::x());
+            let mut intensity_noises = test_world.write_storage::<IntensityNoise>();
+            let intensity_noise = intensity_noises.get_mut(noisy).expect("entity not found");
+            assert_eq!(intensity_noise.nominal_power, Some(1.0));
+            intensity_noise.nominal_power = Some(3.0);
+        }
+        clear.run_now(&test_world);
+        let beams = test_world.read_storage::<GaussianBeam>();
+        let noisy_beam = beams.get(noisy).expect("entity not found");
+        assert_eq!(noisy_beam.power, 3.0);
+        assert_eq!(noisy_beam.direction, Vector3::x());
+        assert_eq!(noisy_beam.intersection, Vector3::zeros());
+    }
+
+    #[test]
+    fn test_heating_rates() {
+        let dt = 1.0e-7;
+        let noise = NoiseProcess::new(0.1, NoiseSpectrum::White);
+        assert_approx_eq!(
+            intensity_noise_heating_rate(1.0e4, &noise, dt),
+            PI.powi(2) * 1.0e8 * 2.0e-9,
+            1e-9
+        );
+        let position = NoiseProcess::new(1.0e-8, NoiseSpectrum::White);
+        assert_approx_eq!(
+            pointing_noise_heating_rate(1.0e-25, 1.0e4, &position, dt),
+            4.0 * PI.powi(4) * 1.0e-25 * 1.0e16 * 2.0e-23,
+            1e-30
+        );
+    }
+}
@@ -0,0 +1,637 @@
+//! Propagation of laser beams through simple optical elements, eg lenses and mirrors.
+//!
+//! Add a `BeamPath` to a `GaussianBeam` entity to describe the optics that the beam passes
+//! through after it has crossed the atoms. Each time the light returns through the atoms after a
+//! reflection, a derived beam entity is created with the transformed waist, power, direction and
+//! polarization, and marked with a `DerivedBeam` component. A retro-reflected MOT beam can then be
+//! declared as a single source beam with a `RetroReflector`, and the return pass is updated
+//! automatically if the source beam moves or changes power.
+//!
+//! The beam is propagated with the complex beam parameter `q = z + i z_R` using ABCD matrices.
+//! All elements lie on the axis of the source beam at normal incidence, so each reflection
+//! reverses the propagation direction. Attenuation of derived cooling beams by the atom cloud on
+//! earlier passes is included by [crate::laser_cooling::attenuation].
+
+use super::frame::Frame;
+use super::gaussian::{calculate_rayleigh_range, GaussianBeam};
+use super::index::LaserIndex;
+use super::polarization::Polarization;
+use super::update_component;
+use crate::constant::PI;
+use crate::dipole::DipoleLight;
+use crate::laser_cooling::CoolingLight;
+use nalgebra::Complex;
+use specs::prelude::*;
+
+/// An optical element in the path of a beam.
+#[derive(Clone, Copy, Debug)]
+pub enum OpticalElement {
+    /// A thin lens with the given focal length, in m. Positive focal lengths focus the beam.
+    ThinLens { focal_length: f64 },
+    /// A spherical mirror with the given radius of curvature, in m. Positive radii are concave and focus the beam.
+    CurvedMirror {
+        radius_of_curvature: f64,
+        /// Fraction of the power reflected.
+        reflectivity: f64,
+    },
+    /// A flat mirror. The polarization vector is unchanged, so the helicity of circularly polarized light is reversed.
+    FlatMirror {
+        /// Fraction of the power reflected.
+        reflectivity: f64,
+    },
+    /// A flat mirror combined with a double-passed quarter-wave plate, as used to retro-reflect MOT beams.
+    /// The helicity of circularly polarized light is preserved.
+    RetroReflector {
+        /// Fraction of the power reflected.
+        reflectivity: f64,
+    },
+    /// A window, eg of the vacuum chamber, which the beam passes through with some loss.
+    Viewport {
+        /// Fraction of the power transmitted.
+        transmission: f64,
+    },
+}
+
+impl OpticalElement {
+    /// True if the element reflects the beam.
+    pub fn reflects(&self) -> bool {
+        matches!(
+            self,
+            OpticalElement::CurvedMirror { .. }
+                | OpticalElement::FlatMirror { .. }
+                | OpticalElement::RetroReflector { .. }
+        )
+    }
+
+    /// True if the element changes the curvature of the beam.
+    pub fn focuses(&self) -> bool {
+        matches!(
+            self,
+            OpticalElement::ThinLens { .. } | OpticalElement::CurvedMirror { .. }
+        )
+    }
+
+    /// Fraction of the power which continues along the path after the element.
+    pub fn efficiency(&self) -> f64 {
+        match self {
+            OpticalElement::ThinLens { .. } => 1.0,
+            OpticalElement::CurvedMirror { reflectivity, .. } => *reflectivity,
+            OpticalElement::FlatMirror { reflectivity } => *reflectivity,
+            OpticalElement::RetroReflector { reflectivity } => *reflectivity,
+            OpticalElement::Viewport { transmission } => *transmission,
+        }
+    }
+}
+
+/// An element of a `BeamPath`, placed a distance along the path from the previous element.
+#[derive(Clone, Copy, Debug)]
+pub struct PathElement {
+    /// Distance travelled by the light from the previous element, or from the `intersection`
+    /// of the source beam for the first element, in m.
+    pub distance: f64,
+    pub element: OpticalElement,
+}
+
+/// A component that describes the optics which a `GaussianBeam` passes through.
+///
+/// The source entity must also have a `CoolingLight` or `DipoleLight`, which gives the wavelength.
+/// Derived beams are created by the `UpdateDerivedBeamsSystem`.
+#[derive(Clone)]
+pub struct BeamPath {
+    /// Elements along the path, in the order the light reaches them.
+    pub elements: Vec<PathElement>,
+    derived: Vec<Entity>,
+}
+impl Component for BeamPath {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl BeamPath {
+    pub fn new(elements: Vec<PathElement>) -> Self {
+        BeamPath {
+            elements,
+            derived: Vec::new(),
+        }
+    }
+
+    /// Retro-reflects the beam at a given distance from the atoms, passing through a viewport
+    /// halfway to the mirror on the way out and on the way back.
+    pub fn retro_reflected(distance: f64, reflectivity: f64, viewport_transmission: f64) -> Self {
+        BeamPath::new(vec![
+            PathElement {
+                distance: distance / 2.0,
+                element: OpticalElement::Viewport {
+                    transmission: viewport_transmission,
+                },
+            },
+            PathElement {
+                distance: distance / 2.0,
+                element: OpticalElement::RetroReflector { reflectivity },
+            },
+            PathElement {
+                distance: distance / 2.0,
+                element: OpticalElement::Viewport {
+                    transmission: viewport_transmission,
+                },
+            },
+        ])
+    }
+
+    /// The derived beam entities, in the order the light crosses the atoms.
+    pub fn derived_beams(&self) -> &[Entity] {
+        &self.derived
+    }
+}
+
+/// A component that marks a beam derived from a source beam by its `BeamPath`.
+#[derive(Clone, Copy)]
+pub struct DerivedBeam {
+    /// The entity of the source beam.
+    pub source: Entity,
+    /// Number of times the light has crossed the atoms before this pass; the source beam is pass 0.
+    pub pass: usize,
+}
+impl Component for DerivedBeam {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Parameters of a beam derived from a `BeamPath`.
+#[derive(Clone, Copy)]
+pub struct DerivedBeamParameters {
+    pub beam: GaussianBeam,
+    pub frame: Frame,
+    /// True if the helicity of the light, with respect to its direction, is reversed compared to the source beam.
+    pub helicity_reversed: bool,
+    /// True if the polarization vector is the complex conjugate of the source polarization.
+    pub conjugated: bool,
+}
+
+/// Traces a beam through a `BeamPath`, and returns the beams which cross the atoms after reflections.
+///
+/// The atoms are assumed to be at the `intersection` of the source beam. A derived beam is
+/// returned each time the light crosses this point after being reflected, including the effect of
+/// all elements passed before the crossing.
+///
+/// # Arguments
+///
+/// `beam`: the source beam.
+///
+/// `frame`: frame of the source beam.
+///
+/// `wavelength`: wavelength of the light, in m.
+///
+/// `path`: the elements the beam passes through.
+pub fn trace_beam_path(
+    beam: &GaussianBeam,
+    frame: &Frame,
+    wavelength: f64,
+    path: &BeamPath,
+) -> Vec<DerivedBeamParameters> {
+    let direction = beam.direction.normalize();
+
+    // A beam with infinite Rayleigh range is treated as collimated, unless it is focused by the optics.
+    let rayleigh_range =
+        if beam.rayleigh_range.is_infinite() && path.elements.iter().any(|e| e.element.focuses()) {
+            calculate_rayleigh_range(&wavelength, &beam.e_radius)
+        } else {
+            beam.rayleigh_range
+        };
+
+    // Position along the source axis relative to the atoms, and sign of the propagation direction.
+    let mut position = 0.0;
+    let mut sign = 1.0;
+    let mut q = Complex::new(0.0, rayleigh_range);
+    let mut power = beam.power;
+    let mut helicity_reversed = false;
+    let mut conjugated = false;
+    let mut reflected = false;
+
+    let mut derived = Vec::new();
+    let mut emit = |q: Complex<f64>,
+                    position: f64,
+                    sign: f64,
+                    power: f64,
+                    helicity_reversed: bool,
+                    conjugated: bool| {
+        let new_direction = sign * direction;
+        let e_radius = if q.im.is_infinite() {
+            beam.e_radius
+        } else {
+            (q.im * wavelength / (2.0 * PI)).sqrt()
+        };
+        derived.push(DerivedBeamParameters {
+            beam: GaussianBeam {
+                intersection: beam.intersection + (position - sign * q.re) * direction,
+                e_radius,
+                power,
+                direction: new_direction,
+                rayleigh_range: q.im,
+                ellipticity: beam.ellipticity,
+            },
+            frame: Frame {
+                x_vector: frame.x_vector,
+                y_vector: new_direction.cross(&frame.x_vector),
+            },
+            helicity_reversed,
+            conjugated,
+        });
+    };
+
+    for path_element in path.elements.iter() {
+        let next_position = position + sign * path_element.distance;
+        if reflected && position * next_position <= 0.0 && position != 0.0 {
+            emit(q, position, sign, power, helicity_reversed, conjugated);
+            reflected = false;
+        }
+        position = next_position;
+        q += path_element.distance;
+        power *= path_element.element.efficiency();
+
+        match path_element.element {
+            OpticalElement::ThinLens { focal_length } => {
+                q = Complex::new(1.0, 0.0) / (Complex::new(1.0, 0.0) / q - 1.0 / focal_length);
+            }
+            OpticalElement::CurvedMirror {
+                radius_of_curvature,
+                ..
+            } => {
+                q = Complex::new(1.0, 0.0)
+                    / (Complex::new(1.0, 0.0) / q - 2.0 / radius_of_curvature);
+                helicity_reversed = !helicity_reversed;
+            }
+            OpticalElement::FlatMirror { .. } => {
+                helicity_reversed = !helicity_reversed;
+            }
+            OpticalElement::RetroReflector { .. } => {
+                conjugated = !conjugated;
+            }
+            OpticalElement::Viewport { .. } => {}
+        }
+        if path_element.element.reflects() {
+            sign = -sign;
+            reflected = true;
+        }
+    }
+
+    // After the last element the light propagates indefinitely, and crosses the atoms if heading towards them.
+    if reflected && position * sign < 0.0 {
+        emit(q, position, sign, power, helicity_reversed, conjugated);
+    }
+    derived
+}
+
+/// Creates and updates the derived beams of each source beam with a `BeamPath`.
+///
+/// Derived beams copy the `CoolingLight` or `DipoleLight` of the source beam, and its
+/// `Polarization` if present. The helicity of a derived `CoolingLight` is reversed by each
+/// flat or curved mirror. The components of existing derived beams are modified in place, and
+/// derived beams are deleted when their source beam, or its `BeamPath`, is removed.
+pub struct UpdateDerivedBeamsSystem;
+impl<'a> System<'a> for UpdateDerivedBeamsSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, BeamPath>,
+        WriteStorage<'a, GaussianBeam>,
+        WriteStorage<'a, Frame>,
+        WriteStorage<'a, CoolingLight>,
+        WriteStorage<'a, DipoleLight>,
+        WriteStorage<'a, Polarization>,
+        WriteStorage<'a, DerivedBeam>,
+        WriteStorage<'a, LaserIndex>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut paths,
+            mut beams,
+            mut frames,
+            mut cooling_lights,
+            mut dipole_lights,
+            mut polarizations,
+            mut derived_beams,
+            mut indices,
+        ): Self::SystemData,
+    ) {
+        // Remove derived beams which no longer belong to the path of their source.
+        for (entity, derived) in (&entities, &derived_beams).join() {
+            let current = match paths.get(derived.source) {
+                Some(path) => path.derived.contains(&entity),
+                None => false,
+            };
+            if !current {
+                let _ = entities.delete(entity);
+            }
+        }
+
+        for (source, path) in (&entities, &mut paths).join() {
+            let beam = beams.get(source).copied();
+            let cooling = cooling_lights.get(source).copied();
+            let dipole = dipole_lights.get(source).copied();
+            let (beam, wavelength) = match (beam, cooling, dipole) {
+                (Some(beam), Some(cooling), _) => (beam, cooling.wavelength),
+                (Some(beam), None, Some(dipole)) => (beam, dipole.wavelength),
+                _ => {
+                    for entity in path.derived.drain(..) {
+                        let _ = entities.delete(entity);
+                    }
+                    continue;
+                }
+            };
+            let frame = match frames.get(source) {
+                Some(frame) => *frame,
+                None => Frame::default_for_direction(beam.direction),
+            };
+            let polarization = polarizations.get(source).copied();
+
+            let traced = trace_beam_path(&beam, &frame, wavelength, path);
+            for entity in path.derived.drain(traced.len().min(path.derived.len())..) {
+                let _ = entities.delete(entity);
+            }
+            for entity in path.derived.iter_mut() {
+                if !entities.is_alive(*entity) {
+                    *entity = entities.create();
+                }
+            }
+            while path.derived.len() < traced.len() {
+                path.derived.push(entities.create());
+            }
+
+            for (pass, (&entity, parameters)) in path.derived.iter().zip(traced.iter()).enumerate()
+            {
+                if indices.get(entity).is_none() {
+                    update_component(&mut indices, entity, LaserIndex::default());
+                }
+                update_component(&mut beams, entity, parameters.beam);
+                update_component(&mut frames, entity, parameters.frame);
+                update_component(
+                    &mut derived_beams,
+                    entity,
+                    DerivedBeam {
+                        source,
+                        pass: pass + 1,
+                    },
+                );
+                if let Some(cooling) = cooling {
+                    let helicity = if parameters.helicity_reversed { -1 } else { 1 };
+                    update_component(
+                        &mut cooling_lights,
+                        entity,
+                        CoolingLight {
+                            polarization: helicity * cooling.polarization,
+                            wavelength: cooling.wavelength,
+                        },
+                    );
+                }
+                if let Some(dipole) = dipole {
+                    update_component(&mut dipole_lights, entity, dipole);
+                }
+                if let Some(polarization) = polarization {
+                    let vector = if parameters.conjugated {
+                        polarization.vector.map(|c| c.conj())
+                    } else {
+                        polarization.vector
+                    };
+                    update_component(
+                        &mut polarizations,
+                        entity,
+                        Polarization {
+                            vector,
+                            degree: polarization.degree,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use nalgebra::Vector3;
+
+    fn collimated_beam() -> GaussianBeam {
+        GaussianBeam {
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 1.0e-3,
+            power: 1.0,
+            direction: Vector3::z(),
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_retro_reflection() {
+        let beam = collimated_beam();
+        let frame = Frame::default_for_direction(beam.direction);
+        let path = BeamPath::retro_reflected(0.2, 0.99, 0.95);
+        let derived = trace_beam_path(&beam, &frame, 780e-9, &path);
+
+        assert_eq!(derived.len(), 1);
+        let reflected = derived[0];
+        assert_eq!(reflected.beam.direction, -Vector3::z());
+        assert_approx_eq!(reflected.beam.power, 0.99 * 0.95 * 0.95, 1e-12);
+        assert_eq!(reflected.beam.e_radius, beam.e_radius);
+        assert!(reflected.beam.rayleigh_range.is_infinite());
+        assert!(!reflected.helicity_reversed);
+        assert!(reflected.conjugated);
+        assert_approx_eq!(reflected.frame.y_vector.dot(&frame.y_vector), -1.0, 1e-12);
+    }
+
+    #[test]
+    fn test_mirror_images_waist() {
+        let wavelength = 1064e-9;
+        let e_radius = 50e-6;
+        let beam = GaussianBeam {
+            rayleigh_range: calculate_rayleigh_range(&wavelength, &e_radius),
+            e_radius,
+            ..collimated_beam()
+        };
+        let frame = Frame::default_for_direction(beam.direction);
+        let path = BeamPath::new(vec![PathElement {
+            distance: 0.1,
+            element: OpticalElement::FlatMirror { reflectivity: 1.0 },
+        }]);
+        let derived = trace_beam_path(&beam, &frame, wavelength, &path);
+
+        // The reflected beam appears to come from a waist at the mirror image of the source waist.
+        assert_eq!(derived.len(), 1);
+        assert_approx_eq!(derived[0].beam.intersection[2], 0.2, 1e-12);
+        assert_approx_eq!(derived[0].beam.e_radius, e_radius, 1e-12);
+        assert!(derived[0].helicity_reversed);
+    }
+
+    #[test]
+    fn test_curved_mirror_focuses() {
+        // A collimated beam is focused to half the radius of curvature in front of the mirror.
+        let beam = GaussianBeam {
+            e_radius: 5.0e-3,
+            ..collimated_beam()
+        };
+        let frame = Frame::default_for_direction(beam.direction);
+        let path = BeamPath::new(vec![PathElement {
+            distance: 0.3,
+            element: OpticalElement::CurvedMirror {
+                radius_of_curvature: 0.4,
+                reflectivity: 1.0,
+            },
+        }]);
+        let derived = trace_beam_path(&beam, &frame, 1064e-9, &path);
+        assert_eq!(derived.len(), 1);
+        assert_approx_eq!(derived[0].beam.intersection[2], 0.1, 1e-5);
+        assert!(derived[0].beam.e_radius < 1.0e-4);
+    }
+
+    #[test]
+    fn test_multiple_passes() {
+        // Two mirrors either side of the atoms, so the light crosses the atoms twice after the source pass.
+        let beam = collimated_beam();
+        let frame = Frame::default_for_direction(beam.direction);
+        let path = BeamPath::new(vec![
+            PathElement {
+                distance: 0.1,
+                element: OpticalElement::FlatMirror { reflectivity: 0.5 },
+            },
+            PathElement {
+                distance: 0.3,
+                element: OpticalElement::FlatMirror { reflectivity: 0.5 },
+            },
+        ]);
+        let derived = trace_beam_path(&beam, &frame, 780e-9, &path);
+        assert_eq!(derived.len(), 2);
+        assert_eq!(derived[0].beam.direction, -Vector3::z());
+        assert_approx_eq!(derived[0].beam.power, 0.5, 1e-12);
+        assert!(derived[0].helicity_reversed);
+        assert_eq!(derived[1].beam.direction, Vector3::z());
+        assert_approx_eq!(derived[1].beam.power, 0.25, 1e-12);
+        assert!(!derived[1].helicity_reversed);
+    }
+
+    #[test]
+    fn test_update_derived_beams_system() {
+        let mut test_world = World::new();
+        test_world.register::<BeamPath>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<DipoleLight>();
+        test_world.register::<Polarization>();
+        test_world.register::<DerivedBeam>();
+        test_world.register::<LaserIndex>();
+
+        let source = test_world
+            .create_entity()
+            .with(collimated_beam())
+            .with(CoolingLight {
+                polarization: 1,
+                wavelength: 461e-9,
+            })
+            .with(Polarization::circular(Vector3::z(), 1))
+            .with(BeamPath::new(vec![PathElement {
+                distance: 0.1,
+                element: OpticalElement::RetroReflector { reflectivity: 0.9 },
+            }]))
+            .build();
+
+        let mut system = UpdateDerivedBeamsSystem;
+        system.run_now(&test_world);
+        test_world.maintain();
+        system.run_now(&test_world);
+        test_world.maintain();
+
+        let derived = test_world.read_storage::<DerivedBeam>();
+        assert_eq!((&derived).join().count(), 1);
+        let paths = test_world.read_storage::<BeamPath>();
+        let entity = paths.get(source).unwrap().derived_beams()[0];
+        assert_eq!(derived.get(entity).unwrap().source, source);
+        assert_eq!(derived.get(entity).unwrap().pass, 1);
+
+        let beams = test_world.read_storage::<GaussianBeam>();
+        assert_approx_eq!(beams.get(entity).unwrap().power, 0.9, 1e-12);
+        let cooling = test_world.read_storage::<CoolingLight>();
+        assert_eq!(cooling.get(entity).unwrap().polarization, 1);
+        let polarizations = test_world.read_storage::<Polarization>();
+        let helicity = |p: &Polarization, direction: Vector3<f64>| {
+            let u = p.vector;
+            let cross = u.map(|c| c.conj()).cross(&u);
+            Vector3::new(cross[0].im, cross[1].im, cross[2].im).dot(&direction)
+        };
+        assert_approx_eq!(
+            helicity(polarizations.get(entity).unwrap(), -Vector3::z()),
+            helicity(polarizations.get(source).unwrap(), Vector3::z()),
+            1e-12
+        );
+        assert!(test_world
+            .read_storage::<LaserIndex>()
+            .get(entity)
+            .is_some());
+    }
+
+    #[test]
+    fn test_derived_beams_follow_source() {
+        let mut test_world = World::new();
+        test_world.register::<BeamPath>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<DipoleLight>();
+        test_world.register::<Polarization>();
+        test_world.register::<DerivedBeam>();
+        test_world.register::<LaserIndex>();
+
+        let source = test_world
+            .create_entity()
+            .with(collimated_beam())
+            .with(DipoleLight {
+                wavelength: 1064e-9,
+            })
+            .with(BeamPath::retro_reflected(0.2, 1.0, 1.0))
+            .build();
+
+        let mut system = UpdateDerivedBeamsSystem;
+        system.run_now(&test_world);
+        test_world.maintain();
+        let derived = test_world
+            .read_storage::<BeamPath>()
+            .get(source)
+            .unwrap()
+            .derived_beams()[0];
+
+        // Changes to the source beam are applied to the same derived entity.
+        test_world
+            .write_storage::<GaussianBeam>()
+            .get_mut(source)
+            .unwrap()
+            .power = 2.0;
+        system.run_now(&test_world);
+        test_world.maintain();
+        assert_eq!(
+            test_world
+                .read_storage::<BeamPath>()
+                .get(source)
+                .unwrap()
+                .derived_beams(),
+            &[derived]
+        );
+        let power = test_world
+            .read_storage::<GaussianBeam>()
+            .get(derived)
+            .unwrap()
+            .power;
+        assert_approx_eq!(power, 2.0, 1e-12);
+
+        // Derived beams are deleted with their source.
+        test_world.delete_entity(source).unwrap();
+        system.run_now(&test_world);
+        test_world.maintain();
+        assert!(!test_world.is_alive(derived));
+        assert_eq!(
+            (&test_world.read_storage::<DerivedBeam>()).join().count(),
+            0
+        );
+    }
+}
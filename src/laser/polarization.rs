@@ -0,0 +1,212 @@
+//! Polarization of laser beams, described by a complex field vector.
+//!
+//! Partially polarized light is described by the `degree` of polarization, with the remaining
+//! fraction of the power unpolarized. Beams can be created from a Jones vector or from Stokes
+//! parameters.
+
+use super::frame::Frame;
+use nalgebra::{Complex, Vector3};
+use specs::{Component, HashMapStorage};
+
+/// A component describing the polarization of a laser beam.
+///
+/// The polarization is stored as a normalised complex vector in the lab frame, which is
+/// perpendicular to the beam direction. The electric field of the beam is proportional to
+/// `Re(vector * exp(-i omega t))`.
+#[derive(Clone, Copy)]
+pub struct Polarization {
+    /// Normalised complex polarization vector, in the lab frame.
+    pub vector: Vector3<Complex<f64>>,
+    /// Fraction of the power which is polarized along `vector`, in the range [0,1]. The rest is unpolarized.
+    pub degree: f64,
+}
+impl Component for Polarization {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl Polarization {
+    /// Creates a polarization from a complex vector, which is normalised.
+    pub fn new(vector: Vector3<Complex<f64>>) -> Self {
+        let norm = vector.iter().map(|c| c.norm_sqr()).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            panic!("Polarization vector must be non-zero.");
+        }
+        Polarization {
+            vector: vector.map(|c| c / norm),
+            degree: 1.0,
+        }
+    }
+
+    /// Linear polarization along the given axis.
+    pub fn linear(axis: Vector3<f64>) -> Self {
+        Polarization::new(axis.map(|x| Complex::new(x, 0.0)))
+    }
+
+    /// Circular polarization with the given helicity with respect to the beam direction.
+    ///
+    /// # Arguments
+    ///
+    /// `direction`: propagation direction of the beam.
+    ///
+    /// `helicity`: +1 for light that drives σ+ transitions when the quantisation axis is
+    /// parallel to `direction`, -1 for σ-.
+    pub fn circular(direction: Vector3<f64>, helicity: i32) -> Self {
+        let frame = Frame::default_for_direction(direction);
+        Polarization::from_jones(
+            &frame,
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, helicity.signum() as f64),
+        )
+    }
+
+    /// Creates a polarization from the components of a Jones vector.
+    ///
+    /// # Arguments
+    ///
+    /// `frame`: the beam frame, which defines the axes of the Jones vector.
+    ///
+    /// `x`: field component along `frame.x_vector`.
+    ///
+    /// `y`: field component along `frame.y_vector`.
+    pub fn from_jones(frame: &Frame, x: Complex<f64>, y: Complex<f64>) -> Self {
+        Polarization::new(
+            frame.x_vector.map(|v| Complex::new(v, 0.0)) * x
+                + frame.y_vector.map(|v| Complex::new(v, 0.0)) * y,
+        )
+    }
+
+    /// Creates a polarization from normalised Stokes parameters, `S1/S0`, `S2/S0` and `S3/S0`.
+    ///
+    /// The degree of polarization is `sqrt(s1^2 + s2^2 + s3^2)`, which must not exceed 1.
+    ///
+    /// # Arguments
+    ///
+    /// `frame`: the beam frame. `s1 = 1` is linear polarization along `frame.x_vector`, and
+    /// `s2 = 1` is linear polarization at 45 degrees between the x and y axes.
+    ///
+    /// `s3`: +1 for circular polarization with positive helicity, see `circular`.
+    pub fn from_stokes(frame: &Frame, s1: f64, s2: f64, s3: f64) -> Self {
+        let degree = (s1 * s1 + s2 * s2 + s3 * s3).sqrt();
+        if degree > 1.0 + 1e-9 {
+            panic!("Stokes parameters describe a degree of polarization greater than 1.");
+        }
+        let (s1, s2, s3) = if degree > 0.0 {
+            (s1 / degree, s2 / degree, s3 / degree)
+        } else {
+            (1.0, 0.0, 0.0)
+        };
+        let phase = s3.atan2(s2);
+        let y = ((1.0 - s1) / 2.0).max(0.0).sqrt();
+        let mut polarization = Polarization::from_jones(
+            frame,
+            Complex::new(((1.0 + s1) / 2.0).sqrt(), 0.0),
+            Complex::new(y * phase.cos(), y * phase.sin()),
+        );
+        polarization.degree = degree.min(1.0);
+        polarization
+    }
+
+    /// Calculates the fractions of the beam power which drive σ+, σ- and π transitions.
+    ///
+    /// # Arguments
+    ///
+    /// `direction`: propagation direction of the beam.
+    ///
+    /// `quantization_axis`: the quantization axis, eg the local magnetic field.
+    pub fn transition_fractions(
+        &self,
+        direction: &Vector3<f64>,
+        quantization_axis: &Vector3<f64>,
+    ) -> (f64, f64, f64) {
+        let axis = quantization_axis.normalize();
+        let axis_complex = axis.map(|x| Complex::new(x, 0.0));
+        let pi = self.vector.dot(&axis_complex).norm_sqr();
+        let cross = self.vector.map(|c| c.conj()).cross(&self.vector);
+        let circular = Vector3::new(cross[0].im, cross[1].im, cross[2].im).dot(&axis);
+
+        // Unpolarized light is an equal mixture of two orthogonal transverse polarizations.
+        let cos_theta = direction.normalize().dot(&axis);
+        let unpolarized_pi = (1.0 - cos_theta.powi(2)) / 2.0;
+
+        let pi = self.degree * pi + (1.0 - self.degree) * unpolarized_pi;
+        let circular = self.degree * circular;
+        ((1.0 - pi + circular) / 2.0, (1.0 - pi - circular) / 2.0, pi)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_polarization_is_normalised() {
+        let polarization = Polarization::linear(Vector3::new(1.0, 1.0, 0.0));
+        assert_approx_eq!(polarization.vector[0].re, 1.0 / 2.0_f64.sqrt());
+
+        let circular = Polarization::circular(Vector3::z(), 1);
+        let norm: f64 = circular.vector.iter().map(|c| c.norm_sqr()).sum();
+        assert_approx_eq!(norm, 1.0);
+        assert_approx_eq!(circular.vector[2].norm_sqr(), 0.0);
+    }
+
+    #[test]
+    fn test_stokes_parameters() {
+        let frame = Frame::default_for_direction(Vector3::z());
+        let circular = Polarization::from_stokes(&frame, 0.0, 0.0, 1.0);
+        let expected = Polarization::circular(Vector3::z(), 1);
+        for i in 0..3 {
+            assert_approx_eq!(
+                (circular.vector[i] - expected.vector[i]).norm_sqr(),
+                0.0,
+                1e-12
+            );
+        }
+
+        let linear = Polarization::from_stokes(&frame, 1.0, 0.0, 0.0);
+        let expected = Polarization::linear(frame.x_vector);
+        for i in 0..3 {
+            assert_approx_eq!(
+                (linear.vector[i] - expected.vector[i]).norm_sqr(),
+                0.0,
+                1e-12
+            );
+        }
+
+        let partial = Polarization::from_stokes(&frame, 0.0, 0.3, 0.4);
+        assert_approx_eq!(partial.degree, 0.5, 1e-12);
+    }
+
+    #[test]
+    fn test_transition_fractions() {
+        // Circularly polarized light along the quantization axis drives only σ+ transitions.
+        let direction = Vector3::z();
+        let circular = Polarization::circular(direction, 1);
+        let (plus, minus, pi) = circular.transition_fractions(&direction, &Vector3::z());
+        assert_approx_eq!(plus, 1.0, 1e-12);
+        assert_approx_eq!(minus, 0.0, 1e-12);
+        assert_approx_eq!(pi, 0.0, 1e-12);
+
+        // At an angle, the fractions match the projection for a helicity of +1.
+        let axis = Vector3::new(1.0, 0.0, 1.0);
+        let cos_theta = 0.5_f64.sqrt();
+        let (plus, minus, pi) = circular.transition_fractions(&direction, &axis);
+        assert_approx_eq!(plus, 0.25 * (1.0 + cos_theta).powi(2), 1e-12);
+        assert_approx_eq!(minus, 0.25 * (1.0 - cos_theta).powi(2), 1e-12);
+        assert_approx_eq!(pi, 0.5 * (1.0 - cos_theta.powi(2)), 1e-12);
+
+        // Light linearly polarized along the quantization axis drives only π transitions.
+        let linear = Polarization::linear(Vector3::x());
+        let (plus, minus, pi) = linear.transition_fractions(&direction, &Vector3::x());
+        assert_approx_eq!(plus + minus, 0.0, 1e-12);
+        assert_approx_eq!(pi, 1.0, 1e-12);
+
+        // Unpolarized light along the quantization axis drives σ+ and σ- equally.
+        let frame = Frame::default_for_direction(direction);
+        let unpolarized = Polarization::from_stokes(&frame, 0.0, 0.0, 0.0);
+        let (plus, minus, pi) = unpolarized.transition_fractions(&direction, &Vector3::z());
+        assert_approx_eq!(plus, 0.5, 1e-12);
+        assert_approx_eq!(minus, 0.5, 1e-12);
+        assert_approx_eq!(pi, 0.0, 1e-12);
+    }
+}
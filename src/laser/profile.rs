@@ -0,0 +1,514 @@
+//! Non-gaussian transverse beam profiles.
+//!
+//! A `BeamProfile` component replaces the transverse intensity distribution of a `GaussianBeam`.
+//! The `GaussianBeam` still defines the position, direction, power and divergence of the beam;
+//! the profile is scaled with the local beam radius `w(z) = e_radius * sqrt(1 + (z/z_R)^2)`
+//! and normalised so that the total power is unchanged.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::frame::Frame;
+use super::gaussian::{CircularMask, GaussianBeam};
+use crate::atom::Position;
+use crate::constant::PI;
+use crate::maths;
+use nalgebra::Vector3;
+use specs::{Component, HashMapStorage};
+
+/// Fractional step, relative to the local beam radius, used to calculate gradients numerically.
+const GRADIENT_STEP: f64 = 1e-4;
+
+/// A component that defines the transverse intensity profile of a `GaussianBeam`.
+///
+/// The transverse axes of the profile are given by the beam's `Frame`. If the beam has no
+/// `Frame`, the one given by `Frame::default_for_direction` is used.
+#[derive(Clone)]
+pub enum BeamProfile {
+    /// Super-gaussian profile, `I ∝ exp(-(r/w)^(2 order))`. An order of 1 is a gaussian beam.
+    SuperGaussian { order: f64 },
+    /// Uniform intensity within a radius `w`, and zero outside.
+    FlatTop,
+    /// Laguerre-Gaussian mode with azimuthal index `l` and radial index `p`.
+    ///
+    /// Modes with `l != 0` have a dark centre, and are suitable for plug beams and ring traps.
+    LaguerreGaussian { l: i32, p: u32 },
+    /// Hermite-Gaussian mode with indices `m` and `n` along the frame x and y axes respectively.
+    HermiteGaussian { m: u32, n: u32 },
+    /// An arbitrary transverse intensity distribution, see `IntensityMap`.
+    ///
+    /// The map does not diffract; it is the same for every position along the beam.
+    IntensityMap(Arc<IntensityMap>),
+}
+impl Component for BeamProfile {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl BeamProfile {
+    /// Returns the intensity of the profile, normalised so that the integral over
+    /// the transverse plane is `PI`.
+    ///
+    /// # Arguments
+    ///
+    /// `x`, `y`: transverse coordinates in units of the local beam radius.
+    fn shape(&self, x: f64, y: f64) -> f64 {
+        let r_squared = x * x + y * y;
+        match self {
+            BeamProfile::SuperGaussian { order } => {
+                (-r_squared.powf(*order)).exp() / maths::gamma(1.0 + 1.0 / order)
+            }
+            BeamProfile::FlatTop => {
+                if r_squared < 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            BeamProfile::LaguerreGaussian { l, p } => {
+                let l = l.abs() as f64;
+                let p = *p as f64;
+                maths::gamma(p + 1.0) / maths::gamma(p + l + 1.0)
+                    * r_squared.powf(l)
+                    * maths::laguerre(p as u32, l, r_squared).powi(2)
+                    * (-r_squared).exp()
+            }
+            BeamProfile::HermiteGaussian { m, n } => {
+                maths::hermite(*m, x).powi(2) * maths::hermite(*n, y).powi(2) * (-r_squared).exp()
+                    / (2.0_f64.powi((m + n) as i32)
+                        * maths::gamma(*m as f64 + 1.0)
+                        * maths::gamma(*n as f64 + 1.0))
+            }
+            BeamProfile::IntensityMap(_) => {
+                unreachable!("Intensity maps are not scaled by the beam radius.")
+            }
+        }
+    }
+}
+
+/// A transverse intensity distribution defined on a regular grid, for example from a camera image.
+///
+/// Row `i` and column `j` of the map correspond to the pixel at
+/// `x = (j - (columns-1)/2) * pixel_size` and `y = ((rows-1)/2 - i) * pixel_size`,
+/// so that the centre of the map lies on the beam axis and the first row is at the top.
+/// Intensities between pixels are interpolated bilinearly, and are zero outside the map.
+pub struct IntensityMap {
+    /// Number of rows in the map.
+    pub rows: usize,
+    /// Number of columns in the map.
+    pub columns: usize,
+    /// Size of each pixel, in units of m.
+    pub pixel_size: f64,
+    /// Intensity per unit power at each pixel, in units of 1/m^2, stored row by row.
+    values: Vec<f64>,
+}
+
+impl IntensityMap {
+    /// Creates an intensity map from pixel values stored row by row.
+    ///
+    /// The values are normalised so that the map integrates to unit power.
+    ///
+    /// Panics if the number of values does not match the size of the map, or if the total intensity is not
+    /// positive. Use `from_csv` or `from_pgm` to load maps from files, which return an error instead.
+    pub fn new(rows: usize, columns: usize, pixel_size: f64, values: Vec<f64>) -> Self {
+        assert_eq!(
+            values.len(),
+            rows * columns,
+            "Number of values does not match the size of the intensity map."
+        );
+        let total: f64 = values.iter().sum::<f64>() * pixel_size.powi(2);
+        if total <= 0.0 {
+            panic!("Intensity map must have a positive total intensity.");
+        }
+        IntensityMap {
+            rows,
+            columns,
+            pixel_size,
+            values: values.iter().map(|v| v / total).collect(),
+        }
+    }
+
+    /// Checks that pixel values read from a file form a valid map before creating it.
+    fn from_file_values(
+        rows: usize,
+        columns: usize,
+        pixel_size: f64,
+        values: Vec<f64>,
+    ) -> io::Result<Self> {
+        if rows == 0 || columns == 0 {
+            return Err(invalid_data("Intensity map is empty."));
+        }
+        if values.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(invalid_data(
+                "Intensity map values must be finite and non-negative.",
+            ));
+        }
+        if !values.iter().any(|v| *v > 0.0) {
+            return Err(invalid_data(
+                "Intensity map must have a positive total intensity.",
+            ));
+        }
+        Ok(IntensityMap::new(rows, columns, pixel_size, values))
+    }
+
+    /// Loads an intensity map from a CSV file, with one row of pixel values per line.
+    ///
+    /// # Arguments
+    ///
+    /// `path`: location of the csv file.
+    ///
+    /// `pixel_size`: size of each pixel, in units of m.
+    pub fn from_csv<P: AsRef<Path>>(path: P, pixel_size: f64) -> io::Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .trim(csv::Trim::All)
+            .from_path(path)?;
+        let mut values = Vec::new();
+        let mut columns = 0;
+        let mut rows = 0;
+        for record in reader.records() {
+            let record = record?;
+            if rows == 0 {
+                columns = record.len();
+            } else if record.len() != columns {
+                return Err(invalid_data(
+                    "Rows of intensity map have different lengths.",
+                ));
+            }
+            for field in record.iter() {
+                values.push(
+                    field
+                        .parse::<f64>()
+                        .map_err(|_| invalid_data("Could not parse intensity map value."))?,
+                );
+            }
+            rows += 1;
+        }
+        IntensityMap::from_file_values(rows, columns, pixel_size, values)
+    }
+
+    /// Loads an intensity map from a greyscale camera image in the PGM (portable graymap) format.
+    ///
+    /// Both plain (`P2`) and binary (`P5`) files are supported, with 8 or 16 bits per pixel.
+    ///
+    /// # Arguments
+    ///
+    /// `path`: location of the image file.
+    ///
+    /// `pixel_size`: size of each camera pixel at the position of the atoms, in units of m.
+    pub fn from_pgm<P: AsRef<Path>>(path: P, pixel_size: f64) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+
+        // Parse the header: magic number, width, height and maximum value.
+        let mut header = Vec::new();
+        let mut position = 0;
+        while header.len() < 4 {
+            while position < bytes.len() && bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            if position < bytes.len() && bytes[position] == b'#' {
+                while position < bytes.len() && bytes[position] != b'\n' {
+                    position += 1;
+                }
+                continue;
+            }
+            let start = position;
+            while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            if start == position {
+                return Err(invalid_data("Incomplete PGM header."));
+            }
+            header.push(String::from_utf8_lossy(&bytes[start..position]).to_string());
+        }
+        let parse = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| invalid_data("Could not parse PGM header."))
+        };
+        let columns = parse(&header[1])?;
+        let rows = parse(&header[2])?;
+        let max_value = parse(&header[3])?;
+
+        let values: Vec<f64> = match header[0].as_str() {
+            "P2" => String::from_utf8_lossy(&bytes[position..])
+                .split_whitespace()
+                .map(|s| {
+                    s.parse::<f64>()
+                        .map_err(|_| invalid_data("Could not parse PGM pixel value."))
+                })
+                .collect::<io::Result<Vec<f64>>>()?,
+            "P5" => {
+                // A single whitespace character separates the header from the pixel data.
+                let data = &bytes[(position + 1).min(bytes.len())..];
+                if max_value < 256 {
+                    data.iter().map(|&b| b as f64).collect()
+                } else {
+                    data.chunks_exact(2)
+                        .map(|b| u16::from_be_bytes([b[0], b[1]]) as f64)
+                        .collect()
+                }
+            }
+            _ => return Err(invalid_data("Only P2 and P5 PGM files are supported.")),
+        };
+        if values.len() < rows * columns {
+            return Err(invalid_data("PGM file contains too few pixels."));
+        }
+        IntensityMap::from_file_values(rows, columns, pixel_size, values[..rows * columns].to_vec())
+    }
+
+    /// Returns the intensity per unit power at transverse coordinates `x`, `y` (in m).
+    pub fn get_value(&self, x: f64, y: f64) -> f64 {
+        let column = x / self.pixel_size + (self.columns as f64 - 1.0) / 2.0;
+        let row = (self.rows as f64 - 1.0) / 2.0 - y / self.pixel_size;
+        if column < 0.0
+            || row < 0.0
+            || column > (self.columns - 1) as f64
+            || row > (self.rows - 1) as f64
+        {
+            return 0.0;
+        }
+        let j = (column.floor() as usize).min(self.columns.saturating_sub(2));
+        let i = (row.floor() as usize).min(self.rows.saturating_sub(2));
+        let value = |i: usize, j: usize| {
+            self.values[i.min(self.rows - 1) * self.columns + j.min(self.columns - 1)]
+        };
+        let u = column - j as f64;
+        let v = row - i as f64;
+        (1.0 - u) * (1.0 - v) * value(i, j)
+            + u * (1.0 - v) * value(i, j + 1)
+            + (1.0 - u) * v * value(i + 1, j)
+            + u * v * value(i + 1, j + 1)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Returns the intensity of a beam with the given transverse profile at the specified position.
+pub fn get_beam_profile_intensity(
+    beam: &GaussianBeam,
+    profile: &BeamProfile,
+    pos: &Position,
+    mask: Option<&CircularMask>,
+    frame: &Frame,
+) -> f64 {
+    let (x, y, z) = maths::get_relative_coordinates_line_point(
+        &pos.pos,
+        &beam.intersection,
+        &beam.direction.normalize(),
+        frame,
+    );
+    if let Some(mask) = mask {
+        if (x * x + y * y).sqrt() < mask.radius {
+            return 0.0;
+        }
+    }
+    match profile {
+        BeamProfile::IntensityMap(map) => beam.power * map.get_value(x, y),
+        _ => {
+            let radius_squared = beam.e_radius.powi(2) * (1.0 + (z / beam.rayleigh_range).powi(2));
+            let radius = radius_squared.sqrt();
+            beam.power / PI / radius_squared * profile.shape(x / radius, y / radius)
+        }
+    }
+}
+
+/// Returns the intensity gradient of a beam with the given transverse profile at the specified position.
+///
+/// The gradient is calculated numerically by central differences.
+pub fn get_beam_profile_intensity_gradient(
+    beam: &GaussianBeam,
+    profile: &BeamProfile,
+    pos: &Position,
+    mask: Option<&CircularMask>,
+    frame: &Frame,
+) -> Vector3<f64> {
+    let step = match profile {
+        BeamProfile::IntensityMap(map) => GRADIENT_STEP * map.pixel_size,
+        _ => GRADIENT_STEP * beam.e_radius,
+    };
+    let mut gradient = Vector3::zeros();
+    for axis in 0..3 {
+        let mut offset = Vector3::zeros();
+        offset[axis] = step;
+        let forward = Position {
+            pos: pos.pos + offset,
+        };
+        let backward = Position {
+            pos: pos.pos - offset,
+        };
+        gradient[axis] = (get_beam_profile_intensity(beam, profile, &forward, mask, frame)
+            - get_beam_profile_intensity(beam, profile, &backward, mask, frame))
+            / (2.0 * step);
+    }
+    gradient
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+    use crate::laser::gaussian::{
+        get_gaussian_beam_intensity, get_gaussian_beam_intensity_gradient,
+    };
+    use assert_approx_eq::assert_approx_eq;
+
+    fn test_beam() -> GaussianBeam {
+        GaussianBeam {
+            direction: Vector3::z(),
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 50e-6,
+            power: 1.0,
+            rayleigh_range: 1e-2,
+            ellipticity: 0.0,
+        }
+    }
+
+    /// Numerically integrates the intensity over the transverse plane at `z`.
+    fn total_power(beam: &GaussianBeam, profile: &BeamProfile, z: f64) -> f64 {
+        let frame = Frame::default_for_direction(beam.direction);
+        let n = 400;
+        let extent = 8.0 * beam.e_radius;
+        let step = 2.0 * extent / n as f64;
+        let mut total = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let pos = Position {
+                    pos: Vector3::new(
+                        -extent + (i as f64 + 0.5) * step,
+                        -extent + (j as f64 + 0.5) * step,
+                        z,
+                    ),
+                };
+                total += get_beam_profile_intensity(beam, profile, &pos, None, &frame);
+            }
+        }
+        total * step * step
+    }
+
+    #[test]
+    fn test_profiles_conserve_power() {
+        let beam = test_beam();
+        for profile in [
+            BeamProfile::SuperGaussian { order: 4.0 },
+            BeamProfile::FlatTop,
+            BeamProfile::LaguerreGaussian { l: 1, p: 0 },
+            BeamProfile::LaguerreGaussian { l: 2, p: 1 },
+            BeamProfile::HermiteGaussian { m: 1, n: 2 },
+        ]
+        .iter()
+        {
+            assert_approx_eq!(total_power(&beam, profile, 5e-3), beam.power, 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_low_order_modes_match_gaussian() {
+        let beam = test_beam();
+        let frame = Frame::default_for_direction(beam.direction);
+        let pos = Position {
+            pos: Vector3::new(20e-6, -30e-6, 4e-3),
+        };
+        let gaussian = get_gaussian_beam_intensity(&beam, &pos, None, None);
+        let gaussian_gradient = get_gaussian_beam_intensity_gradient(&beam, &pos, &frame);
+        for profile in [
+            BeamProfile::SuperGaussian { order: 1.0 },
+            BeamProfile::LaguerreGaussian { l: 0, p: 0 },
+            BeamProfile::HermiteGaussian { m: 0, n: 0 },
+        ]
+        .iter()
+        {
+            assert_approx_eq!(
+                get_beam_profile_intensity(&beam, profile, &pos, None, &frame),
+                gaussian,
+                1e-6 * gaussian
+            );
+            let gradient = get_beam_profile_intensity_gradient(&beam, profile, &pos, None, &frame);
+            assert_approx_eq!(gradient[0], gaussian_gradient[0], 1e-4 * gradient.norm());
+            assert_approx_eq!(gradient[1], gaussian_gradient[1], 1e-4 * gradient.norm());
+            assert_approx_eq!(gradient[2], gaussian_gradient[2], 1e-4 * gradient.norm());
+        }
+    }
+
+    #[test]
+    fn test_donut_beam_is_dark_on_axis() {
+        let beam = test_beam();
+        let frame = Frame::default_for_direction(beam.direction);
+        let profile = BeamProfile::LaguerreGaussian { l: 1, p: 0 };
+        let on_axis = Position {
+            pos: Vector3::new(0.0, 0.0, 0.0),
+        };
+        let on_ring = Position {
+            pos: Vector3::new(beam.e_radius, 0.0, 0.0),
+        };
+        assert_approx_eq!(
+            get_beam_profile_intensity(&beam, &profile, &on_axis, None, &frame),
+            0.0
+        );
+        assert!(get_beam_profile_intensity(&beam, &profile, &on_ring, None, &frame) > 0.0);
+    }
+
+    #[test]
+    fn test_intensity_map() {
+        // A 3x3 map with a single bright pixel above the centre.
+        let pixel_size = 1e-6;
+        let map = IntensityMap::new(
+            3,
+            3,
+            pixel_size,
+            vec![0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        );
+        assert_approx_eq!(map.get_value(0.0, pixel_size), 1.0 / pixel_size.powi(2));
+        assert_approx_eq!(
+            map.get_value(0.0, 0.5 * pixel_size),
+            0.5 / pixel_size.powi(2)
+        );
+        assert_approx_eq!(map.get_value(0.0, 0.0), 0.0);
+        assert_approx_eq!(map.get_value(5.0 * pixel_size, 0.0), 0.0);
+
+        let path = temp_file("intensity_map.pgm");
+        std::fs::write(&path, "P2\n# test image\n3 3\n255\n0 255 0\n0 0 0\n0 0 0\n").unwrap();
+        let loaded = IntensityMap::from_pgm(&path, pixel_size).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_approx_eq!(loaded.get_value(0.0, pixel_size), 1.0 / pixel_size.powi(2));
+
+        let path = temp_file("intensity_map.csv");
+        std::fs::write(&path, "0, 2, 0\n0, 0, 0\n0, 0, 0\n").unwrap();
+        let loaded = IntensityMap::from_csv(&path, pixel_size).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_approx_eq!(loaded.get_value(0.0, pixel_size), 1.0 / pixel_size.powi(2));
+    }
+
+    /// Returns a path in the temporary directory which is unique to this test process.
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("atomecs_test_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_invalid_intensity_maps_are_rejected() {
+        let invalid = [
+            ("empty.csv", ""),
+            ("dark.csv", "0, 0\n0, 0\n"),
+            ("negative.csv", "1, -2\n0, 0\n"),
+            ("empty.pgm", "P2\n0 0\n255\n"),
+            ("dark.pgm", "P2\n2 2\n255\n0 0\n0 0\n"),
+        ];
+        for (name, contents) in invalid.iter() {
+            let path = temp_file(name);
+            std::fs::write(&path, contents).unwrap();
+            let loaded = if name.ends_with(".csv") {
+                IntensityMap::from_csv(&path, 1e-6)
+            } else {
+                IntensityMap::from_pgm(&path, 1e-6)
+            };
+            std::fs::remove_file(&path).unwrap();
+            match loaded {
+                Err(error) => assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", name),
+                Ok(_) => panic!("Invalid intensity map {} was accepted.", name),
+            }
+        }
+    }
+}
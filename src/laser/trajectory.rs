@@ -0,0 +1,285 @@
+//! Time-dependent motion and power of laser beams, eg for moving optical tweezers.
+//!
+//! Add a `BeamTrajectory` to a `GaussianBeam` entity to move the beam's `intersection` along a
+//! path, and a `PowerModulation` to vary its `power`. Unlike a `Ramp<GaussianBeam>`, which only
+//! interpolates linearly between keyframes, trajectories can follow smooth or arbitrary paths.
+
+use std::sync::Arc;
+
+use super::gaussian::GaussianBeam;
+use crate::constant::PI;
+use crate::integrator::{Step, Timestep};
+use nalgebra::Vector3;
+use specs::prelude::*;
+
+/// Linearly interpolates a table of `(time, value)` pairs, holding the first and last values outside the table.
+///
+/// Returns `None` if the table is empty.
+fn interpolate_table<T>(table: &[(f64, T)], time: f64) -> Option<T>
+where
+    T: Copy + std::ops::Mul<f64, Output = T> + std::ops::Add<Output = T>,
+{
+    let &(first_time, first) = table.first()?;
+    if time <= first_time {
+        return Some(first);
+    }
+    for window in table.windows(2) {
+        let (t1, a) = window[0];
+        let (t2, b) = window[1];
+        if time <= t2 {
+            let amount = (time - t1) / (t2 - t1);
+            return Some(a * (1.0 - amount) + b * amount);
+        }
+    }
+    table.last().map(|&(_, value)| value)
+}
+
+/// A component that moves the focus of a `GaussianBeam` along a path.
+#[derive(Clone)]
+pub enum BeamTrajectory {
+    /// Moves from `start` to `end` with the minimum-jerk profile, which starts and ends with zero velocity and acceleration.
+    MinimumJerk {
+        start: Vector3<f64>,
+        end: Vector3<f64>,
+        /// Time at which the move starts, in s.
+        start_time: f64,
+        /// Duration of the move, in s.
+        duration: f64,
+    },
+    /// Oscillates about `centre` as `centre + amplitude * sin(2π frequency t + phase)`.
+    Sinusoidal {
+        centre: Vector3<f64>,
+        amplitude: Vector3<f64>,
+        /// Frequency of the oscillation, in Hz.
+        frequency: f64,
+        /// Phase of the oscillation, in rad.
+        phase: f64,
+    },
+    /// Linearly interpolates a list of `(time, position)` pairs, which must be sorted by time.
+    ///
+    /// An empty table does not move the beam.
+    Table(Vec<(f64, Vector3<f64>)>),
+    /// A user-defined function which returns the position at a given time.
+    Custom(Arc<dyn Fn(f64) -> Vector3<f64> + Send + Sync>),
+}
+impl Component for BeamTrajectory {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl BeamTrajectory {
+    /// Gets the position of the beam focus at the given time, in s.
+    ///
+    /// Returns `None` if the trajectory does not define a position, ie for an empty `Table`.
+    pub fn get_position(&self, time: f64) -> Option<Vector3<f64>> {
+        match self {
+            BeamTrajectory::MinimumJerk {
+                start,
+                end,
+                start_time,
+                duration,
+            } => {
+                let s = ((time - start_time) / duration).clamp(0.0, 1.0);
+                let fraction = s.powi(3) * (10.0 - 15.0 * s + 6.0 * s * s);
+                Some(start + (end - start) * fraction)
+            }
+            BeamTrajectory::Sinusoidal {
+                centre,
+                amplitude,
+                frequency,
+                phase,
+            } => Some(centre + amplitude * (2.0 * PI * frequency * time + phase).sin()),
+            BeamTrajectory::Table(table) => interpolate_table(table, time),
+            BeamTrajectory::Custom(function) => Some(function(time)),
+        }
+    }
+}
+
+/// A component that varies the power of a `GaussianBeam` in time.
+#[derive(Clone)]
+pub enum PowerModulation {
+    /// Modulates the power as `mean * (1 + depth * sin(2π frequency t + phase))`.
+    Sinusoidal {
+        /// Mean power, in W.
+        mean: f64,
+        /// Fractional modulation depth.
+        depth: f64,
+        /// Frequency of the modulation, in Hz.
+        frequency: f64,
+        /// Phase of the modulation, in rad.
+        phase: f64,
+    },
+    /// Linearly interpolates a list of `(time, power)` pairs, which must be sorted by time.
+    ///
+    /// An empty table does not change the power of the beam.
+    Table(Vec<(f64, f64)>),
+    /// A user-defined function which returns the power, in W, at a given time.
+    Custom(Arc<dyn Fn(f64) -> f64 + Send + Sync>),
+}
+impl Component for PowerModulation {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl PowerModulation {
+    /// Gets the power of the beam at the given time, in s.
+    ///
+    /// Returns `None` if the modulation does not define a power, ie for an empty `Table`.
+    pub fn get_power(&self, time: f64) -> Option<f64> {
+        match self {
+            PowerModulation::Sinusoidal {
+                mean,
+                depth,
+                frequency,
+                phase,
+            } => Some(mean * (1.0 + depth * (2.0 * PI * frequency * time + phase).sin())),
+            PowerModulation::Table(table) => interpolate_table(table, time),
+            PowerModulation::Custom(function) => Some(function(time)),
+        }
+    }
+}
+
+/// Updates the `intersection` of each `GaussianBeam` with a `BeamTrajectory`.
+pub struct UpdateBeamTrajectorySystem;
+impl<'a> System<'a> for UpdateBeamTrajectorySystem {
+    type SystemData = (
+        WriteStorage<'a, GaussianBeam>,
+        ReadStorage<'a, BeamTrajectory>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+
+    fn run(&mut self, (mut beams, trajectories, timestep, step): Self::SystemData) {
+        let current_time = step.n as f64 * timestep.delta;
+        for (beam, trajectory) in (&mut beams, &trajectories).join() {
+            if let Some(position) = trajectory.get_position(current_time) {
+                beam.intersection = position;
+            }
+        }
+    }
+}
+
+/// Updates the `power` of each `GaussianBeam` with a `PowerModulation`.
+pub struct UpdatePowerModulationSystem;
+impl<'a> System<'a> for UpdatePowerModulationSystem {
+    type SystemData = (
+        WriteStorage<'a, GaussianBeam>,
+        ReadStorage<'a, PowerModulation>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+
+    fn run(&mut self, (mut beams, modulations, timestep, step): Self::SystemData) {
+        let current_time = step.n as f64 * timestep.delta;
+        for (beam, modulation) in (&mut beams, &modulations).join() {
+            if let Some(power) = modulation.get_power(current_time) {
+                beam.power = power;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_trajectories() {
+        let start = Vector3::new(0.0, 0.0, 0.0);
+        let end = Vector3::new(10.0e-6, 0.0, 0.0);
+        let jerk = BeamTrajectory::MinimumJerk {
+            start,
+            end,
+            start_time: 1.0e-3,
+            duration: 2.0e-3,
+        };
+        assert_eq!(jerk.get_position(0.0).unwrap(), start);
+        assert_approx_eq!(jerk.get_position(2.0e-3).unwrap()[0], 5.0e-6, 1e-12);
+        assert_eq!(jerk.get_position(5.0e-3).unwrap(), end);
+
+        let table = BeamTrajectory::Table(vec![(0.0, start), (1.0, end), (2.0, start)]);
+        assert_approx_eq!(table.get_position(0.25).unwrap()[0], 2.5e-6, 1e-12);
+        assert_approx_eq!(table.get_position(1.5).unwrap()[0], 5.0e-6, 1e-12);
+        assert_eq!(table.get_position(3.0).unwrap(), start);
+
+        let custom = BeamTrajectory::Custom(Arc::new(|t| Vector3::new(t, 2.0 * t, 0.0)));
+        assert_eq!(
+            custom.get_position(2.0).unwrap(),
+            Vector3::new(2.0, 4.0, 0.0)
+        );
+
+        assert!(BeamTrajectory::Table(vec![]).get_position(1.0).is_none());
+        assert!(PowerModulation::Table(vec![]).get_power(1.0).is_none());
+    }
+
+    #[test]
+    fn test_update_systems() {
+        let mut test_world = World::new();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<BeamTrajectory>();
+        test_world.register::<PowerModulation>();
+        test_world.insert(Timestep { delta: 1.0e-6 });
+        test_world.insert(Step { n: 250 });
+
+        let beam = test_world
+            .create_entity()
+            .with(GaussianBeam {
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius: 1.0e-6,
+                power: 1.0,
+                direction: Vector3::z(),
+                rayleigh_range: 1.0e-5,
+                ellipticity: 0.0,
+            })
+            .with(BeamTrajectory::Sinusoidal {
+                centre: Vector3::new(0.0, 0.0, 0.0),
+                amplitude: Vector3::new(1.0e-6, 0.0, 0.0),
+                frequency: 1.0e3,
+                phase: 0.0,
+            })
+            .with(PowerModulation::Table(vec![(0.0, 0.0), (1.0e-3, 2.0)]))
+            .build();
+
+        UpdateBeamTrajectorySystem.run_now(&test_world);
+        UpdatePowerModulationSystem.run_now(&test_world);
+        test_world.maintain();
+
+        let beams = test_world.read_storage::<GaussianBeam>();
+        let beam = beams.get(beam).unwrap();
+        assert_approx_eq!(beam.intersection[0], 1.0e-6, 1e-15);
+        assert_approx_eq!(beam.power, 0.5, 1e-12);
+    }
+
+    #[test]
+    fn test_empty_tables_leave_beam_unchanged() {
+        let mut test_world = World::new();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<BeamTrajectory>();
+        test_world.register::<PowerModulation>();
+        test_world.insert(Timestep { delta: 1.0e-6 });
+        test_world.insert(Step { n: 250 });
+
+        let intersection = Vector3::new(1.0e-6, 2.0e-6, 3.0e-6);
+        let beam = test_world
+            .create_entity()
+            .with(GaussianBeam {
+                intersection,
+                e_radius: 1.0e-6,
+                power: 1.0,
+                direction: Vector3::z(),
+                rayleigh_range: 1.0e-5,
+                ellipticity: 0.0,
+            })
+            .with(BeamTrajectory::Table(vec![]))
+            .with(PowerModulation::Table(vec![]))
+            .build();
+
+        UpdateBeamTrajectorySystem.run_now(&test_world);
+        UpdatePowerModulationSystem.run_now(&test_world);
+        test_world.maintain();
+
+        let beams = test_world.read_storage::<GaussianBeam>();
+        let beam = beams.get(beam).unwrap();
+        assert_eq!(beam.intersection, intersection);
+        assert_eq!(beam.power, 1.0);
+    }
+}
@@ -0,0 +1,421 @@
+//! Attenuation of cooling beams as they propagate through the atom cloud.
+//!
+//! Each atom removes light from a beam at a rate given by its `RateCoefficient` for that beam.
+//! The equivalent absorption cross section is used to calculate the optical depth of the atoms
+//! upstream of each atom, and the beam intensity seen by the atom is reduced accordingly.
+//!
+//! To calculate the optical depth, the cloud is divided into columns parallel to each beam, with a
+//! square cross section of side length `column_width`. The optical depth at an atom is the summed
+//! cross section of all atoms upstream in the same column, divided by the column area.
+//!
+//! Beams derived from a source beam by a [crate::laser::optics::BeamPath] have already passed
+//! through the whole cloud on earlier passes. The optical depth of the full column on each earlier
+//! pass is added to the optical depth of the derived beam.
+
+use std::marker::PhantomData;
+
+use super::rate::RateCoefficients;
+use super::transition::TransitionComponent;
+use super::CoolingLight;
+use crate::atom::Position;
+use crate::constant::{HBAR, PI};
+use crate::laser::frame::Frame;
+use crate::laser::gaussian::GaussianBeam;
+use crate::laser::index::LaserIndex;
+use crate::laser::intensity::LaserIntensitySamplers;
+use crate::laser::optics::{BeamPath, DerivedBeam};
+use hashbrown::HashMap;
+use nalgebra::Vector3;
+use specs::prelude::*;
+
+/// A resource that enables attenuation of cooling beams by the atom cloud.
+///
+/// If this resource is not present, all atoms see the unattenuated beam intensity.
+#[derive(Clone, Copy)]
+pub struct BeamAttenuationOption {
+    /// Number of real atoms represented by each simulated atom.
+    pub macroparticle: f64,
+    /// Side length of the square columns used to calculate optical depth, in units of m.
+    pub column_width: f64,
+}
+
+/// Atoms within a column, stored as (distance along beam, cross section, atom index).
+type Column = Vec<(f64, f64, usize)>;
+
+/// Summed cross section of all atoms in each column of a beam, with the frame and intersection used to find the columns.
+struct ColumnTotals {
+    x_vector: Vector3<f64>,
+    y_vector: Vector3<f64>,
+    intersection: Vector3<f64>,
+    totals: HashMap<(i64, i64), f64>,
+}
+
+impl ColumnTotals {
+    fn key(&self, pos: &Vector3<f64>, column_width: f64) -> (i64, i64) {
+        let rel = pos - self.intersection;
+        (
+            (rel.dot(&self.x_vector) / column_width).floor() as i64,
+            (rel.dot(&self.y_vector) / column_width).floor() as i64,
+        )
+    }
+}
+
+/// Calculates the optical depth of the cloud along each cooling beam, and reduces the
+/// `RateCoefficients` and `LaserIntensitySamplers` of each atom by the attenuation
+/// from atoms upstream.
+///
+/// The cross section of each atom is taken from the unattenuated rate coefficients,
+/// which is valid because the rate coefficient is linear in intensity.
+#[derive(Default)]
+pub struct CalculateBeamAttenuationSystem<T, const N: usize>(PhantomData<T>)
+where
+    T: TransitionComponent;
+
+impl<'a, T, const N: usize> System<'a> for CalculateBeamAttenuationSystem<T, N>
+where
+    T: TransitionComponent,
+{
+    type SystemData = (
+        Option<Read<'a, BeamAttenuationOption>>,
+        Entities<'a>,
+        ReadStorage<'a, CoolingLight>,
+        ReadStorage<'a, LaserIndex>,
+        ReadStorage<'a, GaussianBeam>,
+        ReadStorage<'a, Frame>,
+        ReadStorage<'a, DerivedBeam>,
+        ReadStorage<'a, BeamPath>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, T>,
+        WriteStorage<'a, RateCoefficients<T, N>>,
+        WriteStorage<'a, LaserIntensitySamplers<N>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            attenuation_option,
+            entities,
+            cooling_light,
+            cooling_index,
+            gaussian_beam,
+            frames,
+            derived_beams,
+            beam_paths,
+            positions,
+            transitions,
+            mut rate_coefficients,
+            mut intensity_samplers,
+        ): Self::SystemData,
+    ) {
+        let option = match attenuation_option {
+            None => return,
+            Some(option) => *option,
+        };
+        let column_area = option.column_width.powi(2);
+
+        let atoms: Vec<(
+            Vector3<f64>,
+            RateCoefficients<T, N>,
+            LaserIntensitySamplers<N>,
+        )> = (
+            &positions,
+            &rate_coefficients,
+            &intensity_samplers,
+            &transitions,
+        )
+            .join()
+            .map(|(pos, rates, intensities, _)| (pos.pos, *rates, *intensities))
+            .collect();
+        let mut optical_depths = vec![[0.0; N]; atoms.len()];
+        let mut column_totals: HashMap<Entity, ColumnTotals> = HashMap::new();
+
+        for (laser, cooling, index, beam) in
+            (&entities, &cooling_light, &cooling_index, &gaussian_beam).join()
+        {
+            let frame = match frames.get(laser) {
+                Some(frame) => *frame,
+                None => Frame::default_for_direction(beam.direction),
+            };
+            let direction = beam.direction.normalize();
+            let photon_energy = HBAR * 2.0 * PI * cooling.frequency();
+            let mut totals = ColumnTotals {
+                x_vector: frame.x_vector,
+                y_vector: frame.y_vector,
+                intersection: beam.intersection,
+                totals: HashMap::new(),
+            };
+
+            // Sort atoms into columns along the beam.
+            let mut columns: HashMap<(i64, i64), Column> = HashMap::new();
+            for (i, (pos, rates, intensities)) in atoms.iter().enumerate() {
+                // Atoms without a finite position cannot be placed along the beam.
+                let distance = (pos - beam.intersection).dot(&direction);
+                if !distance.is_finite() {
+                    continue;
+                }
+                let key = totals.key(pos, option.column_width);
+                let intensity = intensities.contents[index.index].intensity;
+                let rate = rates.contents[index.index].rate;
+                let cross_section = if intensity > 0.0 && rate.is_finite() {
+                    rate * photon_energy / intensity
+                } else {
+                    0.0
+                };
+                columns
+                    .entry(key)
+                    .or_default()
+                    .push((distance, cross_section, i));
+            }
+
+            // Accumulate the optical depth from upstream atoms in each column.
+            for (key, column) in columns.iter_mut() {
+                column.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                let mut total_cross_section = 0.0;
+                for &(_, cross_section, i) in column.iter() {
+                    optical_depths[i][index.index] =
+                        option.macroparticle * total_cross_section / column_area;
+                    total_cross_section += cross_section;
+                }
+                totals.totals.insert(*key, total_cross_section);
+            }
+            column_totals.insert(laser, totals);
+        }
+
+        // Derived beams are also attenuated by the whole cloud on each earlier pass.
+        for (derived, index, _) in (&derived_beams, &cooling_index, &cooling_light).join() {
+            let mut earlier_passes = vec![derived.source];
+            if let Some(path) = beam_paths.get(derived.source) {
+                earlier_passes.extend(path.derived_beams().iter().take(derived.pass - 1));
+            }
+            for pass in earlier_passes.iter() {
+                let totals = match column_totals.get(pass) {
+                    Some(totals) => totals,
+                    None => continue,
+                };
+                for (i, (pos, _, _)) in atoms.iter().enumerate() {
+                    let key = totals.key(pos, option.column_width);
+                    if let Some(total) = totals.totals.get(&key) {
+                        optical_depths[i][index.index] +=
+                            option.macroparticle * total / column_area;
+                    }
+                }
+            }
+        }
+
+        for ((rates, intensities, _, _), depths) in (
+            &mut rate_coefficients,
+            &mut intensity_samplers,
+            &positions,
+            &transitions,
+        )
+            .join()
+            .zip(optical_depths.iter())
+        {
+            for (i, depth) in depths.iter().enumerate() {
+                let transmission = (-depth).exp();
+                rates.contents[i].rate *= transmission;
+                intensities.contents[i].intensity *= transmission;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+    use crate::laser::intensity::LaserIntensitySampler;
+    use crate::laser_cooling::rate::RateCoefficient;
+    use crate::species::Strontium88_461;
+    use assert_approx_eq::assert_approx_eq;
+
+    const N: usize = 1;
+
+    #[test]
+    fn test_downstream_atoms_are_shadowed() {
+        let mut test_world = World::new();
+        test_world.register::<CoolingLight>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<DerivedBeam>();
+        test_world.register::<BeamPath>();
+        test_world.register::<Position>();
+        test_world.register::<Strontium88_461>();
+        test_world.register::<RateCoefficients<Strontium88_461, N>>();
+        test_world.register::<LaserIntensitySamplers<N>>();
+
+        let macroparticle = 100.0;
+        let column_width = 1e-4;
+        test_world.insert(BeamAttenuationOption {
+            macroparticle,
+            column_width,
+        });
+
+        let cooling = CoolingLight {
+            polarization: 1,
+            wavelength: 461e-9,
+        };
+        test_world
+            .create_entity()
+            .with(cooling)
+            .with(LaserIndex {
+                index: 0,
+                initiated: true,
+            })
+            .with(GaussianBeam {
+                direction: Vector3::x(),
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius: 1e-2,
+                power: 1.0,
+                rayleigh_range: f64::INFINITY,
+                ellipticity: 0.0,
+            })
+            .build();
+
+        let rate = 1.0e5;
+        let intensity = 10.0;
+        let mut rate_coefficient = RateCoefficient::<Strontium88_461>::default();
+        rate_coefficient.rate = rate;
+        let mut create_atom = |x: f64| {
+            test_world
+                .create_entity()
+                .with(Position {
+                    pos: Vector3::new(x, 1e-5, 1e-5),
+                })
+                .with(Strontium88_461)
+                .with(RateCoefficients {
+                    contents: [rate_coefficient; N],
+                })
+                .with(LaserIntensitySamplers {
+                    contents: [LaserIntensitySampler { intensity }; N],
+                })
+                .build()
+        };
+        let downstream = create_atom(1e-3);
+        let upstream = create_atom(-1e-3);
+        // An atom with an invalid position neither shadows nor panics.
+        create_atom(f64::NAN);
+
+        let mut system = CalculateBeamAttenuationSystem::<Strontium88_461, N>::default();
+        system.run_now(&test_world);
+        test_world.maintain();
+
+        let cross_section = rate * HBAR * 2.0 * PI * cooling.frequency() / intensity;
+        let transmission = (-macroparticle * cross_section / column_width.powi(2)).exp();
+
+        let rates = test_world.read_storage::<RateCoefficients<Strontium88_461, N>>();
+        let intensities = test_world.read_storage::<LaserIntensitySamplers<N>>();
+        assert_approx_eq!(rates.get(upstream).unwrap().contents[0].rate, rate);
+        assert_approx_eq!(
+            rates.get(downstream).unwrap().contents[0].rate,
+            rate * transmission,
+            1e-6 * rate
+        );
+        assert_approx_eq!(
+            intensities.get(downstream).unwrap().contents[0].intensity,
+            intensity * transmission,
+            1e-6 * intensity
+        );
+    }
+
+    #[test]
+    fn test_derived_beams_are_shadowed_on_earlier_passes() {
+        const M: usize = 2;
+        let mut test_world = World::new();
+        test_world.register::<CoolingLight>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Frame>();
+        test_world.register::<DerivedBeam>();
+        test_world.register::<BeamPath>();
+        test_world.register::<Position>();
+        test_world.register::<Strontium88_461>();
+        test_world.register::<RateCoefficients<Strontium88_461, M>>();
+        test_world.register::<LaserIntensitySamplers<M>>();
+
+        let macroparticle = 100.0;
+        let column_width = 1e-4;
+        test_world.insert(BeamAttenuationOption {
+            macroparticle,
+            column_width,
+        });
+
+        let cooling = CoolingLight {
+            polarization: 1,
+            wavelength: 461e-9,
+        };
+        let beam = GaussianBeam {
+            direction: Vector3::x(),
+            intersection: Vector3::new(0.0, 0.0, 0.0),
+            e_radius: 1e-2,
+            power: 1.0,
+            rayleigh_range: f64::INFINITY,
+            ellipticity: 0.0,
+        };
+        let source = test_world
+            .create_entity()
+            .with(cooling)
+            .with(LaserIndex {
+                index: 0,
+                initiated: true,
+            })
+            .with(beam)
+            .build();
+        test_world
+            .create_entity()
+            .with(cooling)
+            .with(LaserIndex {
+                index: 1,
+                initiated: true,
+            })
+            .with(GaussianBeam {
+                direction: -Vector3::x(),
+                ..beam
+            })
+            .with(DerivedBeam { source, pass: 1 })
+            .build();
+
+        let rate = 1.0e5;
+        let intensity = 10.0;
+        let mut rate_coefficient = RateCoefficient::<Strontium88_461>::default();
+        rate_coefficient.rate = rate;
+        let mut create_atom = |x: f64| {
+            test_world
+                .create_entity()
+                .with(Position {
+                    pos: Vector3::new(x, 1e-5, 1e-5),
+                })
+                .with(Strontium88_461)
+                .with(RateCoefficients {
+                    contents: [rate_coefficient; M],
+                })
+                .with(LaserIntensitySamplers {
+                    contents: [LaserIntensitySampler { intensity }; M],
+                })
+                .build()
+        };
+        let near_mirror = create_atom(1e-3);
+        let far_from_mirror = create_atom(-1e-3);
+
+        let mut system = CalculateBeamAttenuationSystem::<Strontium88_461, M>::default();
+        system.run_now(&test_world);
+        test_world.maintain();
+
+        // On the return pass, both atoms are shadowed by the whole cloud on the first pass.
+        let depth = macroparticle * rate * HBAR * 2.0 * PI * cooling.frequency()
+            / intensity
+            / column_width.powi(2);
+        let rates = test_world.read_storage::<RateCoefficients<Strontium88_461, M>>();
+        assert_approx_eq!(
+            rates.get(near_mirror).unwrap().contents[1].rate,
+            rate * (-2.0 * depth).exp(),
+            1e-6 * rate
+        );
+        assert_approx_eq!(
+            rates.get(far_from_mirror).unwrap().contents[1].rate,
+            rate * (-3.0 * depth).exp(),
+            1e-6 * rate
+        );
+    }
+}
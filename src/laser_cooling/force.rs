@@ -5,9 +5,16 @@ use std::marker::PhantomData;
 use super::CoolingLight;
 use super::transition::{TransitionComponent};
 use crate::constant;
+use crate::laser::frame::Frame;
 use crate::laser::gaussian::GaussianBeam;
 use crate::laser::index::LaserIndex;
-use crate::laser_cooling::photons_scattered::ActualPhotonsScatteredVector;
+use crate::laser_cooling::photons_scattered::{
+    ActualPhotonsScatteredVector, ScatteringFluctuationsOption,
+};
+use crate::laser_cooling::quantum_jump::InternalState;
+use crate::laser_cooling::rate::RateCoefficients;
+use crate::magnetic::MagneticFieldSampler;
+use rand::Rng;
 use nalgebra::Vector3;
 use rand_distr;
 use rand_distr::{Distribution, Normal, UnitSphere};
@@ -116,6 +123,103 @@ pub struct EmissionForceConfiguration {
     pub explicit_threshold: u64,
 }
 
+/// The angular distribution of photons emitted spontaneously by an atom.
+#[derive(Clone, Copy)]
+pub enum EmissionPattern {
+    /// Photons are emitted uniformly in all directions.
+    Isotropic,
+    /// Photons are emitted with the radiation pattern of an electric dipole.
+    ///
+    /// Photons emitted on σ± transitions follow `(1 + cos²θ)`, and photons emitted on π transitions
+    /// follow `sin²θ`, where θ is the angle to the quantization `axis`.
+    Dipole {
+        /// Unit vector along the quantization axis.
+        axis: Vector3<f64>,
+        /// Fraction of photons that are emitted on π transitions.
+        pi_fraction: f64,
+    },
+}
+
+impl EmissionPattern {
+    /// Gets the emission pattern of an atom from its `RateCoefficients` and the local magnetic field.
+    ///
+    /// In the rate equation picture, the population of each excited sublevel is proportional to the rate at
+    /// which it is pumped, so photons are emitted on each transition in proportion to its rate. The pattern
+    /// is isotropic if there is no magnetic field to define the quantization axis.
+    pub fn from_rates<T, const N: usize>(
+        rates: &RateCoefficients<T, N>,
+        field: &Vector3<f64>,
+    ) -> Self
+    where
+        T: TransitionComponent,
+    {
+        if field.norm_squared() < (10.0 * f64::EPSILON) {
+            return EmissionPattern::Isotropic;
+        }
+        let (mut sigma, mut pi) = (0.0, 0.0);
+        for rate in rates.contents.iter() {
+            if rate.sigma_plus.is_finite() && rate.sigma_minus.is_finite() && rate.pi.is_finite() {
+                sigma += rate.sigma_plus + rate.sigma_minus;
+                pi += rate.pi;
+            }
+        }
+        if sigma + pi <= 0.0 {
+            return EmissionPattern::Isotropic;
+        }
+        EmissionPattern::Dipole {
+            axis: field.normalize(),
+            pi_fraction: pi / (sigma + pi),
+        }
+    }
+
+    /// Draws the direction of a single emitted photon.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Vector3<f64> {
+        loop {
+            let v: [f64; 3] = UnitSphere.sample(rng);
+            let direction = Vector3::new(v[0], v[1], v[2]);
+            match self {
+                EmissionPattern::Isotropic => return direction,
+                EmissionPattern::Dipole { axis, pi_fraction } => {
+                    let cos2 = direction.dot(axis).powi(2);
+                    // rejection sampling from the weighted sum of both patterns, which is at most 1.
+                    let weight = (1.0 - pi_fraction) * (1.0 + cos2) / 2.0 + pi_fraction * (1.0 - cos2);
+                    if rng.gen_range(0.0..1.0) < weight {
+                        return direction;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws the total momentum direction of `n` emitted photons, for large `n`.
+    ///
+    /// The sum of many photons is normally distributed, with a variance along each axis given by the
+    /// mean squared component of a single photon along that axis.
+    pub fn sample_sum<R: Rng>(&self, n: u64, rng: &mut R) -> Vector3<f64> {
+        // see HSIUNG, HSIUNG,GORDUS,1960, A Closed General Solution of the Probability Distribution Function for
+        //Three-Dimensional Random Walk Processes*
+        let mut normal = |variance: f64| -> f64 {
+            Normal::new(0.0, (n as f64 * variance).sqrt())
+                .unwrap()
+                .sample(rng)
+        };
+        match self {
+            EmissionPattern::Isotropic => {
+                Vector3::new(normal(1.0 / 3.0), normal(1.0 / 3.0), normal(1.0 / 3.0))
+            }
+            EmissionPattern::Dipole { axis, pi_fraction } => {
+                // <cos²θ> is 2/5 for σ± photons and 1/5 for π photons.
+                let axial = (1.0 - pi_fraction) * 2.0 / 5.0 + pi_fraction / 5.0;
+                let transverse = (1.0 - axial) / 2.0;
+                let frame = Frame::default_for_direction(*axis);
+                axis * normal(axial)
+                    + frame.x_vector * normal(transverse)
+                    + frame.y_vector * normal(transverse)
+            }
+        }
+    }
+}
+
 /// Calculates the force vector due to the spontaneous emissions in this
 /// simulation step.
 ///
@@ -123,21 +227,41 @@ pub struct EmissionForceConfiguration {
 ///
 /// Uses an internal threshold of 5 to decide if the random vektor is iteratively
 /// produced or derived by random-walk formula and a single random unit vector.
+///
+/// Photons are emitted with the dipole radiation pattern about the local magnetic field,
+/// see [EmissionPattern], for atoms with `RateCoefficients` and a `MagneticFieldSampler`.
+///
+/// In the `ScatteringFluctuationsOption::QuantumJumps` mode, the number of spontaneous
+/// emissions is taken from the `InternalState` of each atom.
 #[derive(Default)]
 pub struct ApplyEmissionForceSystem<T, const N: usize>(PhantomData<T>) where T : TransitionComponent;
 
 impl<'a, T, const N: usize> System<'a> for ApplyEmissionForceSystem<T, N> where T : TransitionComponent {
     type SystemData = (
         Option<Read<'a, EmissionForceOption>>,
+        Option<Read<'a, ScatteringFluctuationsOption>>,
         WriteStorage<'a, Force>,
         ReadStorage<'a, ActualPhotonsScatteredVector<T, N>>,
+        ReadStorage<'a, InternalState<T>>,
+        ReadStorage<'a, RateCoefficients<T, N>>,
+        ReadStorage<'a, MagneticFieldSampler>,
         ReadStorage<'a, T>,
         ReadExpect<'a, Timestep>,
     );
 
     fn run(
         &mut self,
-        (rand_opt, mut force, actual_scattered_vector, transition, timestep): Self::SystemData,
+        (
+            rand_opt,
+            fluctuations_option,
+            mut force,
+            actual_scattered_vector,
+            internal_states,
+            rate_coefficients,
+            magnetic_field_samplers,
+            transition,
+            timestep,
+        ): Self::SystemData,
     ) {
         use rayon::prelude::*;
 
@@ -147,35 +271,40 @@ impl<'a, T, const N: usize> System<'a> for ApplyEmissionForceSystem<T, N> where
                 match *opt {
                     EmissionForceOption::Off => {}
                     EmissionForceOption::On(configuration) => {
-                        (&mut force, &transition, &actual_scattered_vector)
+                        let quantum_jumps = matches!(
+                            fluctuations_option.as_deref(),
+                            Some(ScatteringFluctuationsOption::QuantumJumps)
+                        );
+                        (
+                            &mut force,
+                            &transition,
+                            &actual_scattered_vector,
+                            internal_states.maybe(),
+                            rate_coefficients.maybe(),
+                            magnetic_field_samplers.maybe(),
+                        )
                             .par_join()
-                            .for_each(|(force, _atom_info, kick)| {
-                                let total: u64 = kick.calculate_total_scattered();
+                            .for_each(|(force, _atom_info, kick, internal_state, rates, bfield)| {
+                                let total: u64 = match internal_state {
+                                    Some(state) if quantum_jumps => state.spontaneous_emissions,
+                                    _ => kick.calculate_total_scattered(),
+                                };
+                                let pattern = match (rates, bfield) {
+                                    (Some(rates), Some(bfield)) => {
+                                        EmissionPattern::from_rates(rates, &bfield.field)
+                                    }
+                                    _ => EmissionPattern::Isotropic,
+                                };
                                 let mut rng = rand::thread_rng();
                                 let omega = 2.0 * constant::PI * T::frequency();
                                 let force_one_kick =
                                     constant::HBAR * omega / constant::C / timestep.delta;
                                 if total > configuration.explicit_threshold {
-                                    // see HSIUNG, HSIUNG,GORDUS,1960, A Closed General Solution of the Probability Distribution Function for
-                                    //Three-Dimensional Random Walk Processes*
-                                    let normal = Normal::new(
-                                        0.0,
-                                        (total as f64 * force_one_kick.powf(2.0) / 3.0).powf(0.5),
-                                    )
-                                    .unwrap();
-
-                                    let force_n_kicks = Vector3::new(
-                                        normal.sample(&mut rng),
-                                        normal.sample(&mut rng),
-                                        normal.sample(&mut rng),
-                                    );
-                                    force.force += force_n_kicks;
+                                    force.force += force_one_kick * pattern.sample_sum(total, &mut rng);
                                 } else {
                                     // explicit random walk implementation
                                     for _i in 0..total {
-                                        let v: [f64; 3] = UnitSphere.sample(&mut rng);
-                                        force.force +=
-                                            force_one_kick * Vector3::new(v[0], v[1], v[2]);
+                                        force.force += force_one_kick * pattern.sample(&mut rng);
                                     }
                                 }
                             });
@@ -271,6 +400,9 @@ pub mod tests {
         test_world.register::<ActualPhotonsScatteredVector<Strontium88_461, { DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<Force>();
         test_world.register::<Strontium88_461>();
+        test_world.register::<InternalState<Strontium88_461>>();
+        test_world.register::<RateCoefficients<Strontium88_461, { DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<MagneticFieldSampler>();
         test_world.insert(EmissionForceOption::default());
         test_world.insert(Timestep { delta: time_delta });
         let number_scattered = 1_000_000.0;
@@ -306,4 +438,48 @@ pub mod tests {
             max_force_total / 1.9
         );
     }
+
+    /// Tests that photons are emitted with the dipole radiation pattern about the field.
+    #[test]
+    fn test_dipole_emission_pattern() {
+        use crate::laser_cooling::rate::RateCoefficient;
+
+        let mut rate = RateCoefficient::<Strontium88_461>::default();
+        rate.sigma_plus = 1.0;
+        rate.sigma_minus = 0.0;
+        rate.pi = 0.0;
+        let rates = RateCoefficients::<Strontium88_461, 1> { contents: [rate] };
+
+        let sigma = EmissionPattern::from_rates(&rates, &Vector3::new(0.0, 0.0, 2.0));
+        let mut pi_rates = rates;
+        pi_rates.contents[0].sigma_plus = 0.0;
+        pi_rates.contents[0].pi = 1.0;
+        let pi = EmissionPattern::from_rates(&pi_rates, &Vector3::new(0.0, 0.0, 2.0));
+        assert!(matches!(
+            EmissionPattern::from_rates(&rates, &Vector3::zeros()),
+            EmissionPattern::Isotropic
+        ));
+
+        let mut rng = rand::thread_rng();
+        let n = 100000;
+        let patterns = [
+            (sigma, 0.4),
+            (pi, 0.2),
+            (EmissionPattern::Isotropic, 1.0 / 3.0),
+        ];
+        for (pattern, expected) in patterns.iter() {
+            let mean_cos2 = (0..n)
+                .map(|_| pattern.sample(&mut rng)[2].powi(2))
+                .sum::<f64>()
+                / n as f64;
+            assert_approx_eq!(mean_cos2, *expected, 0.01);
+
+            // the sum of many photons has the same distribution.
+            let mean_sum = (0..n)
+                .map(|_| pattern.sample_sum(100, &mut rng)[2].powi(2))
+                .sum::<f64>()
+                / (100 * n) as f64;
+            assert_approx_eq!(mean_sum, *expected, 0.01);
+        }
+    }
 }
@@ -0,0 +1,242 @@
+//! Frequency modulation and frequency noise of cooling beams.
+//!
+//! A `CoolingLight` has a single, fixed wavelength. The components in this module add a
+//! time-dependent offset to the frequency of a beam, which is included in the detunings
+//! calculated by [crate::laser_cooling::sampler::CalculateLaserDetuningSystem]:
+//!
+//! * [FrequencyModulation] sweeps the frequency of the beam, for example to generate the
+//!   'broadband' light used to load narrow-line MOTs such as the Sr 689nm MOT.
+//!
+//! * [LaserFrequencyNoise] adds stochastic noise to the frequency of the beam.
+//!
+//! The laser frequency is treated as an instantaneous frequency which the atoms follow
+//! adiabatically. The timestep must therefore be small compared to the modulation period
+//! and the correlation time of the noise.
+
+use super::CoolingLight;
+use crate::constant;
+use crate::integrator::{Step, Timestep};
+use crate::laser::update_component;
+use crate::maths;
+use rand_distr::{Distribution, StandardNormal};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+/// Modulates the frequency of a `CoolingLight` entity.
+///
+/// All frequencies are in SI units of Hz, and are offsets from the frequency of the `CoolingLight`.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub enum FrequencyModulation {
+    /// Sinusoidal modulation, `deviation * sin(2 pi frequency t)`.
+    ///
+    /// When the modulation frequency is much larger than the transition linewidth, atoms see a comb
+    /// of sidebands spanning twice the `deviation`.
+    Sinusoidal { deviation: f64, frequency: f64 },
+    /// A sawtooth sweep, repeated with the given `frequency`, from `start` to `end`.
+    Sawtooth {
+        start: f64,
+        end: f64,
+        frequency: f64,
+    },
+}
+
+impl FrequencyModulation {
+    /// The frequency offset at time `t`, in SI units of Hz.
+    pub fn offset(&self, t: f64) -> f64 {
+        match self {
+            FrequencyModulation::Sinusoidal {
+                deviation,
+                frequency,
+            } => deviation * (2.0 * constant::PI * frequency * t).sin(),
+            FrequencyModulation::Sawtooth {
+                start,
+                end,
+                frequency,
+            } => start + (end - start) * (frequency * t).fract(),
+        }
+    }
+}
+
+impl Component for FrequencyModulation {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Adds frequency noise to a `CoolingLight` entity.
+///
+/// The instantaneous frequency of the beam performs an Ornstein-Uhlenbeck process with the given
+/// `correlation_time`; the phase of the light is not modelled. The strength of the noise is given
+/// as a `linewidth`: in the limit of short correlation times, the spectrum of a field with this
+/// frequency noise is a Lorentzian with a full width at half maximum of `linewidth`.
+///
+/// Because atoms follow the instantaneous frequency, they see a Gaussian distribution of detunings
+/// with a standard deviation of `sqrt(linewidth / (4 pi correlation_time))` in units of Hz, rather
+/// than the Lorentzian line. This is only accurate when the correlation time is long compared to
+/// the lifetime of the transition; faster noise is not averaged out by the atoms.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct LaserFrequencyNoise {
+    /// Full width at half maximum of the Lorentzian line of white frequency noise with the same
+    /// low-frequency spectral density, in SI units of Hz.
+    pub linewidth: f64,
+    /// Correlation time of the frequency noise, in SI units of s.
+    pub correlation_time: f64,
+    /// Current frequency offset due to the noise, in SI units of rad/s.
+    frequency_noise: f64,
+}
+
+impl LaserFrequencyNoise {
+    pub fn new(linewidth: f64, correlation_time: f64) -> Self {
+        LaserFrequencyNoise {
+            linewidth,
+            correlation_time,
+            frequency_noise: 0.0,
+        }
+    }
+
+    /// Standard deviation of the frequency noise, in SI units of rad/s.
+    pub fn standard_deviation(&self) -> f64 {
+        (constant::PI * self.linewidth / self.correlation_time).sqrt()
+    }
+}
+
+impl Component for LaserFrequencyNoise {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// The current frequency offset of a `CoolingLight` entity, in SI units of rad/s.
+#[derive(Clone, Copy, Default)]
+pub struct LaserFrequencyOffset {
+    pub offset: f64,
+}
+
+impl Component for LaserFrequencyOffset {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Calculates the `LaserFrequencyOffset` of `CoolingLight` entities with a [FrequencyModulation]
+/// or [LaserFrequencyNoise].
+pub struct UpdateLaserFrequencyOffsetSystem;
+impl<'a> System<'a> for UpdateLaserFrequencyOffsetSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, CoolingLight>,
+        ReadStorage<'a, FrequencyModulation>,
+        WriteStorage<'a, LaserFrequencyNoise>,
+        WriteStorage<'a, LaserFrequencyOffset>,
+        ReadExpect<'a, Timestep>,
+        ReadExpect<'a, Step>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, cooling, modulations, mut frequency_noises, mut offsets, timestep, step): Self::SystemData,
+    ) {
+        let current_time = step.n as f64 * timestep.delta;
+        let mut rng = rand::thread_rng();
+        for (entity, _, modulation, frequency_noise) in (
+            &entities,
+            &cooling,
+            modulations.maybe(),
+            (&mut frequency_noises).maybe(),
+        )
+            .join()
+        {
+            if modulation.is_none() && frequency_noise.is_none() {
+                continue;
+            }
+            let mut offset =
+                modulation.map_or(0.0, |m| 2.0 * constant::PI * m.offset(current_time));
+            if let Some(noise) = frequency_noise {
+                noise.frequency_noise = maths::ornstein_uhlenbeck_step(
+                    noise.frequency_noise,
+                    noise.standard_deviation(),
+                    noise.correlation_time,
+                    timestep.delta,
+                    StandardNormal.sample(&mut rng),
+                );
+                offset += noise.frequency_noise;
+            }
+            update_component(&mut offsets, entity, LaserFrequencyOffset { offset });
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_frequency_modulation_offset() {
+        let sine = FrequencyModulation::Sinusoidal {
+            deviation: 1.0e6,
+            frequency: 50.0e3,
+        };
+        assert_approx_eq!(sine.offset(5.0e-6), 1.0e6, 1e-6);
+        assert_approx_eq!(sine.offset(15.0e-6), -1.0e6, 1e-6);
+
+        let sawtooth = FrequencyModulation::Sawtooth {
+            start: -2.0e6,
+            end: 0.0,
+            frequency: 10.0e3,
+        };
+        assert_approx_eq!(sawtooth.offset(0.0), -2.0e6, 1e-6);
+        assert_approx_eq!(sawtooth.offset(125.0e-6), -1.5e6, 1e-3);
+    }
+
+    #[test]
+    fn test_update_laser_frequency_offset_system() {
+        let mut test_world = World::new();
+        test_world.register::<CoolingLight>();
+        test_world.register::<FrequencyModulation>();
+        test_world.register::<LaserFrequencyNoise>();
+        test_world.register::<LaserFrequencyOffset>();
+        test_world.insert(Timestep { delta: 1.0e-6 });
+        test_world.insert(Step { n: 5 });
+
+        let light = CoolingLight {
+            polarization: 1,
+            wavelength: 689e-9,
+        };
+        let modulated = test_world
+            .create_entity()
+            .with(light)
+            .with(FrequencyModulation::Sinusoidal {
+                deviation: 1.0e6,
+                frequency: 50.0e3,
+            })
+            .build();
+        let noisy = test_world
+            .create_entity()
+            .with(light)
+            .with(LaserFrequencyNoise::new(1.0e3, 1.0e-6))
+            .build();
+        let plain = test_world.create_entity().with(light).build();
+
+        let mut system = UpdateLaserFrequencyOffsetSystem;
+        let mut noise = Vec::new();
+        for _ in 0..20000 {
+            system.run_now(&test_world);
+            noise.push(
+                test_world
+                    .read_storage::<LaserFrequencyOffset>()
+                    .get(noisy)
+                    .expect("entity not found")
+                    .offset,
+            );
+        }
+
+        let offsets = test_world.read_storage::<LaserFrequencyOffset>();
+        assert_approx_eq!(
+            offsets.get(modulated).expect("entity not found").offset,
+            2.0 * constant::PI * 1.0e6,
+            1e-3
+        );
+        assert!(offsets.get(plain).is_none());
+
+        // the noise should settle to its stationary standard deviation.
+        let samples = &noise[1000..];
+        let variance = samples.iter().map(|x| x * x).sum::<f64>() / samples.len() as f64;
+        let expected = LaserFrequencyNoise::new(1.0e3, 1.0e-6).standard_deviation();
+        assert!((variance.sqrt() / expected - 1.0).abs() < 0.1);
+    }
+}
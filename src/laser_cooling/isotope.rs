@@ -0,0 +1,115 @@
+//! Isotope shifts of laser cooling transitions.
+//!
+//! Atoms of every isotope of an element share the same transition component, eg `Strontium88_461`.
+//! Atoms of a minority isotope carry an `IsotopeShift` for the transition, which shifts the transition
+//! frequency by the isotope shift and, for isotopes with nuclear spin, the hyperfine shift of the cycling
+//! transition. The hyperfine structure also changes the g-factors of the cycling transition, which can
+//! be given by the `levels` of the `Isotope`.
+//!
+//! Atom sources attach an `IsotopeShift` to atoms drawn from a [crate::atom_sources::mass::MassDistribution]
+//! whose masses are given `IsotopeShifts` by an [crate::atom_sources::mass::IsotopeDistribution], for
+//! each transition listed in the `isotopes` of the `species!` macro.
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use super::transition::{stretched_magnetic_moments, TransitionComponent, ZeemanLevel};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+/// Properties of a transition for a particular isotope, relative to the transition of the species.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Isotope {
+    /// Shift of the transition frequency, including the hyperfine shift of the cycling transition, in Hz.
+    pub shift: f64,
+    /// The lower and upper levels of the cycling transition, if they differ from those of the species.
+    #[serde(default)]
+    pub levels: Option<(ZeemanLevel, ZeemanLevel)>,
+}
+
+impl Isotope {
+    /// Creates an `Isotope` which shifts the transition frequency by `shift`, in Hz.
+    pub fn shifted(shift: f64) -> Self {
+        Isotope {
+            shift,
+            levels: None,
+        }
+    }
+}
+
+/// The `Isotope` of each transition of a minority isotope, by the name of the transition.
+pub type IsotopeShifts = BTreeMap<String, Isotope>;
+
+/// Shifts the transition `T` of an atom belonging to a minority isotope.
+#[derive(Clone, Copy)]
+pub struct IsotopeShift<T>
+where
+    T: TransitionComponent,
+{
+    pub isotope: Isotope,
+    phantom: PhantomData<T>,
+}
+
+impl<T> IsotopeShift<T>
+where
+    T: TransitionComponent,
+{
+    pub fn new(isotope: Isotope) -> Self {
+        IsotopeShift {
+            isotope,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Shift of the transition frequency, in rad/s.
+    pub fn angular_shift(&self) -> f64 {
+        2.0 * std::f64::consts::PI * self.isotope.shift
+    }
+
+    /// Shifts of the sigma+, sigma- and pi transitions per unit magnetic field, in J/T.
+    pub fn magnetic_moments(&self) -> (f64, f64, f64) {
+        match &self.isotope.levels {
+            Some((lower, upper)) => stretched_magnetic_moments(lower, upper),
+            None => (T::mup(), T::mum(), T::muz()),
+        }
+    }
+}
+
+impl<T> Component for IsotopeShift<T>
+where
+    T: TransitionComponent + 'static,
+{
+    type Storage = VecStorage<Self>;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::constant::BOHRMAG;
+    use crate::laser_cooling::transition::AtomicTransition;
+    use crate::species::Strontium88_689;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_isotope_magnetic_moments() {
+        let shift = IsotopeShift::<Strontium88_689>::new(Isotope::shifted(-351.5e6));
+        assert_approx_eq!(shift.angular_shift(), -2.0 * std::f64::consts::PI * 351.5e6);
+        assert_eq!(shift.magnetic_moments().0, Strontium88_689::mup());
+
+        // Sr87 F=9/2 -> F'=11/2, where the ground state has only a nuclear magnetic moment.
+        let sr87 = IsotopeShift::<Strontium88_689>::new(Isotope {
+            shift: -1241.5e6,
+            levels: Some((
+                ZeemanLevel { f: 4.5, g: 0.0 },
+                ZeemanLevel {
+                    f: 5.5,
+                    g: 1.5 * 13.0 / 71.5,
+                },
+            )),
+        });
+        let (sigma_plus, sigma_minus, pi) = sr87.magnetic_moments();
+        assert_approx_eq!(sigma_plus, 1.5 * BOHRMAG, 1e-30);
+        assert_approx_eq!(sigma_minus, -1.5 * BOHRMAG, 1e-30);
+        assert_approx_eq!(pi, 4.5 * 1.5 * 13.0 / 71.5 * BOHRMAG, 1e-30);
+    }
+}
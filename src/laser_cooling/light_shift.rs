@@ -0,0 +1,236 @@
+//! Differential light shift of a cooling transition, induced by `DipoleLight` beams.
+//!
+//! Dipole beams shift the ground state of an atom by `-ground_prefactor * I` (see `Polarizability`)
+//! and the excited state of a transition by `-excited_prefactor * I`. The difference between the
+//! two shifts moves the transition frequency, and therefore the detuning of all cooling beams
+//! addressing the transition. When the two prefactors are equal, the dipole light is at a magic
+//! wavelength for the transition and the shift vanishes.
+//!
+//! The shift is evaluated at the position of each atom, so effects such as the gravitational sag
+//! of atoms in a weak trap (which moves them away from the intensity maximum) are included.
+
+use std::marker::PhantomData;
+
+use super::transition::TransitionComponent;
+use crate::constant::HBAR;
+use crate::dipole::polarizability::DipolePrefactors;
+use crate::dipole::{DipoleLight, Polarizability};
+use crate::initiate::NewlyCreated;
+use crate::laser::index::LaserIndex;
+use crate::laser::intensity::LaserIntensitySamplers;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+/// An atom component that represents the polarizability of the excited state of transition `T`.
+///
+/// The prefactor has the same meaning as `Polarizability::prefactor`, which describes the ground state.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct ExcitedStatePolarizability<T>
+where
+    T: TransitionComponent,
+{
+    /// Constant of proportionality between intensity (in W/m^2) and the (negative) energy shift of the excited state (in J).
+    pub prefactor: f64,
+    #[serde(skip)]
+    phantom: PhantomData<T>,
+}
+
+impl<T> ExcitedStatePolarizability<T>
+where
+    T: TransitionComponent,
+{
+    pub fn new(prefactor: f64) -> Self {
+        ExcitedStatePolarizability {
+            prefactor,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Component for ExcitedStatePolarizability<T>
+where
+    T: TransitionComponent,
+{
+    type Storage = VecStorage<Self>;
+}
+
+/// Represents the shift of the transition frequency of `T` caused by dipole light.
+#[derive(Clone, Copy, Serialize)]
+pub struct LightShiftSampler<T>
+where
+    T: TransitionComponent,
+{
+    /// Shift of the transition frequency, in units of rad/s.
+    pub shift: f64,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Default for LightShiftSampler<T>
+where
+    T: TransitionComponent,
+{
+    fn default() -> Self {
+        LightShiftSampler {
+            shift: f64::NAN,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Component for LightShiftSampler<T>
+where
+    T: TransitionComponent,
+{
+    type Storage = VecStorage<Self>;
+}
+
+/// Attaches a `LightShiftSampler` to newly created atoms that have an `ExcitedStatePolarizability`.
+#[derive(Default)]
+pub struct AttachLightShiftSamplersToNewlyCreatedAtomsSystem<T>(PhantomData<T>)
+where
+    T: TransitionComponent;
+
+impl<'a, T> System<'a> for AttachLightShiftSamplersToNewlyCreatedAtomsSystem<T>
+where
+    T: TransitionComponent,
+{
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, NewlyCreated>,
+        ReadStorage<'a, ExcitedStatePolarizability<T>>,
+        Read<'a, LazyUpdate>,
+    );
+    fn run(&mut self, (ent, newly_created, polarizability, updater): Self::SystemData) {
+        for (ent, _, _) in (&ent, &newly_created, &polarizability).join() {
+            updater.insert(ent, LightShiftSampler::<T>::default());
+        }
+    }
+}
+
+/// Calculates the differential light shift of transition `T` from the intensity of all dipole beams.
+///
+/// The ground state polarizability is taken from the `DipolePrefactors` of the atom if present, otherwise from `Polarizability`.
+#[derive(Default)]
+pub struct CalculateLightShiftSystem<T, const N: usize>(PhantomData<T>)
+where
+    T: TransitionComponent;
+
+impl<'a, T, const N: usize> System<'a> for CalculateLightShiftSystem<T, N>
+where
+    T: TransitionComponent,
+{
+    type SystemData = (
+        ReadStorage<'a, DipoleLight>,
+        ReadStorage<'a, LaserIndex>,
+        ReadStorage<'a, Polarizability>,
+        ReadStorage<'a, DipolePrefactors<N>>,
+        ReadStorage<'a, ExcitedStatePolarizability<T>>,
+        ReadStorage<'a, LaserIntensitySamplers<N>>,
+        WriteStorage<'a, LightShiftSampler<T>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            dipole_light,
+            indices,
+            ground_polarizability,
+            ground_prefactors,
+            excited_polarizability,
+            intensity_samplers,
+            mut light_shift_samplers,
+        ): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        let dipole_indices: Vec<usize> = (&dipole_light, &indices)
+            .join()
+            .map(|(_, index)| index.index)
+            .collect();
+
+        (
+            ground_polarizability.maybe(),
+            ground_prefactors.maybe(),
+            &excited_polarizability,
+            &intensity_samplers,
+            &mut light_shift_samplers,
+        )
+            .par_join()
+            .for_each(|(ground, prefactors, excited, intensities, sampler)| {
+                let energy_shift: f64 = dipole_indices
+                    .iter()
+                    .map(|&i| {
+                        let ground = match (prefactors, ground) {
+                            (Some(prefactors), _) => prefactors.contents[i],
+                            (None, Some(ground)) => ground.prefactor,
+                            (None, None) => 0.0,
+                        };
+                        (ground - excited.prefactor) * intensities.contents[i].intensity
+                    })
+                    .sum();
+                sampler.shift = energy_shift / HBAR;
+            });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+    use crate::laser::intensity::LaserIntensitySampler;
+    use crate::species::Strontium88_689;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_calculate_light_shift_system() {
+        let mut test_world = World::new();
+        test_world.register::<DipoleLight>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<Polarizability>();
+        test_world.register::<DipolePrefactors<2>>();
+        test_world.register::<ExcitedStatePolarizability<Strontium88_689>>();
+        test_world.register::<LaserIntensitySamplers<2>>();
+        test_world.register::<LightShiftSampler<Strontium88_689>>();
+
+        test_world
+            .create_entity()
+            .with(DipoleLight {
+                wavelength: 1064e-9,
+            })
+            .with(LaserIndex {
+                index: 1,
+                initiated: true,
+            })
+            .build();
+
+        let ground = Polarizability { prefactor: 1.0e-36 };
+        let excited_prefactor = 0.4e-36;
+        let intensity = 1.0e9;
+        let atom = test_world
+            .create_entity()
+            .with(ground)
+            .with(ExcitedStatePolarizability::<Strontium88_689>::new(
+                excited_prefactor,
+            ))
+            .with(LaserIntensitySamplers {
+                contents: [
+                    // a cooling beam, which does not contribute to the light shift.
+                    LaserIntensitySampler { intensity: 1.0 },
+                    LaserIntensitySampler { intensity },
+                ],
+            })
+            .with(LightShiftSampler::<Strontium88_689>::default())
+            .build();
+
+        let mut system = CalculateLightShiftSystem::<Strontium88_689, 2>::default();
+        system.run_now(&test_world);
+        test_world.maintain();
+
+        let samplers = test_world.read_storage::<LightShiftSampler<Strontium88_689>>();
+        assert_approx_eq!(
+            samplers.get(atom).unwrap().shift,
+            (ground.prefactor - excited_prefactor) * intensity / HBAR,
+            1e-6
+        );
+    }
+}
@@ -1,7 +1,9 @@
 //! A module that implements systems and components for calculating optical scattering forces in AtomECS.
 
+use std::any::{type_name, TypeId};
 use std::marker::PhantomData;
 
+use crate::electric::ElectricFieldPlugin;
 use crate::laser::LaserPlugin;
 use crate::{constant, simulation::Plugin};
 use crate::initiate::NewlyCreated;
@@ -14,12 +16,20 @@ use transition::AtomicTransition;
 
 use self::transition::TransitionComponent;
 
+pub mod attenuation;
 pub mod doppler;
 pub mod force;
+pub mod frequency;
+pub mod isotope;
+pub mod light_shift;
+pub mod molecule;
+pub mod mot;
 pub mod photons_scattered;
+pub mod quantum_jump;
 pub mod rate;
 pub mod repump;
 pub mod sampler;
+pub mod stark;
 pub mod twolevel;
 pub mod transition;
 pub mod zeeman;
@@ -34,12 +44,11 @@ pub struct CoolingLight {
     /// Polarisation of the laser light, 1 for +, -1 for -,
     ///
     /// Note that the polarization is defined by the quantization vector (e.g. magnetic field)
-    /// and not (always) in direction of the wavevector. Look at the given examples of 3D-MOT
-    /// simulations to see a working example if unsure.
+    /// and not (always) in direction of the wavevector. See [mot::MotBuilder] to create
+    /// standard MOT configurations with the correct polarizations.
     ///
-    /// Currently this is an integer value since every partial polarization can be expressed
-    /// as a superposition of fully polarized beams. It  is possible that this will be
-    /// changed to a non-integer value in the future.
+    /// This value is ignored for beams with a [crate::laser::polarization::Polarization]
+    /// component, which can describe linear, elliptical and partially polarized light.
     pub polarization: i32,
 
     /// wavelength of the laser light, in SI units of m.
@@ -87,6 +96,59 @@ impl Component for CoolingLight {
     type Storage = HashMapStorage<Self>;
 }
 
+/// Restricts a `CoolingLight` to drive only the listed transitions.
+///
+/// Cooling beams without a `TargetTransitions` component drive every transition in the simulation.
+/// When several `LaserCoolingPlugin`s are added, for example for a dual-colour Sr MOT or a two-species
+/// MOT, each beam should target the transitions it is tuned to, so that it does not drive the other
+/// transitions at an enormous detuning.
+#[derive(Clone, Default)]
+pub struct TargetTransitions {
+    transitions: Vec<TypeId>,
+}
+
+impl TargetTransitions {
+    /// Creates a `TargetTransitions` which addresses only the transition `T`.
+    pub fn of<T>() -> Self
+    where
+        T: TransitionComponent,
+    {
+        TargetTransitions::default().and::<T>()
+    }
+
+    /// Adds the transition `T` to the transitions addressed by the beam.
+    pub fn and<T>(mut self) -> Self
+    where
+        T: TransitionComponent,
+    {
+        self.transitions.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Returns true if the beam addresses the transition `T`.
+    pub fn addresses<T>(&self) -> bool
+    where
+        T: TransitionComponent,
+    {
+        self.transitions.contains(&TypeId::of::<T>())
+    }
+}
+
+impl Component for TargetTransitions {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Returns true if a cooling beam with the (optional) `TargetTransitions` drives the transition `T`.
+pub fn drives_transition<T>(targets: Option<&TargetTransitions>) -> bool
+where
+    T: TransitionComponent,
+{
+    match targets {
+        Some(targets) => targets.addresses::<T>(),
+        None => true,
+    }
+}
+
 /// A system which attaches components required for optical scattering force calculation to newly created atoms.
 ///
 /// They are recognized as newly created if they are associated with
@@ -135,6 +197,7 @@ impl<'a, T, const N: usize> System<'a> for AttachLaserCoolingComponentsToNewlyCr
                     contents: [photons_scattered::ActualPhotonsScattered::default(); N],
                 },
             );
+            updater.insert(ent, quantum_jump::InternalState::<T>::default());
         }
     }
 }
@@ -160,6 +223,12 @@ impl<'a> System<'a> for AttachIndexToCoolingLightSystem {
 /// 
 /// For more information see [crate::laser_cooling].
 /// 
+/// A `LaserCoolingPlugin` can be added for each transition in the simulation. Use
+/// [TargetTransitions] to choose which transitions are driven by each `CoolingLight`.
+///
+/// The Stark shift of the transition (see [stark]) is only included when the [ElectricFieldPlugin] has been
+/// added before this plugin. Adding the [ElectricFieldPlugin] after this plugin panics.
+/// 
 /// # Generic Arguments
 /// 
 /// * `T`: The laser cooling transition to solve the two-level system for.
@@ -169,7 +238,22 @@ impl<'a> System<'a> for AttachIndexToCoolingLightSystem {
 pub struct LaserCoolingPlugin<T, const N : usize>(PhantomData<T>) where T : TransitionComponent;
 impl<T, const N : usize> Plugin for LaserCoolingPlugin<T, N> where T : TransitionComponent {
     fn build(&self, builder: &mut crate::simulation::SimulationBuilder) {
-        add_systems_to_dispatch::<T, N>(&mut builder.dispatcher_builder, &[]);
+        if !builder.has_plugin::<CoolingLightPlugin<N>>() {
+            builder.add_plugin(CoolingLightPlugin::<N>);
+        }
+        let stark = builder.has_plugin::<ElectricFieldPlugin>();
+        if !stark {
+            builder
+                .world
+                .entry::<stark::StarkShiftOmitted>()
+                .or_insert_with(Default::default)
+                .transitions
+                .push(type_name::<T>());
+        }
+        add_systems_to_dispatch::<T, N>(&mut builder.dispatcher_builder, &[], stark);
+        builder.world.register::<stark::ExcitedStateStark<T>>();
+        builder.world.register::<stark::StarkShiftSampler<T>>();
+        builder.world.register::<repump::Dark>();
     }
 
     fn deps(&self) -> Vec::<Box<dyn Plugin>> {
@@ -177,6 +261,64 @@ impl<T, const N : usize> Plugin for LaserCoolingPlugin<T, N> where T : Transitio
     }
 }
 
+/// Adds the systems for `CoolingLight` entities which are shared by all transitions.
+///
+/// This plugin is added automatically by the first [LaserCoolingPlugin].
+pub struct CoolingLightPlugin<const N : usize>;
+impl<const N : usize> Plugin for CoolingLightPlugin<N> {
+    fn build(&self, builder: &mut crate::simulation::SimulationBuilder) {
+        add_shared_systems_to_dispatch::<N>(&mut builder.dispatcher_builder, &[]);
+    }
+
+    fn deps(&self) -> Vec::<Box<dyn Plugin>> {
+        vec![Box::new(LaserPlugin::<{N}>)]
+    }
+}
+
+/// Gets the name of a system added for the transition `T`.
+///
+/// Each [LaserCoolingPlugin] adds its own copy of the systems, so the names must be distinct.
+pub fn system_name<T>(name: &str) -> String where T : TransitionComponent {
+    format!("{}<{}>", name, type_name::<T>())
+}
+
+/// Adds the systems shared by all transitions to the dispatcher.
+///
+/// #Arguments
+///
+/// `builder`: the dispatch builder to modify
+///
+/// `deps`: any dependencies that must be completed before the systems run.
+fn add_shared_systems_to_dispatch<const N: usize>(
+    builder: &mut DispatcherBuilder<'static, 'static>,
+    deps: &[&str],
+) {
+    builder.add(
+        doppler::CalculateDopplerShiftSystem::<N>,
+        "calculate_doppler_shift",
+        &["index_lasers"],
+    );
+    builder.add(
+        frequency::UpdateLaserFrequencyOffsetSystem,
+        "update_laser_frequency_offsets",
+        &[],
+    );
+    builder.add(
+        AttachIndexToCoolingLightSystem,
+        "attach_cooling_index",
+        deps,
+    );
+    builder.add(
+        repump::RepumpDarkStatesSystem::<N>,
+        "repump_dark_states",
+        &[
+            "sample_laser_intensity",
+            "calculate_doppler_shift",
+            "update_laser_frequency_offsets",
+        ],
+    );
+}
+
 /// Adds the systems required by the module to the dispatcher.
 ///
 /// #Arguments
@@ -184,103 +326,147 @@ impl<T, const N : usize> Plugin for LaserCoolingPlugin<T, N> where T : Transitio
 /// `builder`: the dispatch builder to modify
 ///
 /// `deps`: any dependencies that must be completed before the systems run.
+///
+/// `stark`: whether to calculate the Stark shift of the transition, which requires the [ElectricFieldPlugin].
 fn add_systems_to_dispatch<T, const N: usize>(
     builder: &mut DispatcherBuilder<'static, 'static>,
     deps: &[&str],
+    stark: bool,
 )  where T : TransitionComponent {
+    let name = system_name::<T>;
     builder.add(
         AttachLaserCoolingComponentsToNewlyCreatedAtomsSystem::<T, N>::default(),
-        "attach_laser_cooling_components",
+        &name("attach_laser_cooling_components"),
         deps,
     );
     builder.add(
         photons_scattered::InitialiseExpectedPhotonsScatteredVectorSystem::<T, N>::default(),
-        "initialise_expected_photons",
+        &name("initialise_expected_photons"),
         deps,
     );
     builder.add(
         rate::InitialiseRateCoefficientsSystem::<T, N>::default(),
-        "initialise_rate_coefficients",
+        &name("initialise_rate_coefficients"),
         deps,
     );
-    builder.add(
-        doppler::CalculateDopplerShiftSystem::<N>,
-        "calculate_doppler_shift",
-        &["index_lasers"],
-    );
     builder.add(
         zeeman::CalculateZeemanShiftSystem::<T>::default(),
-        "zeeman_shift",
+        &name("zeeman_shift"),
         &["magnetics_magnitude"],
     );
+    builder.add(
+        light_shift::CalculateLightShiftSystem::<T, N>::default(),
+        &name("calculate_light_shift"),
+        &["sample_laser_intensity", "sample_coherent_beams"],
+    );
+    let mut detuning_deps = vec![
+        "update_laser_frequency_offsets".to_string(),
+        "calculate_doppler_shift".to_string(),
+        name("zeeman_shift"),
+        name("calculate_light_shift"),
+        "index_lasers".to_string(),
+    ];
+    if stark {
+        builder.add(
+            stark::CalculateStarkShiftSystem::<T>::default(),
+            &name("calculate_stark_shift"),
+            &["electric_magnitude"],
+        );
+        builder.add(
+            stark::AttachStarkShiftSamplersToNewlyCreatedAtomsSystem::<T>::default(),
+            &name("attach_stark_shift_samplers"),
+            &[],
+        );
+        detuning_deps.push(name("calculate_stark_shift"));
+    }
     builder.add(
         sampler::CalculateLaserDetuningSystem::<T, N>::default(),
-        "calculate_laser_detuning",
-        &["calculate_doppler_shift", "zeeman_shift", "index_lasers"],
+        &name("calculate_laser_detuning"),
+        &detuning_deps.iter().map(String::as_str).collect::<Vec<_>>(),
     );
     builder.add(
         rate::CalculateRateCoefficientsSystem::<T, N>::default(),
-        "calculate_rate_coefficients",
-        &["calculate_laser_detuning", "initialise_rate_coefficients"],
+        &name("calculate_rate_coefficients"),
+        &[
+            &name("calculate_laser_detuning"),
+            &name("initialise_rate_coefficients"),
+            "sample_coherent_beams",
+        ],
+    );
+    builder.add(
+        attenuation::CalculateBeamAttenuationSystem::<T, N>::default(),
+        &name("calculate_beam_attenuation"),
+        &[&name("calculate_rate_coefficients")],
     );
     builder.add(
         twolevel::CalculateTwoLevelPopulationSystem::<T, N>::default(),
-        "calculate_twolevel",
-        &["calculate_rate_coefficients", "fill_laser_sampler_masks"],
+        &name("calculate_twolevel"),
+        &[&name("calculate_beam_attenuation"), "fill_laser_sampler_masks"],
     );
     builder.add(
         photons_scattered::CalculateMeanTotalPhotonsScatteredSystem::<T>::default(),
-        "calculate_total_photons",
-        &["calculate_twolevel"],
+        &name("calculate_total_photons"),
+        &[&name("calculate_twolevel")],
     );
     builder.add(
         photons_scattered::CalculateExpectedPhotonsScatteredSystem::<T, N>::default(),
-        "calculate_expected_photons",
+        &name("calculate_expected_photons"),
         &[
-            "calculate_total_photons",
+            &name("calculate_total_photons"),
             "fill_laser_sampler_masks",
-            "initialise_expected_photons",
+            &name("initialise_expected_photons"),
         ],
     );
     builder.add(
         photons_scattered::CalculateActualPhotonsScatteredSystem::<T,N>::default(),
-        "calculate_actual_photons",
-        &["calculate_expected_photons"],
+        &name("calculate_actual_photons"),
+        &[&name("calculate_expected_photons")],
+    );
+    builder.add(
+        quantum_jump::CalculateQuantumJumpsSystem::<T, N>::default(),
+        &name("calculate_quantum_jumps"),
+        &[&name("calculate_actual_photons")],
     );
     builder.add(
         force::CalculateAbsorptionForcesSystem::<T, N>::default(),
-        "calculate_absorption_forces",
-        &["calculate_actual_photons", INTEGRATE_POSITION_SYSTEM_NAME],
+        &name("calculate_absorption_forces"),
+        &[&name("calculate_quantum_jumps"), INTEGRATE_POSITION_SYSTEM_NAME],
     );
     builder.add(
         repump::RepumpSystem::<T>::default(),
-        "repump",
-        &["calculate_absorption_forces"],
+        &name("repump"),
+        &[&name("calculate_absorption_forces")],
+    );
+    builder.add(
+        repump::DarkStateBranchingSystem::<T, N>::default(),
+        &name("dark_state_branching"),
+        &[&name("calculate_absorption_forces")],
     );
     builder.add(
         force::ApplyEmissionForceSystem::<T, N>::default(),
-        "calculate_emission_forces",
+        &name("calculate_emission_forces"),
         &[
-            "calculate_absorption_forces",
+            &name("calculate_absorption_forces"),
             INTEGRATE_POSITION_SYSTEM_NAME,
         ],
     );
     builder.add(
         zeeman::AttachZeemanShiftSamplersToNewlyCreatedAtomsSystem::<T>::default(),
-        "attach_zeeman_shift_samplers",
+        &name("attach_zeeman_shift_samplers"),
         &[],
     );
     builder.add(
-        AttachIndexToCoolingLightSystem,
-        "attach_cooling_index",
-        deps,
+        light_shift::AttachLightShiftSamplersToNewlyCreatedAtomsSystem::<T>::default(),
+        &name("attach_light_shift_samplers"),
+        &[],
     );
 }
 
 #[cfg(test)]
 pub mod tests {
 
-    use crate::species::Rubidium87_780D2;
+    use crate::simulation::SimulationBuilder;
+    use crate::species::{Rubidium87_780D2, Strontium88_461, Strontium88_689};
 
     use super::*;
     use assert_approx_eq::assert_approx_eq;
@@ -317,4 +503,88 @@ pub mod tests {
             Rubidium87_780D2::frequency() + 1.0e6 * detuning
         );
     }
+
+    #[test]
+    fn test_target_transitions() {
+        let targets = TargetTransitions::of::<Strontium88_461>();
+        assert!(targets.addresses::<Strontium88_461>());
+        assert!(!targets.addresses::<Strontium88_689>());
+        assert!(drives_transition::<Strontium88_461>(Some(&targets)));
+        assert!(!drives_transition::<Strontium88_689>(Some(&targets)));
+        assert!(drives_transition::<Strontium88_689>(None));
+
+        let both = targets.and::<Strontium88_689>();
+        assert!(both.addresses::<Strontium88_461>());
+        assert!(both.addresses::<Strontium88_689>());
+    }
+
+    #[test]
+    fn test_multiple_transitions_share_simulation() {
+        let mut sim_builder = SimulationBuilder::default();
+        sim_builder.add_plugin(LaserPlugin::<4>);
+        sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_461, 4>::default());
+        sim_builder.add_plugin(LaserCoolingPlugin::<Strontium88_689, 4>::default());
+        assert!(sim_builder.has_plugin::<CoolingLightPlugin<4>>());
+        let mut sim = sim_builder.build();
+        sim.world.insert(crate::integrator::Timestep { delta: 1.0e-6 });
+        sim.step();
+    }
+
+    #[test]
+    fn test_stark_shift_requires_electric_field_plugin() {
+        use crate::atom::{Atom, Force, Mass, Position, Velocity};
+        use crate::electric::uniform::UniformElectricField;
+        use nalgebra::Vector3;
+
+        for electric in [false, true] {
+            let mut sim_builder = SimulationBuilder::default();
+            if electric {
+                sim_builder.add_plugin(ElectricFieldPlugin);
+            }
+            sim_builder.add_plugin(LaserPlugin::<4>);
+            sim_builder.add_plugin(LaserCoolingPlugin::<Rubidium87_780D2, 4>::default());
+            let mut sim = sim_builder.build();
+            sim.world.insert(crate::integrator::Timestep { delta: 1.0e-6 });
+            if electric {
+                sim.world
+                    .create_entity()
+                    .with(UniformElectricField::volts_per_cm(Vector3::new(0.0, 0.0, 1.0e4)))
+                    .build();
+            }
+            let atom = sim
+                .world
+                .create_entity()
+                .with(Position::new())
+                .with(Velocity { vel: Vector3::zeros() })
+                .with(Force::new())
+                .with(Mass { value: 87.0 })
+                .with(Rubidium87_780D2)
+                .with(stark::ExcitedStateStark::<Rubidium87_780D2>::new(1.0e-38, 0.0))
+                .with(Atom)
+                .with(NewlyCreated)
+                .build();
+            sim.step();
+            sim.step();
+
+            let samplers = sim
+                .world
+                .read_storage::<stark::StarkShiftSampler<Rubidium87_780D2>>();
+            match samplers.get(atom) {
+                Some(sampler) => {
+                    assert!(electric);
+                    assert!(sampler.shift < 0.0);
+                }
+                None => assert!(!electric),
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ElectricFieldPlugin must be added before")]
+    fn test_electric_field_plugin_after_laser_cooling_panics() {
+        let mut sim_builder = SimulationBuilder::default();
+        sim_builder.add_plugin(LaserPlugin::<4>);
+        sim_builder.add_plugin(LaserCoolingPlugin::<Rubidium87_780D2, 4>::default());
+        sim_builder.add_plugin(ElectricFieldPlugin);
+    }
 }
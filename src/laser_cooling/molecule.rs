@@ -0,0 +1,233 @@
+//! Laser cooling of molecules with vibrational branching and repumping.
+//!
+//! The cycling transition of a molecule is not closed: each spontaneous emission may leave the molecule in
+//! an excited vibrational level of the ground state, with a probability given by the `VibrationalBranching`
+//! ratios of the transition. A molecule that leaves the `v=0` level no longer scatters the cooling light.
+//!
+//! The excited vibrational levels are modelled as the named dark states of the [super::repump] module, so that
+//! molecules and atoms share the same systems. `VibrationalBranching::dark_state_branching` creates the
+//! `DarkStateBranching` resource for the cooling transition, in which the level `v` is the dark state named
+//! by `vibrational_state(v)`, and `VibrationalBranching::repump` creates the `RepumpLight` of a repump
+//! which addresses a single level. A molecule in a level which is not repumped is lost from the cycle, so
+//! the number of photons a molecule can scatter is limited by the branching ratios of the levels which are
+//! not repumped.
+
+use super::repump::{DarkStateBranching, RepumpLight};
+use super::transition::TransitionComponent;
+use serde::{Deserialize, Serialize};
+
+/// Returns the name of the dark state for the vibrational level `v` of the ground state.
+pub fn vibrational_state(level: usize) -> String {
+    format!("v={}", level)
+}
+
+/// Vibrational branching ratios of the excited state of a molecular cooling transition.
+///
+/// The `i`th entry is the probability that the molecule decays into the vibrational level `v=i` of the
+/// ground state. The cooling transition is driven from `v=0`.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct VibrationalBranching {
+    pub ratios: Vec<f64>,
+}
+
+impl VibrationalBranching {
+    /// Creates a `VibrationalBranching` from the given ratios, which are normalised to sum to one.
+    pub fn new(ratios: Vec<f64>) -> Self {
+        let total: f64 = ratios.iter().sum();
+        VibrationalBranching {
+            ratios: ratios.iter().map(|ratio| ratio / total).collect(),
+        }
+    }
+
+    /// Probability that a scattered photon leaves the molecule in a level above `repumped`.
+    ///
+    /// When the levels `v=1` to `v=repumped` are repumped, this is the probability of loss per photon.
+    pub fn loss_probability(&self, repumped: usize) -> f64 {
+        self.ratios.iter().skip(repumped + 1).sum()
+    }
+
+    /// Mean number of photons scattered before the molecule decays into a level above `repumped`.
+    pub fn photon_budget(&self, repumped: usize) -> f64 {
+        1.0 / self.loss_probability(repumped)
+    }
+
+    /// Decays from the excited state into each excited vibrational level, as named dark states.
+    fn dark_states(&self) -> impl Iterator<Item = (String, f64)> + '_ {
+        self.ratios
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(level, ratio)| (vibrational_state(level), *ratio))
+    }
+
+    /// Creates the `DarkStateBranching` resource of the cooling transition `T`, with a dark state for each
+    /// excited vibrational level.
+    pub fn dark_state_branching<T>(&self) -> DarkStateBranching<T>
+    where
+        T: TransitionComponent,
+    {
+        self.dark_states().fold(
+            DarkStateBranching::default(),
+            |branching, (state, ratio)| branching.with_state(&state, ratio),
+        )
+    }
+
+    /// Creates the `RepumpLight` of a repump which addresses the vibrational `level`.
+    ///
+    /// The repump drives the level to the excited state of the cooling transition `T`, which then decays
+    /// according to the branching ratios. Repumps are often broadened to cover the hyperfine structure of
+    /// the level; this can be modelled by increasing the `linewidth` of the returned repump.
+    ///
+    /// # Arguments
+    ///
+    /// * `level`: The vibrational level `v` addressed by the repump.
+    ///
+    /// * `frequency`: Frequency of the repump transition, in Hz.
+    pub fn repump<T>(&self, level: usize, frequency: f64) -> RepumpLight
+    where
+        T: TransitionComponent,
+    {
+        self.dark_states().fold(
+            RepumpLight::new(
+                &vibrational_state(level),
+                frequency,
+                T::linewidth(),
+                T::saturation_intensity(),
+            ),
+            |repump, (state, ratio)| repump.with_decay(&state, ratio),
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::integrator::Timestep;
+    use crate::laser::index::LaserIndex;
+    use crate::laser::intensity::{LaserIntensitySampler, LaserIntensitySamplers};
+    use crate::laser::DEFAULT_BEAM_LIMIT;
+    use crate::laser_cooling::doppler::{DopplerShiftSampler, DopplerShiftSamplers};
+    use crate::laser_cooling::frequency::LaserFrequencyOffset;
+    use crate::laser_cooling::photons_scattered::{
+        ActualPhotonsScattered, ActualPhotonsScatteredVector,
+    };
+    use crate::laser_cooling::repump::{
+        Dark, DarkState, DarkStateBranchingSystem, RepumpDarkStatesSystem,
+    };
+    use crate::laser_cooling::CoolingLight;
+    use crate::species::CalciumFluoride59_606;
+    use assert_approx_eq::assert_approx_eq;
+    use specs::prelude::*;
+
+    #[test]
+    fn test_photon_budget() {
+        let branching = VibrationalBranching::new(vec![0.96, 0.035, 0.005]);
+        assert_approx_eq!(branching.ratios.iter().sum::<f64>(), 1.0, 1e-12);
+        assert_approx_eq!(branching.loss_probability(0), 0.04, 1e-12);
+        assert_approx_eq!(branching.photon_budget(1), 200.0, 1e-9);
+        assert_eq!(branching.loss_probability(2), 0.0);
+
+        let dark_states = branching.dark_state_branching::<CalciumFluoride59_606>();
+        assert_eq!(dark_states.branches.len(), 2);
+        assert_eq!(dark_states.branches[1].state, "v=2");
+        assert_approx_eq!(dark_states.total_ratio(), 0.04, 1e-12);
+
+        let repump = branching.repump::<CalciumFluoride59_606>(1, 477.0e12);
+        assert_eq!(repump.state, "v=1");
+        assert_eq!(repump.decays.len(), 2);
+        assert_approx_eq!(repump.decays[0].ratio, 0.035, 1e-12);
+    }
+
+    #[test]
+    fn test_vibrational_repumping() {
+        let mut test_world = World::new();
+        test_world.register::<CalciumFluoride59_606>();
+        test_world
+            .register::<ActualPhotonsScatteredVector<CalciumFluoride59_606, { DEFAULT_BEAM_LIMIT }>>(
+            );
+        test_world.register::<Dark>();
+        test_world.register::<DarkState>();
+        test_world.register::<LaserIntensitySamplers<{ DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<DopplerShiftSamplers<{ DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<RepumpLight>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<LaserFrequencyOffset>();
+        test_world.register::<LaserIndex>();
+        test_world.insert(Timestep { delta: 1.0e-6 });
+
+        let branching = VibrationalBranching::new(vec![0.9, 0.1]);
+        test_world.insert(branching.dark_state_branching::<CalciumFluoride59_606>());
+        let repump = branching.repump::<CalciumFluoride59_606>(1, 477.0e12);
+        test_world
+            .create_entity()
+            .with(repump.cooling_light(0.0, 1))
+            .with(repump)
+            .with(LaserIndex {
+                index: 0,
+                initiated: true,
+            })
+            .build();
+
+        // Molecules which scatter many photons from a leaky transition are lost to v=1.
+        let mut scattered =
+            ActualPhotonsScatteredVector::<CalciumFluoride59_606, { DEFAULT_BEAM_LIMIT }> {
+                contents: [ActualPhotonsScattered::default(); DEFAULT_BEAM_LIMIT],
+            };
+        scattered.contents[1].scattered = 1000.0;
+        let molecule = test_world
+            .create_entity()
+            .with(CalciumFluoride59_606)
+            .with(scattered.clone())
+            .with(LaserIntensitySamplers {
+                contents: [LaserIntensitySampler { intensity: 0.0 }; DEFAULT_BEAM_LIMIT],
+            })
+            .with(DopplerShiftSamplers {
+                contents: [DopplerShiftSampler { doppler_shift: 0.0 }; DEFAULT_BEAM_LIMIT],
+            })
+            .build();
+
+        let state = |world: &World| {
+            world
+                .read_storage::<DarkState>()
+                .get(molecule)
+                .map(|dark_state| dark_state.state.clone())
+        };
+
+        let mut branching =
+            DarkStateBranchingSystem::<CalciumFluoride59_606, { DEFAULT_BEAM_LIMIT }>::default();
+        let mut repump = RepumpDarkStatesSystem::<{ DEFAULT_BEAM_LIMIT }>;
+        let mut run = |world: &mut World| {
+            branching.run_now(world);
+            repump.run_now(world);
+            world.maintain();
+        };
+        run(&mut test_world);
+        assert_eq!(state(&test_world), Some(vibrational_state(1)));
+        assert!(test_world.read_storage::<Dark>().get(molecule).is_some());
+
+        // Without repump light the molecule stays dark.
+        scattered.contents[1].scattered = 0.0;
+        test_world
+            .write_storage::<ActualPhotonsScatteredVector<CalciumFluoride59_606, { DEFAULT_BEAM_LIMIT }>>()
+            .insert(molecule, scattered)
+            .unwrap();
+        run(&mut test_world);
+        assert_eq!(state(&test_world), Some(vibrational_state(1)));
+
+        // A strong repump returns the molecule to the cycle.
+        test_world
+            .write_storage::<LaserIntensitySamplers<{ DEFAULT_BEAM_LIMIT }>>()
+            .insert(
+                molecule,
+                LaserIntensitySamplers {
+                    contents: [LaserIntensitySampler { intensity: 1.0e6 }; DEFAULT_BEAM_LIMIT],
+                },
+            )
+            .unwrap();
+        for _ in 0..100 {
+            run(&mut test_world);
+        }
+        assert_eq!(state(&test_world), None);
+        assert!(test_world.read_storage::<Dark>().get(molecule).is_none());
+    }
+}
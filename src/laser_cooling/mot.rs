@@ -0,0 +1,459 @@
+//! Builders for standard magneto-optical trap (MOT) beam configurations.
+//!
+//! The polarization of a `CoolingLight` is given with respect to the beam's direction, and must
+//! be chosen so that each beam pushes atoms back towards the quadrupole node. A beam along `d`
+//! sees the quadrupole field `B = G r` with projection `d . G d = gradient (1 - 3 (d . a)^2)` onto
+//! its axis, where `a` is the symmetry axis of the `QuadrupoleField3D`. The sign of this projection
+//! determines the restoring polarization, see `restoring_polarization`.
+//!
+//! The `MotBuilder` creates a `MotConfiguration` with the polarizations assigned in this way,
+//! which is validated and added to the world with `MotConfiguration::build`. The diffracted beams
+//! of a grating MOT are not part of the configuration; they are created from the incident beam by
+//! its [Grating], see [crate::laser::grating].
+
+use super::transition::AtomicTransition;
+use super::CoolingLight;
+use crate::atom::Position;
+use crate::laser::frame::Frame;
+use crate::laser::gaussian::GaussianBeam;
+use crate::laser::grating::Grating;
+use crate::magnetic::quadrupole::QuadrupoleField3D;
+use nalgebra::Vector3;
+use specs::prelude::*;
+
+/// Beams closer to the magic angle than this, in units of `1 - 3 cos^2(theta)`, have no well-defined restoring polarization.
+const MAGIC_ANGLE_TOLERANCE: f64 = 1e-6;
+
+/// Gets the polarization which gives a restoring force for a beam along `direction`.
+///
+/// Returns 0 if the beam is at the magic angle to the quadrupole axis, where the field has no
+/// component along the beam.
+pub fn restoring_polarization(direction: &Vector3<f64>, field: &QuadrupoleField3D) -> i32 {
+    let cos_theta = direction.normalize().dot(&field.direction.normalize());
+    let projection = field.gradient * (1.0 - 3.0 * cos_theta.powi(2));
+    if projection.abs() < MAGIC_ANGLE_TOLERANCE * field.gradient.abs() || projection == 0.0 {
+        0
+    } else if projection > 0.0 {
+        1
+    } else {
+        -1
+    }
+}
+
+/// A single cooling beam of a `MotConfiguration`.
+#[derive(Clone)]
+pub struct MotBeam {
+    pub beam: GaussianBeam,
+    pub cooling: CoolingLight,
+    /// True if the beam is not part of the trapping configuration, eg a push beam.
+    pub auxiliary: bool,
+    /// The grating which diffracts the beam, if any.
+    pub grating: Option<Grating>,
+}
+
+/// A set of cooling beams and the quadrupole field of a MOT.
+#[derive(Clone)]
+pub struct MotConfiguration {
+    pub field: QuadrupoleField3D,
+    /// Position of the quadrupole node, in m.
+    pub centre: Vector3<f64>,
+    pub beams: Vec<MotBeam>,
+}
+
+impl MotConfiguration {
+    /// Checks that every trapping beam has the restoring polarization for the quadrupole field.
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, mot_beam) in self.beams.iter().enumerate() {
+            if mot_beam.auxiliary {
+                continue;
+            }
+            let expected = restoring_polarization(&mot_beam.beam.direction, &self.field);
+            if expected == 0 {
+                return Err(format!(
+                    "Beam {} is at the magic angle to the quadrupole axis, so it cannot trap atoms.",
+                    i
+                ));
+            }
+            if mot_beam.cooling.polarization != expected {
+                return Err(format!(
+                    "Beam {} has polarization {} but the quadrupole field requires {}.",
+                    i, mot_beam.cooling.polarization, expected
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the quadrupole field and cooling beams to the world, and returns the beam entities.
+    ///
+    /// Panics if the configuration is not valid, see `validate`.
+    pub fn build(&self, world: &mut World) -> Vec<Entity> {
+        if let Err(message) = self.validate() {
+            panic!("Invalid MOT configuration: {}", message);
+        }
+        world
+            .create_entity()
+            .with(self.field)
+            .with(Position { pos: self.centre })
+            .build();
+        self.beams
+            .iter()
+            .map(|mot_beam| {
+                let mut builder = world
+                    .create_entity()
+                    .with(mot_beam.beam)
+                    .with(mot_beam.cooling);
+                if let Some(grating) = &mot_beam.grating {
+                    builder = builder.with(grating.clone());
+                }
+                builder.build()
+            })
+            .collect()
+    }
+}
+
+/// Parameters of the push beam of a 2D+ MOT.
+#[derive(Clone, Copy)]
+pub struct PushBeam {
+    /// Power of the push beam, in W.
+    pub power: f64,
+    /// 1/e radius of the push beam, in m.
+    pub e_radius: f64,
+    /// Detuning of the push beam from the transition, in MHz.
+    pub detuning: f64,
+}
+
+/// Creates the cooling beams of standard MOT configurations, with polarizations chosen for the given quadrupole field.
+///
+/// All trapping beams share the same detuning, power and radius, and intersect at the quadrupole node.
+#[derive(Clone, Copy)]
+pub struct MotBuilder {
+    field: QuadrupoleField3D,
+    centre: Vector3<f64>,
+    detuning: f64,
+    power: f64,
+    e_radius: f64,
+}
+
+impl MotBuilder {
+    /// # Arguments
+    ///
+    /// `field`: the quadrupole field of the MOT.
+    ///
+    /// `detuning`: detuning of the cooling beams from the transition, in MHz.
+    ///
+    /// `power`: power of each cooling beam, in W.
+    ///
+    /// `e_radius`: 1/e radius of each cooling beam, in m.
+    pub fn new(field: QuadrupoleField3D, detuning: f64, power: f64, e_radius: f64) -> Self {
+        MotBuilder {
+            field,
+            centre: Vector3::new(0.0, 0.0, 0.0),
+            detuning,
+            power,
+            e_radius,
+        }
+    }
+
+    /// Sets the position of the quadrupole node, where the beams intersect.
+    pub fn with_centre(mut self, centre: Vector3<f64>) -> Self {
+        self.centre = centre;
+        self
+    }
+
+    fn beam<T>(&self, direction: Vector3<f64>, power: f64) -> MotBeam
+    where
+        T: AtomicTransition,
+    {
+        let polarization = restoring_polarization(&direction, &self.field);
+        if polarization == 0 {
+            panic!("A MOT beam cannot lie at the magic angle to the quadrupole axis.");
+        }
+        MotBeam {
+            beam: GaussianBeam {
+                intersection: self.centre,
+                e_radius: self.e_radius,
+                power,
+                direction: direction.normalize(),
+                rayleigh_range: f64::INFINITY,
+                ellipticity: 0.0,
+            },
+            cooling: CoolingLight::for_transition::<T>(self.detuning, polarization),
+            auxiliary: false,
+            grating: None,
+        }
+    }
+
+    fn configuration(&self, beams: Vec<MotBeam>) -> MotConfiguration {
+        MotConfiguration {
+            field: self.field,
+            centre: self.centre,
+            beams,
+        }
+    }
+
+    /// Three pairs of counter-propagating beams, one pair along the quadrupole axis.
+    pub fn six_beam<T>(&self) -> MotConfiguration
+    where
+        T: AtomicTransition,
+    {
+        let axis = self.field.direction.normalize();
+        let frame = Frame::default_for_direction(axis);
+        let beams = [
+            axis,
+            -axis,
+            frame.x_vector,
+            -frame.x_vector,
+            frame.y_vector,
+            -frame.y_vector,
+        ]
+        .iter()
+        .map(|direction| self.beam::<T>(*direction, self.power))
+        .collect();
+        self.configuration(beams)
+    }
+
+    /// Four beams with tetrahedral symmetry, one propagating along the quadrupole axis, as formed by a pyramid mirror.
+    pub fn tetrahedral<T>(&self) -> MotConfiguration
+    where
+        T: AtomicTransition,
+    {
+        let axis = self.field.direction.normalize();
+        let frame = Frame::default_for_direction(axis);
+        let mut beams = vec![self.beam::<T>(axis, self.power)];
+        for i in 0..3 {
+            let phi = 2.0 * std::f64::consts::PI * i as f64 / 3.0;
+            let radial = phi.cos() * frame.x_vector + phi.sin() * frame.y_vector;
+            let direction = -axis / 3.0 + 8.0_f64.sqrt() / 3.0 * radial;
+            beams.push(self.beam::<T>(direction, self.power));
+        }
+        self.configuration(beams)
+    }
+
+    /// Two pairs of counter-propagating beams perpendicular to `axis`, which cool and trap the atoms in two dimensions.
+    ///
+    /// # Arguments
+    ///
+    /// `axis`: the axis along which atoms are not trapped.
+    ///
+    /// `first_beam`: direction of the first beam, which must be perpendicular to `axis`.
+    pub fn two_dimensional<T>(
+        &self,
+        axis: Vector3<f64>,
+        first_beam: Vector3<f64>,
+    ) -> MotConfiguration
+    where
+        T: AtomicTransition,
+    {
+        let frame = Frame::from_direction(axis, first_beam);
+        let beams = [
+            frame.x_vector,
+            -frame.x_vector,
+            frame.y_vector,
+            -frame.y_vector,
+        ]
+        .iter()
+        .map(|direction| self.beam::<T>(*direction, self.power))
+        .collect();
+        self.configuration(beams)
+    }
+
+    /// A 2D MOT with an additional push beam along `axis`, which pushes the atoms out of the MOT.
+    ///
+    /// See `two_dimensional` for the arguments.
+    pub fn two_dimensional_plus<T>(
+        &self,
+        axis: Vector3<f64>,
+        first_beam: Vector3<f64>,
+        push: PushBeam,
+    ) -> MotConfiguration
+    where
+        T: AtomicTransition,
+    {
+        let mut configuration = self.two_dimensional::<T>(axis, first_beam);
+        let direction = axis.normalize();
+        let polarization = match restoring_polarization(&direction, &self.field) {
+            0 => 1,
+            polarization => polarization,
+        };
+        configuration.beams.push(MotBeam {
+            beam: GaussianBeam {
+                intersection: self.centre,
+                e_radius: push.e_radius,
+                power: push.power,
+                direction,
+                rayleigh_range: f64::INFINITY,
+                ellipticity: 0.0,
+            },
+            cooling: CoolingLight::for_transition::<T>(push.detuning, polarization),
+            auxiliary: true,
+            grating: None,
+        });
+        configuration
+    }
+
+    /// A grating MOT, formed by a single beam propagating against the quadrupole axis onto a grating.
+    ///
+    /// The configuration contains only the incident beam, which carries a [Grating] centred on the
+    /// quadrupole axis. The first order diffracted beams, with their angles, helicity, intensity and
+    /// footprint, are created from it by the [crate::laser::grating::UpdateGratingBeamsSystem].
+    ///
+    /// # Arguments
+    ///
+    /// `distance`: distance of the grating from the quadrupole node, in m.
+    ///
+    /// `period`: period of the grating lines, in m.
+    ///
+    /// `sectors`: number of sectors of the grating, eg 3 for a tri-grating.
+    ///
+    /// `efficiency`: fraction of the incident power diffracted into each order.
+    ///
+    /// `radius`: radius of the grating, in m.
+    pub fn grating<T>(
+        &self,
+        distance: f64,
+        period: f64,
+        sectors: usize,
+        efficiency: f64,
+        radius: f64,
+    ) -> MotConfiguration
+    where
+        T: AtomicTransition,
+    {
+        let axis = self.field.direction.normalize();
+        let mut incident = self.beam::<T>(-axis, self.power);
+        incident.grating = Some(Grating::new(
+            self.centre - distance * axis,
+            axis,
+            period,
+            sectors,
+            efficiency,
+            radius,
+        ));
+        self.configuration(vec![incident])
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::species::Rubidium87_780D2;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_restoring_polarization() {
+        let field = QuadrupoleField3D::gauss_per_cm(10.0, Vector3::z());
+        assert_eq!(restoring_polarization(&Vector3::z(), &field), -1);
+        assert_eq!(restoring_polarization(&-Vector3::z(), &field), -1);
+        assert_eq!(restoring_polarization(&Vector3::x(), &field), 1);
+
+        let reversed = QuadrupoleField3D::gauss_per_cm(-10.0, Vector3::z());
+        assert_eq!(restoring_polarization(&Vector3::z(), &reversed), 1);
+
+        let magic = Vector3::new(2.0_f64.sqrt(), 0.0, 1.0);
+        assert_eq!(restoring_polarization(&magic, &field), 0);
+    }
+
+    #[test]
+    fn test_six_beam_mot() {
+        let field = QuadrupoleField3D::gauss_per_cm(10.0, Vector3::z());
+        let mot = MotBuilder::new(field, -6.0, 0.01, 0.005).six_beam::<Rubidium87_780D2>();
+        assert_eq!(mot.beams.len(), 6);
+        assert!(mot.validate().is_ok());
+        for mot_beam in mot.beams.iter() {
+            let axial = mot_beam.beam.direction.dot(&Vector3::z()).abs() > 0.5;
+            let expected = if axial { -1 } else { 1 };
+            assert_eq!(mot_beam.cooling.polarization, expected);
+        }
+
+        let mut wrong = mot.clone();
+        wrong.beams[0].cooling.polarization *= -1;
+        assert!(wrong.validate().is_err());
+    }
+
+    #[test]
+    fn test_tetrahedral_mot_is_balanced() {
+        let field = QuadrupoleField3D::gauss_per_cm(10.0, Vector3::new(1.0, 1.0, 0.0));
+        let mot = MotBuilder::new(field, -6.0, 0.01, 0.005).tetrahedral::<Rubidium87_780D2>();
+        assert_eq!(mot.beams.len(), 4);
+        assert!(mot.validate().is_ok());
+        let net: Vector3<f64> = mot.beams.iter().map(|b| b.beam.direction).sum();
+        assert_approx_eq!(net.norm(), 0.0, 1e-12);
+    }
+
+    #[test]
+    fn test_two_dimensional_plus_mot() {
+        let field = QuadrupoleField3D::gauss_per_cm(65.0, Vector3::z());
+        let push = PushBeam {
+            power: 0.01,
+            e_radius: 1e-3,
+            detuning: 0.0,
+        };
+        let mot = MotBuilder::new(field, -45.0, 0.2, 0.01)
+            .two_dimensional_plus::<Rubidium87_780D2>(
+                Vector3::z(),
+                Vector3::new(1.0, 1.0, 0.0),
+                push,
+            );
+        assert_eq!(mot.beams.len(), 5);
+        assert!(mot.validate().is_ok());
+        assert!(mot.beams[4].auxiliary);
+        assert_eq!(mot.beams[4].beam.direction, Vector3::z());
+        for mot_beam in mot.beams.iter().take(4) {
+            assert_approx_eq!(mot_beam.beam.direction.dot(&Vector3::z()), 0.0, 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_grating_mot() {
+        let mut test_world = World::new();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<QuadrupoleField3D>();
+        test_world.register::<Position>();
+        test_world.register::<Grating>();
+
+        let field = QuadrupoleField3D::gauss_per_cm(10.0, Vector3::z());
+        let mot = MotBuilder::new(field, -6.0, 0.03, 0.01)
+            .grating::<Rubidium87_780D2>(3.0e-3, 1.2e-6, 3, 0.33, 0.01);
+        assert_eq!(mot.beams.len(), 1);
+        assert!(mot.validate().is_ok());
+        assert_eq!(mot.beams[0].beam.direction, -Vector3::z());
+
+        let beams = mot.build(&mut test_world);
+        let gratings = test_world.read_storage::<Grating>();
+        let grating = gratings.get(beams[0]).expect("entity not found");
+        assert_eq!(grating.centre, Vector3::new(0.0, 0.0, -3.0e-3));
+        assert_eq!(grating.normal, Vector3::z());
+        assert_eq!(grating.sectors, 3);
+    }
+
+    #[test]
+    fn test_build_creates_entities() {
+        let mut test_world = World::new();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<QuadrupoleField3D>();
+        test_world.register::<Position>();
+
+        let field = QuadrupoleField3D::gauss_per_cm(10.0, Vector3::z());
+        let beams = MotBuilder::new(field, -6.0, 0.01, 0.005)
+            .with_centre(Vector3::new(0.0, 0.0, 1e-3))
+            .six_beam::<Rubidium87_780D2>()
+            .build(&mut test_world);
+        assert_eq!(beams.len(), 6);
+        assert_eq!(test_world.read_storage::<CoolingLight>().join().count(), 6);
+        assert_eq!(
+            test_world
+                .read_storage::<QuadrupoleField3D>()
+                .join()
+                .count(),
+            1
+        );
+        let gaussian_beams = test_world.read_storage::<GaussianBeam>();
+        assert_eq!(
+            gaussian_beams.get(beams[0]).unwrap().intersection,
+            Vector3::new(0.0, 0.0, 1e-3)
+        );
+    }
+}
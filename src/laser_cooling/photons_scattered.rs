@@ -238,10 +238,14 @@ impl<T, const N: usize> Component for ActualPhotonsScatteredVector<T, N> where T
 ///
 /// Otherwise, the entries of `ActualPhotonsScatteredVector` will be identical with those of
 /// `ExpectedPhotonsScatteredVector`.
+///
+/// With `QuantumJumps`, individual scattering events are instead drawn from the internal state of each
+/// atom, see [super::quantum_jump].
 #[derive(Clone, Copy)]
 pub enum ScatteringFluctuationsOption {
     Off,
     On,
+    QuantumJumps,
 }
 impl Default for ScatteringFluctuationsOption {
     fn default() -> Self {
@@ -305,6 +309,8 @@ impl<'a, T, const N: usize> System<'a> for CalculateActualPhotonsScatteredSystem
                             }
                         });
                 }
+                // photons are counted by the `CalculateQuantumJumpsSystem`.
+                ScatteringFluctuationsOption::QuantumJumps => {}
             },
         }
     }
@@ -0,0 +1,257 @@
+//! Quantum-jump Monte Carlo treatment of photon scattering.
+//!
+//! The default treatment of photon scattering draws a Poisson-distributed number of photons from the
+//! steady-state scattering rate of each atom. This fails for narrow transitions, such as the 7.4kHz
+//! Sr88 689nm line, where the recoil from a single photon shifts the atom out of resonance.
+//!
+//! When the `ScatteringFluctuationsOption::QuantumJumps` resource is inserted, each atom instead tracks
+//! whether it is in the ground or excited state of the transition. Absorption, stimulated emission and
+//! spontaneous emission events are drawn one at a time using the Gillespie algorithm, and each event
+//! gives the atom the recoil of a single photon.
+//!
+//! The rates of each process are held fixed during a simulation step, so that a photon recoil only changes
+//! the rates in the following step. The `Timestep` should therefore be short compared to the inverse
+//! scattering rate, so that atoms scatter at most a few photons each step.
+
+use std::marker::PhantomData;
+
+use super::photons_scattered::{ActualPhotonsScatteredVector, ScatteringFluctuationsOption};
+use super::rate::RateCoefficients;
+use super::transition::TransitionComponent;
+use crate::integrator::Timestep;
+use crate::laser::sampler::CoolingLaserSamplerMasks;
+use rand::Rng;
+use specs::prelude::*;
+
+/// The internal state of an atom, used for quantum-jump Monte Carlo simulations.
+#[derive(Clone, Copy)]
+pub struct InternalState<T>
+where
+    T: TransitionComponent,
+{
+    /// True if the atom is in the excited state of the transition.
+    pub excited: bool,
+    /// Number of photons spontaneously emitted in the last simulation step.
+    pub spontaneous_emissions: u64,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Default for InternalState<T>
+where
+    T: TransitionComponent,
+{
+    fn default() -> Self {
+        InternalState {
+            excited: false,
+            spontaneous_emissions: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Component for InternalState<T>
+where
+    T: TransitionComponent + 'static,
+{
+    type Storage = VecStorage<Self>;
+}
+
+/// Evolves the `InternalState` of each atom through one simulation step, and records the photons
+/// absorbed from each beam in the `ActualPhotonsScatteredVector`.
+///
+/// Photons emitted by stimulated emission are recorded as a negative number of photons absorbed from
+/// the stimulating beam. Only runs if the `ScatteringFluctuationsOption::QuantumJumps` resource is set.
+#[derive(Default)]
+pub struct CalculateQuantumJumpsSystem<T, const N: usize>(PhantomData<T>)
+where
+    T: TransitionComponent;
+
+impl<'a, T, const N: usize> System<'a> for CalculateQuantumJumpsSystem<T, N>
+where
+    T: TransitionComponent,
+{
+    type SystemData = (
+        Option<Read<'a, ScatteringFluctuationsOption>>,
+        ReadExpect<'a, Timestep>,
+        ReadStorage<'a, RateCoefficients<T, N>>,
+        ReadStorage<'a, CoolingLaserSamplerMasks<N>>,
+        WriteStorage<'a, InternalState<T>>,
+        WriteStorage<'a, ActualPhotonsScatteredVector<T, N>>,
+    );
+
+    fn run(
+        &mut self,
+        (fluctuations_option, timestep, rates, masks, mut states, mut actual_photons): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        match fluctuations_option.as_deref() {
+            Some(ScatteringFluctuationsOption::QuantumJumps) => (),
+            _ => return,
+        }
+
+        (&rates, &masks, &mut states, &mut actual_photons)
+            .par_join()
+            .for_each(|(rates, mask, state, actual)| {
+                let mut rng = rand::thread_rng();
+                let mut beam_rates = [0.0; N];
+                for (index, beam_rate) in beam_rates.iter_mut().enumerate() {
+                    actual.contents[index].scattered = 0.0;
+                    if mask.contents[index].filled && rates.contents[index].rate > 0.0 {
+                        *beam_rate = rates.contents[index].rate;
+                    }
+                }
+                let sum_rates: f64 = beam_rates.iter().sum();
+                state.spontaneous_emissions = 0;
+
+                let mut time = 0.0;
+                loop {
+                    let total_rate = if state.excited {
+                        T::gamma() + sum_rates
+                    } else {
+                        sum_rates
+                    };
+                    if total_rate <= 0.0 {
+                        break;
+                    }
+                    let uniform: f64 = rng.gen_range(0.0..1.0);
+                    time += -(1.0 - uniform).ln() / total_rate;
+                    if time > timestep.delta {
+                        break;
+                    }
+
+                    // Choose which process occurs. Spontaneous emission is only possible from the excited state.
+                    let mut choice = rng.gen_range(0.0..total_rate);
+                    if state.excited {
+                        if choice < T::gamma() {
+                            state.spontaneous_emissions += 1;
+                            state.excited = false;
+                            continue;
+                        }
+                        choice -= T::gamma();
+                    }
+                    let beam = beam_rates
+                        .iter()
+                        .position(|rate| {
+                            choice -= rate;
+                            choice < 0.0
+                        })
+                        .unwrap_or_else(|| beam_rates.iter().rposition(|r| *r > 0.0).unwrap());
+                    actual.contents[beam].scattered += if state.excited { -1.0 } else { 1.0 };
+                    state.excited = !state.excited;
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::laser::sampler::LaserSamplerMask;
+    use crate::laser_cooling::photons_scattered::ActualPhotonsScattered;
+    use crate::laser_cooling::rate::RateCoefficient;
+    use crate::laser_cooling::transition::AtomicTransition;
+    use crate::species::Strontium88_689;
+    use assert_approx_eq::assert_approx_eq;
+
+    const N: usize = 2;
+
+    #[test]
+    fn test_quantum_jumps_system() {
+        let mut test_world = World::new();
+        test_world.register::<RateCoefficients<Strontium88_689, N>>();
+        test_world.register::<CoolingLaserSamplerMasks<N>>();
+        test_world.register::<InternalState<Strontium88_689>>();
+        test_world.register::<ActualPhotonsScatteredVector<Strontium88_689, N>>();
+        let dt = 1.0e-6;
+        test_world.insert(Timestep { delta: dt });
+        test_world.insert(ScatteringFluctuationsOption::QuantumJumps);
+
+        let mut rate = RateCoefficient::<Strontium88_689>::default();
+        rate.rate = Strontium88_689::gamma();
+        let atom = test_world
+            .create_entity()
+            .with(RateCoefficients {
+                contents: [rate; N],
+            })
+            .with(CoolingLaserSamplerMasks {
+                contents: [
+                    LaserSamplerMask { filled: true },
+                    LaserSamplerMask { filled: false },
+                ],
+            })
+            .with(InternalState::<Strontium88_689>::default())
+            .with(ActualPhotonsScatteredVector {
+                contents: [ActualPhotonsScattered::<Strontium88_689>::default(); N],
+            })
+            .build();
+
+        let mut system = CalculateQuantumJumpsSystem::<Strontium88_689, N>::default();
+        let steps = 200000;
+        let mut spontaneous = 0;
+        let mut absorbed = 0.0;
+        let mut excited = 0;
+        for _ in 0..steps {
+            system.run_now(&test_world);
+            let states = test_world.read_storage::<InternalState<Strontium88_689>>();
+            let state = states.get(atom).expect("entity not found");
+            spontaneous += state.spontaneous_emissions;
+            if state.excited {
+                excited += 1;
+            }
+            let photons =
+                test_world.read_storage::<ActualPhotonsScatteredVector<Strontium88_689, N>>();
+            let photons = photons.get(atom).expect("entity not found");
+            absorbed += photons.contents[0].scattered;
+            assert_eq!(photons.contents[1].scattered, 0.0);
+        }
+
+        // The steady state excited population is R / (gamma + 2R) = 1/3.
+        let time = steps as f64 * dt;
+        assert_approx_eq!(excited as f64 / steps as f64, 1.0 / 3.0, 0.02);
+        assert_approx_eq!(
+            spontaneous as f64 / time,
+            Strontium88_689::gamma() / 3.0,
+            0.05 * Strontium88_689::gamma() / 3.0
+        );
+        // Every photon spontaneously emitted was absorbed from the beam.
+        assert!((absorbed - spontaneous as f64).abs() <= 1.0);
+    }
+
+    #[test]
+    fn test_quantum_jumps_require_option() {
+        let mut test_world = World::new();
+        test_world.register::<RateCoefficients<Strontium88_689, N>>();
+        test_world.register::<CoolingLaserSamplerMasks<N>>();
+        test_world.register::<InternalState<Strontium88_689>>();
+        test_world.register::<ActualPhotonsScatteredVector<Strontium88_689, N>>();
+        test_world.insert(Timestep { delta: 1.0e-3 });
+        test_world.insert(ScatteringFluctuationsOption::On);
+
+        let mut rate = RateCoefficient::<Strontium88_689>::default();
+        rate.rate = Strontium88_689::gamma();
+        let mut scattered = ActualPhotonsScattered::<Strontium88_689>::default();
+        scattered.scattered = 7.0;
+        let atom = test_world
+            .create_entity()
+            .with(RateCoefficients {
+                contents: [rate; N],
+            })
+            .with(CoolingLaserSamplerMasks {
+                contents: [LaserSamplerMask { filled: true }; N],
+            })
+            .with(InternalState::<Strontium88_689>::default())
+            .with(ActualPhotonsScatteredVector {
+                contents: [scattered; N],
+            })
+            .build();
+
+        let mut system = CalculateQuantumJumpsSystem::<Strontium88_689, N>::default();
+        system.run_now(&test_world);
+        let photons = test_world.read_storage::<ActualPhotonsScatteredVector<Strontium88_689, N>>();
+        assert_eq!(
+            photons.get(atom).expect("entity not found").contents[0].scattered,
+            7.0
+        );
+    }
+}
@@ -4,11 +4,13 @@ extern crate serde;
 
 use std::marker::PhantomData;
 
-use super::CoolingLight;
+use super::{drives_transition, CoolingLight, TargetTransitions};
+use super::repump::Dark;
 use super::transition::{TransitionComponent};
 use crate::laser::gaussian::GaussianBeam;
 use crate::laser::index::LaserIndex;
 use crate::laser::intensity::LaserIntensitySamplers;
+use crate::laser::polarization::Polarization;
 use crate::laser_cooling::sampler::LaserDetuningSamplers;
 use crate::magnetic::MagneticFieldSampler;
 use serde::Serialize;
@@ -19,6 +21,12 @@ use specs::prelude::*;
 pub struct RateCoefficient<T> where T : TransitionComponent {
     /// rate coefficient in Hz
     pub rate: f64,
+    /// part of the rate coefficient due to the sigma plus transition, in Hz
+    pub sigma_plus: f64,
+    /// part of the rate coefficient due to the sigma minus transition, in Hz
+    pub sigma_minus: f64,
+    /// part of the rate coefficient due to the pi transition, in Hz
+    pub pi: f64,
     phantom: PhantomData<T>
 }
 
@@ -27,6 +35,9 @@ impl<T> Default for RateCoefficient<T> where T : TransitionComponent {
         RateCoefficient {
             /// rate coefficient in Hz
             rate: f64::NAN,
+            sigma_plus: f64::NAN,
+            sigma_minus: f64::NAN,
+            pi: f64::NAN,
             phantom: PhantomData
         }
     }
@@ -71,6 +82,13 @@ impl<'a, T, const N: usize> System<'a> for InitialiseRateCoefficientsSystem<T, N
 /// This is also the System that currently takes care of handling the polarizations correctly.
 /// The polarization is projected onto the quantization axis given by the local magnetic
 /// field vector. For fully polarized CoolingLight all projection pre-factors add up to 1.
+///
+/// Beams with a `Polarization` component are decomposed into σ+, σ- and π components using
+/// the full polarization vector, which allows linear, elliptical and partially polarized light.
+/// Otherwise, the beam is circularly polarized with the helicity given by `CoolingLight::polarization`.
+///
+/// The rate coefficient is zero for beams whose `TargetTransitions` do not include the transition,
+/// and for atoms which are in a `Dark` state.
 #[derive(Default)]
 pub struct CalculateRateCoefficientsSystem<T, const N: usize>(PhantomData<T>) where T : TransitionComponent;
 
@@ -82,7 +100,10 @@ impl<'a, T, const N: usize> System<'a> for CalculateRateCoefficientsSystem<T, N>
         ReadStorage<'a, LaserIntensitySamplers<N>>,
         ReadStorage<'a, T>,
         ReadStorage<'a, GaussianBeam>,
+        ReadStorage<'a, Polarization>,
+        ReadStorage<'a, TargetTransitions>,
         ReadStorage<'a, MagneticFieldSampler>,
+        ReadStorage<'a, Dark>,
         WriteStorage<'a, RateCoefficients<T, N>>,
     );
     fn run(
@@ -94,24 +115,61 @@ impl<'a, T, const N: usize> System<'a> for CalculateRateCoefficientsSystem<T, N>
             laser_intensities,
             atomic_transition,
             gaussian_beam,
+            polarizations,
+            targets,
             magnetic_field_sampler,
+            dark,
             mut rate_coefficients,
         ): Self::SystemData,
     ) {
         use rayon::prelude::*;
 
-        for (cooling, index, gaussian) in (&cooling_light, &cooling_index, &gaussian_beam).join() {
+        for (cooling, index, gaussian, polarization, target) in (
+            &cooling_light,
+            &cooling_index,
+            &gaussian_beam,
+            polarizations.maybe(),
+            targets.maybe(),
+        )
+            .join()
+        {
+            // Beams which do not address this transition do not scatter photons.
+            if !drives_transition::<T>(target) {
+                (&atomic_transition, &mut rate_coefficients)
+                    .par_join()
+                    .for_each(|(_atominfo, rates)| {
+                        let rate = &mut rates.contents[index.index];
+                        rate.sigma_plus = 0.0;
+                        rate.sigma_minus = 0.0;
+                        rate.pi = 0.0;
+                        rate.rate = 0.0;
+                    });
+                continue;
+            }
+
             (
                 &laser_detunings,
                 &laser_intensities,
                 &atomic_transition,
                 &magnetic_field_sampler,
+                dark.maybe(),
                 &mut rate_coefficients,
             )
                 .par_join()
-                .for_each(|(detunings, intensities, _atominfo, bfield, rates)| {
+                .for_each(|(detunings, intensities, _atominfo, bfield, dark, rates)| {
+                    // Atoms in a dark state do not scatter photons.
+                    if dark.is_some() {
+                        let rate = &mut rates.contents[index.index];
+                        rate.sigma_plus = 0.0;
+                        rate.sigma_minus = 0.0;
+                        rate.pi = 0.0;
+                        rate.rate = 0.0;
+                        return;
+                    }
+
                     let beam_direction_vector = gaussian.direction.normalize();
-                    let costheta = if bfield.field.norm_squared() < (10.0 * f64::EPSILON) {
+                    let no_field = bfield.field.norm_squared() < (10.0 * f64::EPSILON);
+                    let costheta = if no_field {
                         0.0
                     } else {
                         beam_direction_vector
@@ -119,24 +177,37 @@ impl<'a, T, const N: usize> System<'a> for CalculateRateCoefficientsSystem<T, N>
                             .dot(&bfield.field.normalize())
                     };
 
+                    // Fractions of the light driving sigma+, sigma- and pi transitions.
+                    let (sigma_plus, sigma_minus, pi) = match polarization {
+                        Some(polarization) if !no_field => polarization
+                            .transition_fractions(&beam_direction_vector, &bfield.field),
+                        _ => (
+                            0.25 * (cooling.polarization as f64 * costheta + 1.).powf(2.),
+                            0.25 * (cooling.polarization as f64 * costheta - 1.).powi(2),
+                            0.5 * (1. - costheta.powf(2.)),
+                        ),
+                    };
+
                     let prefactor =
                         T::rate_prefactor() * intensities.contents[index.index].intensity;
                     let gamma = T::gamma();
 
-                    let scatter1 =
-                        0.25 * (cooling.polarization as f64 * costheta + 1.).powf(2.) * prefactor
-                            / (detunings.contents[index.index].detuning_sigma_plus.powi(2)
-                                + (gamma / 2.0).powi(2));
+                    let scatter1 = sigma_plus * prefactor
+                        / (detunings.contents[index.index].detuning_sigma_plus.powi(2)
+                            + (gamma / 2.0).powi(2));
 
-                    let scatter2 =
-                        0.25 * (cooling.polarization as f64 * costheta - 1.).powi(2) * prefactor
-                            / (detunings.contents[index.index].detuning_sigma_minus.powi(2)
-                                + (gamma / 2.0).powi(2));
+                    let scatter2 = sigma_minus * prefactor
+                        / (detunings.contents[index.index].detuning_sigma_minus.powi(2)
+                            + (gamma / 2.0).powi(2));
 
-                    let scatter3 = 0.5 * (1. - costheta.powf(2.)) * prefactor
+                    let scatter3 = pi * prefactor
                         / (detunings.contents[index.index].detuning_pi.powi(2)
                             + (gamma / 2.0).powi(2));
-                    rates.contents[index.index].rate = scatter1 + scatter2 + scatter3;
+                    let rate = &mut rates.contents[index.index];
+                    rate.sigma_plus = scatter1;
+                    rate.sigma_minus = scatter2;
+                    rate.pi = scatter3;
+                    rate.rate = scatter1 + scatter2 + scatter3;
                 });
         }
     }
@@ -151,7 +222,7 @@ pub mod tests {
     use crate::laser::DEFAULT_BEAM_LIMIT;
     use crate::laser_cooling::CoolingLight;
     use crate::laser_cooling::transition::AtomicTransition;
-    use crate::species::Strontium88_461;
+    use crate::species::{Strontium88_461, Strontium88_689};
     use assert_approx_eq::assert_approx_eq;
     extern crate nalgebra;
     use nalgebra::{Matrix3, Vector3};
@@ -168,10 +239,13 @@ pub mod tests {
         test_world.register::<LaserIndex>();
         test_world.register::<CoolingLight>();
         test_world.register::<GaussianBeam>();
+        test_world.register::<Polarization>();
+        test_world.register::<TargetTransitions>();
         test_world.register::<LaserDetuningSamplers<Strontium88_461, { DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<LaserIntensitySamplers<{ DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<Strontium88_461>();
         test_world.register::<MagneticFieldSampler>();
+        test_world.register::<Dark>();
         test_world.register::<RateCoefficients<Strontium88_461, { DEFAULT_BEAM_LIMIT }>>();
 
         let wavelength = 461e-9;
@@ -195,6 +269,28 @@ pub mod tests {
             })
             .build();
 
+        // A beam which addresses a different transition does not drive the atoms.
+        test_world
+            .create_entity()
+            .with(CoolingLight {
+                polarization: 1,
+                wavelength,
+            })
+            .with(LaserIndex {
+                index: 1,
+                initiated: true,
+            })
+            .with(GaussianBeam {
+                direction: Vector3::new(1.0, 0.0, 0.0),
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius: 2.0,
+                power: 1.0,
+                rayleigh_range: 1.0,
+                ellipticity: 0.0,
+            })
+            .with(TargetTransitions::of::<Strontium88_689>())
+            .build();
+
         let detuning = -1.0e7;
         let field = Vector3::new(0.0, 0.0, 1.0);
         let intensity = 1.0;
@@ -225,6 +321,29 @@ pub mod tests {
             })
             .build();
 
+        // An atom in a dark state does not scatter photons.
+        let dark_atom = test_world
+            .create_entity()
+            .with(LaserDetuningSamplers {
+                contents: [lds; DEFAULT_BEAM_LIMIT],
+            })
+            .with(LaserIntensitySamplers {
+                contents: [LaserIntensitySampler { intensity };
+                    DEFAULT_BEAM_LIMIT],
+            })
+            .with(Strontium88_461)
+            .with(MagneticFieldSampler {
+                field,
+                magnitude: 1.0,
+                gradient: Vector3::new(0.0, 0.0, 0.0),
+                jacobian: Matrix3::zeros(),
+            })
+            .with(RateCoefficients {
+                contents: [RateCoefficient::<Strontium88_461>::default(); crate::laser::DEFAULT_BEAM_LIMIT],
+            })
+            .with(Dark)
+            .build();
+
         let mut system = CalculateRateCoefficientsSystem::<Strontium88_461, { DEFAULT_BEAM_LIMIT }>::default();
         system.run_now(&test_world);
         test_world.maintain();
@@ -247,5 +366,107 @@ pub mod tests {
             scatter1 + scatter2 + scatter3,
             1e-5_f64
         );
+        assert_approx_eq!(
+            sampler_storage
+                .get(atom1)
+                .expect("entity not found")
+                .contents[0]
+                .pi,
+            scatter3,
+            1e-5_f64
+        );
+        assert_eq!(
+            sampler_storage
+                .get(atom1)
+                .expect("entity not found")
+                .contents[1]
+                .rate,
+            0.0
+        );
+        assert_eq!(
+            sampler_storage
+                .get(dark_atom)
+                .expect("entity not found")
+                .contents[0]
+                .rate,
+            0.0
+        );
+    }
+
+    /// Tests that light linearly polarized along the magnetic field drives only π transitions.
+    #[test]
+    fn test_rate_coefficients_with_polarization() {
+        let mut test_world = World::new();
+
+        test_world.register::<LaserIndex>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<GaussianBeam>();
+        test_world.register::<Polarization>();
+        test_world.register::<TargetTransitions>();
+        test_world.register::<LaserDetuningSamplers<Strontium88_461, 1>>();
+        test_world.register::<LaserIntensitySamplers<1>>();
+        test_world.register::<Strontium88_461>();
+        test_world.register::<MagneticFieldSampler>();
+        test_world.register::<Dark>();
+        test_world.register::<RateCoefficients<Strontium88_461, 1>>();
+
+        test_world
+            .create_entity()
+            .with(CoolingLight {
+                polarization: 1,
+                wavelength: 461e-9,
+            })
+            .with(LaserIndex {
+                index: 0,
+                initiated: true,
+            })
+            .with(GaussianBeam {
+                direction: Vector3::new(1.0, 0.0, 0.0),
+                intersection: Vector3::new(0.0, 0.0, 0.0),
+                e_radius: 2.0,
+                power: 1.0,
+                rayleigh_range: 1.0,
+                ellipticity: 0.0,
+            })
+            .with(Polarization::linear(Vector3::z()))
+            .build();
+
+        // Only the π transition is resonant.
+        let mut lds = LaserDetuningSampler::<Strontium88_461>::default();
+        lds.detuning_sigma_plus = 1.0e9;
+        lds.detuning_sigma_minus = 1.0e9;
+        lds.detuning_pi = 0.0;
+        let intensity = 1.0;
+
+        let atom = test_world
+            .create_entity()
+            .with(LaserDetuningSamplers { contents: [lds] })
+            .with(LaserIntensitySamplers {
+                contents: [LaserIntensitySampler { intensity }],
+            })
+            .with(Strontium88_461)
+            .with(MagneticFieldSampler {
+                field: Vector3::new(0.0, 0.0, 1.0),
+                magnitude: 1.0,
+                gradient: Vector3::new(0.0, 0.0, 0.0),
+                jacobian: Matrix3::zeros(),
+            })
+            .with(RateCoefficients {
+                contents: [RateCoefficient::<Strontium88_461>::default()],
+            })
+            .build();
+
+        let mut system = CalculateRateCoefficientsSystem::<Strontium88_461, 1>::default();
+        system.run_now(&test_world);
+        test_world.maintain();
+        let rates = test_world.read_storage::<RateCoefficients<Strontium88_461, 1>>();
+
+        let expected = Strontium88_461::rate_prefactor() * intensity
+            / (Strontium88_461::gamma() / 2.).powi(2);
+        assert_approx_eq!(
+            rates.get(atom).unwrap().contents[0].rate / expected,
+            1.0,
+            1e-6
+        );
     }
 }
@@ -1,12 +1,33 @@
 //! Handling of dark states and repumping
+//!
+//! Atoms which scatter photons from a transition may decay into states which are not coupled by the cooling
+//! light. Two models are provided:
+//!
+//! * A global `RepumpLoss` resource, which marks atoms as permanently `Dark` with a fixed chance per photon.
+//!
+//! * Named dark states, with branching ratios given for each transition by a `DarkStateBranching` resource.
+//!   Atoms in a named dark state carry a `DarkState` component, and are returned to the cooling cycle by
+//!   laser beams with a `RepumpLight` component. Repump beams are `CoolingLight` entities, so their
+//!   intensity, detuning and spatial profile are calculated by the laser pipeline. They should be given an
+//!   empty [TargetTransitions](crate::laser_cooling::TargetTransitions) so that they do not drive the
+//!   cooling transition.
+//!   The excited vibrational levels of molecules are also named dark states, see [molecule](crate::laser_cooling::molecule).
 
 use std::marker::PhantomData;
 
 use rand;
 extern crate specs;
-use crate::laser_cooling::photons_scattered::TotalPhotonsScattered;
+use crate::constant;
+use crate::integrator::Timestep;
+use crate::laser::index::LaserIndex;
+use crate::laser::intensity::LaserIntensitySamplers;
+use crate::laser_cooling::doppler::DopplerShiftSamplers;
+use crate::laser_cooling::frequency::LaserFrequencyOffset;
+use crate::laser_cooling::photons_scattered::{ActualPhotonsScatteredVector, TotalPhotonsScattered};
+use crate::laser_cooling::CoolingLight;
 use rand::Rng;
-use specs::{Component, Entities, LazyUpdate, Read, ReadStorage, System, VecStorage};
+use serde::{Deserialize, Serialize};
+use specs::{Component, Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, VecStorage, HashMapStorage};
 
 use super::transition::{TransitionComponent};
 
@@ -24,10 +45,14 @@ pub struct RepumpLoss {
 }
 
 impl RepumpLoss {
+    /// Returns true if an atom is depumped while scattering the given number of photons.
+    ///
+    /// The atom survives each scattering event with probability `1 - depump_chance`, so it is lost with
+    /// probability `1 - (1 - depump_chance)^n`.
     pub fn if_loss(&self, number_scattering_events: f64) -> bool {
         let mut rng = rand::thread_rng();
         let result: f64 = rng.gen_range(0.0..1.0);
-        result < (1.0 - self.depump_chance).powf(number_scattering_events)
+        result < 1.0 - (1.0 - self.depump_chance).powf(number_scattering_events)
     }
 }
 
@@ -59,3 +84,434 @@ impl<'a, T> System<'a> for RepumpSystem<T> where T : TransitionComponent {
         }
     }
 }
+
+/// A decay into the named dark state `state`, with probability `ratio`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DarkStateBranch {
+    pub state: String,
+    pub ratio: f64,
+}
+
+/// Chooses the dark state an atom decays into, or `None` if it decays back into the cooling cycle.
+fn choose_branch<R: Rng + ?Sized>(branches: &[DarkStateBranch], rng: &mut R) -> Option<String> {
+    let mut remaining: f64 = rng.gen_range(0.0..1.0);
+    for branch in branches {
+        if remaining < branch.ratio {
+            return Some(branch.state.clone());
+        }
+        remaining -= branch.ratio;
+    }
+    None
+}
+
+/// Branching ratios from the excited state of the transition `T` into named dark states.
+///
+/// Insert this as a resource to enable decay into dark states for atoms with the transition `T`.
+pub struct DarkStateBranching<T> where T : TransitionComponent {
+    pub branches: Vec<DarkStateBranch>,
+    marker: PhantomData<T>,
+}
+
+impl<T> Default for DarkStateBranching<T> where T : TransitionComponent {
+    fn default() -> Self {
+        DarkStateBranching {
+            branches: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> DarkStateBranching<T> where T : TransitionComponent {
+    /// Adds a decay into the named dark `state`, with the probability `ratio` per scattered photon.
+    pub fn with_state(mut self, state: &str, ratio: f64) -> Self {
+        self.branches.push(DarkStateBranch {
+            state: state.to_string(),
+            ratio,
+        });
+        self
+    }
+
+    /// Probability that an atom decays into any dark state after scattering a photon.
+    pub fn total_ratio(&self) -> f64 {
+        self.branches.iter().map(|branch| branch.ratio).sum()
+    }
+}
+
+/// The named dark state occupied by an atom.
+///
+/// Atoms in a `DarkState` are also marked `Dark`, so they do not scatter the cooling light.
+#[derive(Clone, Debug)]
+pub struct DarkState {
+    pub state: String,
+}
+
+impl Component for DarkState {
+    type Storage = VecStorage<Self>;
+}
+
+/// Marks a `CoolingLight` as a repump laser, which pumps atoms out of the named dark `state`.
+///
+/// The atom is excited at a rate calculated from the intensity of the beam and its detuning from the
+/// repump transition, including the Doppler shift. The excited state decays back into the cooling cycle,
+/// except for the `decays` into other dark states. For example, the 707nm repump of the Sr 3P2 state also
+/// pumps atoms into the 3P0 state, which must be repumped by a second, 679nm laser.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RepumpLight {
+    /// Name of the dark state addressed by the repump.
+    pub state: String,
+    /// Frequency of the repump transition, in Hz.
+    pub frequency: f64,
+    /// Linewidth of the repump transition, in Hz.
+    pub linewidth: f64,
+    /// Saturation intensity of the repump transition, in W/m^2.
+    pub saturation_intensity: f64,
+    /// Decays from the excited state of the repump transition into dark states.
+    pub decays: Vec<DarkStateBranch>,
+}
+
+impl RepumpLight {
+    /// Creates a `RepumpLight` for the named dark `state`, which returns all atoms to the cooling cycle.
+    pub fn new(state: &str, frequency: f64, linewidth: f64, saturation_intensity: f64) -> Self {
+        RepumpLight {
+            state: state.to_string(),
+            frequency,
+            linewidth,
+            saturation_intensity,
+            decays: Vec::new(),
+        }
+    }
+
+    /// Adds a decay from the excited state of the repump transition into the named dark `state`.
+    pub fn with_decay(mut self, state: &str, ratio: f64) -> Self {
+        self.decays.push(DarkStateBranch {
+            state: state.to_string(),
+            ratio,
+        });
+        self
+    }
+
+    /// Creates the `CoolingLight` of the repump laser.
+    ///
+    /// # Arguments
+    ///
+    /// * `detuning`: Detuning of the laser from the repump transition in units of MHz
+    ///
+    /// * `polarization`: Polarization of the repump beam.
+    pub fn cooling_light(&self, detuning: f64, polarization: i32) -> CoolingLight {
+        CoolingLight {
+            wavelength: constant::C / (self.frequency + detuning * 1.0e6),
+            polarization,
+        }
+    }
+
+    /// Rate at which atoms are excited by the repump, in Hz.
+    ///
+    /// # Arguments
+    ///
+    /// * `intensity`: intensity of the repump light, in W/m^2.
+    ///
+    /// * `detuning`: detuning of the repump light from the transition, in rad/s.
+    pub fn rate(&self, intensity: f64, detuning: f64) -> f64 {
+        let gamma = 2.0 * constant::PI * self.linewidth;
+        let s = intensity / self.saturation_intensity;
+        gamma / 2.0 * s / (1.0 + s + (2.0 * detuning / gamma).powi(2))
+    }
+}
+
+impl Component for RepumpLight {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Moves atoms which scatter photons from the transition `T` into named dark states.
+///
+/// Atoms decay into the dark states of the `DarkStateBranching<T>` resource, if it exists. Atoms which are
+/// already `Dark` are not changed. Atoms are returned to the cooling cycle by the [RepumpDarkStatesSystem].
+#[derive(Default)]
+pub struct DarkStateBranchingSystem<T, const N: usize>(PhantomData<T>) where T : TransitionComponent;
+
+impl<'a, T, const N: usize> System<'a> for DarkStateBranchingSystem<T, N> where T : TransitionComponent {
+    type SystemData = (
+        Entities<'a>,
+        Option<Read<'a, DarkStateBranching<T>>>,
+        ReadStorage<'a, T>,
+        ReadStorage<'a, ActualPhotonsScatteredVector<T, N>>,
+        ReadStorage<'a, Dark>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, branching, transitions, scattered, dark, updater): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+        use specs::ParJoin;
+
+        let branching = match &branching {
+            Some(branching) => branching,
+            None => return,
+        };
+        // Choose the dark state in proportion to the branching ratios.
+        let total = branching.total_ratio();
+        let normalised: Vec<DarkStateBranch> = branching
+            .branches
+            .iter()
+            .map(|branch| DarkStateBranch {
+                state: branch.state.clone(),
+                ratio: branch.ratio / total,
+            })
+            .collect();
+
+        (&entities, &transitions, &scattered, !&dark)
+            .par_join()
+            .for_each(|(entity, _transition, scattered, _)| {
+                let mut rng = rand::thread_rng();
+                let photons: f64 = scattered.contents.iter().map(|s| s.scattered).sum();
+                let loss = 1.0 - (1.0 - total).powf(photons);
+                if rng.gen_range(0.0..1.0) < loss {
+                    if let Some(state) = choose_branch(&normalised, &mut rng) {
+                        updater.insert(entity, DarkState { state });
+                        updater.insert(entity, Dark);
+                    }
+                }
+            });
+    }
+}
+
+/// Returns atoms in named dark states to the cooling cycle.
+///
+/// Atoms in a dark state are excited by the `RepumpLight` beams which address it, and then decay either into
+/// another dark state or back into the cooling cycle. Atoms which are `Dark` without a `DarkState` are not
+/// changed. Repumping does not depend on the cooling transition, so a single system is shared by all of them.
+#[derive(Default)]
+pub struct RepumpDarkStatesSystem<const N: usize>;
+
+impl<'a, const N: usize> System<'a> for RepumpDarkStatesSystem<N> {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, DarkState>,
+        ReadStorage<'a, LaserIntensitySamplers<N>>,
+        ReadStorage<'a, DopplerShiftSamplers<N>>,
+        ReadStorage<'a, RepumpLight>,
+        ReadStorage<'a, CoolingLight>,
+        ReadStorage<'a, LaserFrequencyOffset>,
+        ReadStorage<'a, LaserIndex>,
+        ReadExpect<'a, Timestep>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            dark_states,
+            intensities,
+            doppler_shifts,
+            repumps,
+            cooling_light,
+            frequency_offsets,
+            indices,
+            timestep,
+            updater,
+        ): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+        use specs::ParJoin;
+
+        // Detuning of each repump beam from its transition, in rad/s, excluding the Doppler shift.
+        type CachedRepump = (RepumpLight, LaserIndex, f64);
+        let repump_cache: Vec<CachedRepump> = (
+            &repumps,
+            &cooling_light,
+            frequency_offsets.maybe(),
+            &indices,
+        )
+            .join()
+            .map(|(repump, cooling, offset, index)| {
+                let detuning = 2.0 * constant::PI * (cooling.frequency() - repump.frequency)
+                    + offset.map_or(0.0, |o| o.offset);
+                (repump.clone(), *index, detuning)
+            })
+            .collect();
+        if repump_cache.is_empty() {
+            return;
+        }
+
+        (&entities, &dark_states, &intensities, &doppler_shifts)
+            .par_join()
+            .for_each(|(entity, dark_state, intensities, doppler)| {
+                let mut rng = rand::thread_rng();
+                // Find the total rate at which the atom is excited out of the dark state.
+                let rates: Vec<(&RepumpLight, f64)> = repump_cache
+                    .iter()
+                    .filter(|(repump, _, _)| repump.state == dark_state.state)
+                    .map(|(repump, index, detuning)| {
+                        let detuning = detuning - doppler.contents[index.index].doppler_shift;
+                        let intensity = intensities.contents[index.index].intensity;
+                        (repump, repump.rate(intensity, detuning))
+                    })
+                    .collect();
+                let total_rate: f64 = rates.iter().map(|(_, rate)| rate).sum();
+                let probability = 1.0 - (-total_rate * timestep.delta).exp();
+                if total_rate <= 0.0 || rng.gen_range(0.0..1.0) >= probability {
+                    return;
+                }
+
+                // Choose the beam which excited the atom, then the state the atom decays into.
+                let mut remaining = rng.gen_range(0.0..total_rate);
+                let mut excited_by = rates[rates.len() - 1].0;
+                for (repump, rate) in rates.iter() {
+                    if remaining < *rate {
+                        excited_by = repump;
+                        break;
+                    }
+                    remaining -= rate;
+                }
+                match choose_branch(&excited_by.decays, &mut rng) {
+                    Some(state) => updater.insert(entity, DarkState { state }),
+                    None => {
+                        updater.remove::<DarkState>(entity);
+                        updater.remove::<Dark>(entity);
+                    }
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::laser::intensity::LaserIntensitySampler;
+    use crate::laser::DEFAULT_BEAM_LIMIT;
+    use crate::laser_cooling::doppler::DopplerShiftSampler;
+    use crate::laser_cooling::photons_scattered::ActualPhotonsScattered;
+    use crate::species::Strontium88_461;
+    use assert_approx_eq::assert_approx_eq;
+    use specs::{Builder, RunNow, World, WorldExt};
+
+    #[test]
+    fn test_repump_loss() {
+        let never = RepumpLoss { depump_chance: 0.0 };
+        let always = RepumpLoss { depump_chance: 1.0 };
+        for _ in 0..100 {
+            assert!(!never.if_loss(1000.0));
+            assert!(always.if_loss(1.0));
+            assert!(!always.if_loss(0.0));
+        }
+
+        // The chance of loss after n events is 1-(1-p)^n.
+        let repump = RepumpLoss { depump_chance: 0.1 };
+        let samples = 100_000;
+        let lost = (0..samples).filter(|_| repump.if_loss(5.0)).count();
+        assert_approx_eq!(lost as f64 / samples as f64, 1.0 - 0.9_f64.powi(5), 0.01);
+    }
+
+    #[test]
+    fn test_repump_rate() {
+        let repump = RepumpLight::new("3P2", 423.9e12, 7.0e6, 26.0);
+        let gamma = 2.0 * constant::PI * 7.0e6;
+        assert_approx_eq!(repump.rate(26.0, 0.0), gamma / 4.0, 1e-6);
+        assert_approx_eq!(repump.rate(26.0, gamma / 2.0), gamma / 6.0, 1e-6);
+        assert_approx_eq!(repump.cooling_light(0.0, 1).frequency(), 423.9e12, 1.0);
+    }
+
+    #[test]
+    fn test_dark_state_system() {
+        let mut test_world = World::new();
+        test_world.register::<Strontium88_461>();
+        test_world.register::<ActualPhotonsScatteredVector<Strontium88_461, { DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<Dark>();
+        test_world.register::<DarkState>();
+        test_world.register::<LaserIntensitySamplers<{ DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<DopplerShiftSamplers<{ DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<RepumpLight>();
+        test_world.register::<CoolingLight>();
+        test_world.register::<LaserFrequencyOffset>();
+        test_world.register::<LaserIndex>();
+        test_world.insert(Timestep { delta: 1.0e-6 });
+        test_world.insert(DarkStateBranching::<Strontium88_461>::default().with_state("3P2", 1.0e-3));
+
+        // The 707nm repump pumps atoms from 3P2 into 3P0, and the 679nm repump returns them to the cycle.
+        let repump_707 = RepumpLight::new("3P2", 423.9e12, 7.0e6, 26.0).with_decay("3P0", 1.0);
+        let repump_679 = RepumpLight::new("3P0", 441.3e12, 7.0e6, 29.0);
+        for (i, repump) in [repump_707, repump_679].iter().enumerate() {
+            test_world
+                .create_entity()
+                .with(repump.cooling_light(0.0, 1))
+                .with(repump.clone())
+                .with(LaserIndex {
+                    index: i,
+                    initiated: true,
+                })
+                .build();
+        }
+
+        let mut scattered = ActualPhotonsScatteredVector::<Strontium88_461, { DEFAULT_BEAM_LIMIT }> {
+            contents: [ActualPhotonsScattered::default(); DEFAULT_BEAM_LIMIT],
+        };
+        scattered.contents[2].scattered = 1.0e5;
+        let atom = test_world
+            .create_entity()
+            .with(Strontium88_461)
+            .with(scattered.clone())
+            .with(LaserIntensitySamplers {
+                contents: [LaserIntensitySampler { intensity: 0.0 }; DEFAULT_BEAM_LIMIT],
+            })
+            .with(DopplerShiftSamplers {
+                contents: [DopplerShiftSampler { doppler_shift: 0.0 }; DEFAULT_BEAM_LIMIT],
+            })
+            .build();
+
+        let state = |world: &World| {
+            world
+                .read_storage::<DarkState>()
+                .get(atom)
+                .map(|dark_state| dark_state.state.clone())
+        };
+
+        let mut branching = DarkStateBranchingSystem::<Strontium88_461, { DEFAULT_BEAM_LIMIT }>::default();
+        let mut repump = RepumpDarkStatesSystem::<{ DEFAULT_BEAM_LIMIT }>;
+        let mut run = |world: &mut World| {
+            branching.run_now(world);
+            repump.run_now(world);
+            world.maintain();
+        };
+        run(&mut test_world);
+        assert_eq!(state(&test_world), Some("3P2".to_string()));
+        assert!(test_world.read_storage::<Dark>().get(atom).is_some());
+
+        // Without repump light, the atom stays in the dark state.
+        run(&mut test_world);
+        assert_eq!(state(&test_world), Some("3P2".to_string()));
+
+        // With only the 707nm repump, the atom is pumped into 3P0.
+        let mut samplers = LaserIntensitySamplers {
+            contents: [LaserIntensitySampler { intensity: 0.0 }; DEFAULT_BEAM_LIMIT],
+        };
+        samplers.contents[0].intensity = 1.0e4;
+        test_world
+            .write_storage::<LaserIntensitySamplers<{ DEFAULT_BEAM_LIMIT }>>()
+            .insert(atom, samplers)
+            .unwrap();
+        for _ in 0..100 {
+            run(&mut test_world);
+        }
+        assert_eq!(state(&test_world), Some("3P0".to_string()));
+
+        // Both repumps return the atom to the cooling cycle.
+        samplers.contents[1].intensity = 1.0e4;
+        test_world
+            .write_storage::<LaserIntensitySamplers<{ DEFAULT_BEAM_LIMIT }>>()
+            .insert(atom, samplers)
+            .unwrap();
+        scattered.contents[2].scattered = 0.0;
+        test_world
+            .write_storage::<ActualPhotonsScatteredVector<Strontium88_461, { DEFAULT_BEAM_LIMIT }>>()
+            .insert(atom, scattered)
+            .unwrap();
+        for _ in 0..100 {
+            run(&mut test_world);
+        }
+        assert_eq!(state(&test_world), None);
+        assert!(test_world.read_storage::<Dark>().get(atom).is_none());
+    }
+}
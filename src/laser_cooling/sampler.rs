@@ -1,10 +1,14 @@
 //! Calculation of the total detuning for specific atoms and CoolingLight entities
 
-use super::CoolingLight;
+use super::{drives_transition, CoolingLight, TargetTransitions};
+use super::frequency::LaserFrequencyOffset;
+use super::isotope::IsotopeShift;
 use super::transition::TransitionComponent;
 use crate::constant;
 use crate::laser::index::LaserIndex;
 use crate::laser_cooling::doppler::DopplerShiftSamplers;
+use super::light_shift::LightShiftSampler;
+use super::stark::StarkShiftSampler;
 use super::zeeman::ZeemanShiftSampler;
 use specs::prelude::*;
 use specs::{Component, Join, ReadStorage, System, VecStorage, WriteStorage};
@@ -66,6 +70,10 @@ impl<'a, T, const N: usize> System<'a> for InitialiseLaserDetuningSamplersSystem
 
 /// This system calculates the total Laser Detuning for each atom with respect to
 /// each CoolingLight entities.
+///
+/// Atoms with a `LightShiftSampler` also have their transition frequency shifted by the dipole light,
+/// atoms with a `StarkShiftSampler` by electric fields, and atoms with an `IsotopeShift` by the isotope shift of the transition.
+/// Beams with a `LaserFrequencyOffset` are shifted from the frequency of their `CoolingLight`.
 #[derive(Default)]
 pub struct CalculateLaserDetuningSystem<T, const N: usize>(PhantomData<T>) where T : TransitionComponent;
 impl<'a, T, const N: usize> System<'a> for CalculateLaserDetuningSystem<T, N> where T : TransitionComponent {
@@ -73,8 +81,13 @@ impl<'a, T, const N: usize> System<'a> for CalculateLaserDetuningSystem<T, N> wh
         ReadStorage<'a, T>,
         ReadStorage<'a, LaserIndex>,
         ReadStorage<'a, CoolingLight>,
+        ReadStorage<'a, LaserFrequencyOffset>,
+        ReadStorage<'a, TargetTransitions>,
         ReadStorage<'a, DopplerShiftSamplers<N>>,
         ReadStorage<'a, ZeemanShiftSampler<T>>,
+        ReadStorage<'a, LightShiftSampler<T>>,
+        ReadStorage<'a, StarkShiftSampler<T>>,
+        ReadStorage<'a, IsotopeShift<T>>,
         WriteStorage<'a, LaserDetuningSamplers<T, N>>,
     );
 
@@ -84,8 +97,13 @@ impl<'a, T, const N: usize> System<'a> for CalculateLaserDetuningSystem<T, N> wh
             transitions,
             indices,
             cooling_light,
+            frequency_offsets,
+            targets,
             doppler_samplers,
             zeeman_sampler,
+            light_shift_sampler,
+            stark_shift_sampler,
+            isotope_shifts,
             mut detuning_samplers,
         ): Self::SystemData,
     ) {
@@ -94,10 +112,16 @@ impl<'a, T, const N: usize> System<'a> for CalculateLaserDetuningSystem<T, N> wh
         // There are typically only a small number of lasers in a simulation.
         // For a speedup, cache the required components into thread memory,
         // so they can be distributed to parallel workers during the atom loop.
-        type CachedLaser = (LaserIndex, CoolingLight);
-        let laser_cache: Vec<CachedLaser> = (&indices, &cooling_light)
+        type CachedLaser = (LaserIndex, CoolingLight, f64);
+        let laser_cache: Vec<CachedLaser> = (
+            &indices,
+            &cooling_light,
+            frequency_offsets.maybe(),
+            targets.maybe(),
+        )
             .join()
-            .map(|(index, cooling)| (*index, *cooling))
+            .filter(|(_, _, _, target)| drives_transition::<T>(*target))
+            .map(|(index, cooling, offset, _)| (*index, *cooling, offset.map_or(0.0, |o| o.offset)))
             .collect();
 
         // Perform the iteration over atoms, `LASER_CACHE_SIZE` at a time.
@@ -112,16 +136,25 @@ impl<'a, T, const N: usize> System<'a> for CalculateLaserDetuningSystem<T, N> wh
                 &mut detuning_samplers,
                 &doppler_samplers,
                 &zeeman_sampler,
+                light_shift_sampler.maybe(),
+                stark_shift_sampler.maybe(),
+                isotope_shifts.maybe(),
                 &transitions,
             )
                 .par_join()
                 .for_each(
-                    |(detuning_sampler, doppler_samplers, zeeman_sampler, _transitions)| {
-                        for (index, cooling) in laser_array.iter().take(number_in_iteration) {
+                    |(detuning_sampler, doppler_samplers, zeeman_sampler, light_shift, stark_shift, isotope, _transitions)| {
+                        // Shift of the atomic transition frequency from T::frequency().
+                        let transition_shift = light_shift.map_or(0.0, |sampler| sampler.shift)
+                            + stark_shift.map_or(0.0, |sampler| sampler.shift)
+                            + isotope.map_or(0.0, |isotope| isotope.angular_shift());
+                        for (index, cooling, offset) in laser_array.iter().take(number_in_iteration) {
                             let without_zeeman = 2.0
                                 * constant::PI
                                 * (constant::C / cooling.wavelength - T::frequency())
-                                - doppler_samplers.contents[index.index].doppler_shift;
+                                + offset
+                                - doppler_samplers.contents[index.index].doppler_shift
+                                - transition_shift;
 
                             detuning_sampler.contents[index.index].detuning_sigma_plus =
                                 without_zeeman - zeeman_sampler.sigma_plus;
@@ -139,7 +172,7 @@ impl<'a, T, const N: usize> System<'a> for CalculateLaserDetuningSystem<T, N> wh
 #[cfg(test)]
 pub mod tests {
 
-    use crate::{laser::DEFAULT_BEAM_LIMIT, species::Strontium88_461, laser_cooling::{transition::AtomicTransition, doppler::DopplerShiftSampler}};
+    use crate::{laser::DEFAULT_BEAM_LIMIT, species::Strontium88_461, laser_cooling::{transition::AtomicTransition, doppler::DopplerShiftSampler, isotope::Isotope}};
 
     use super::*;
 
@@ -152,11 +185,16 @@ pub mod tests {
     fn test_calculate_laser_detuning_system() {
         let mut test_world = World::new();
         test_world.register::<CoolingLight>();
+        test_world.register::<LaserFrequencyOffset>();
+        test_world.register::<TargetTransitions>();
         test_world.register::<LaserIndex>();
         test_world.register::<DopplerShiftSamplers<{ DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<LaserDetuningSamplers<Strontium88_461, { DEFAULT_BEAM_LIMIT }>>();
         test_world.register::<Strontium88_461>();
         test_world.register::<ZeemanShiftSampler<Strontium88_461>>();
+        test_world.register::<LightShiftSampler<Strontium88_461>>();
+        test_world.register::<StarkShiftSampler<Strontium88_461>>();
+        test_world.register::<IsotopeShift<Strontium88_461>>();
 
         let wavelength = constant::C / Strontium88_461::frequency();
         test_world
@@ -190,6 +228,38 @@ pub mod tests {
             })
             .build();
 
+        let mut light_shift = LightShiftSampler::<Strontium88_461>::default();
+        light_shift.shift = 5.0e6;
+        let atom2 = test_world
+            .create_entity()
+            .with(DopplerShiftSamplers {
+                contents: [DopplerShiftSampler {
+                    doppler_shift: 10.0e6, //rad/s
+                }; DEFAULT_BEAM_LIMIT],
+            })
+            .with(Strontium88_461)
+            .with(zss)
+            .with(light_shift)
+            .with(LaserDetuningSamplers::<Strontium88_461, DEFAULT_BEAM_LIMIT> {
+                contents: [LaserDetuningSampler::default(); DEFAULT_BEAM_LIMIT],
+            })
+            .build();
+
+        let atom3 = test_world
+            .create_entity()
+            .with(DopplerShiftSamplers {
+                contents: [DopplerShiftSampler {
+                    doppler_shift: 10.0e6, //rad/s
+                }; DEFAULT_BEAM_LIMIT],
+            })
+            .with(Strontium88_461)
+            .with(zss)
+            .with(IsotopeShift::<Strontium88_461>::new(Isotope::shifted(-124.5e6)))
+            .with(LaserDetuningSamplers::<Strontium88_461, DEFAULT_BEAM_LIMIT> {
+                contents: [LaserDetuningSampler::default(); DEFAULT_BEAM_LIMIT],
+            })
+            .build();
+
         let mut system = CalculateLaserDetuningSystem::<Strontium88_461, { DEFAULT_BEAM_LIMIT }>::default();
         system.run_now(&test_world);
         test_world.maintain();
@@ -224,5 +294,27 @@ pub mod tests {
             -10.0e6,
             1e-2_f64
         );
+
+        // the light shift moves the transition frequency, so all detunings change equally.
+        assert_approx_eq!(
+            sampler_storage
+                .get(atom2)
+                .expect("entity not found")
+                .contents[0]
+                .detuning_pi,
+            -10.0e6 - 5.0e6,
+            1e-2_f64
+        );
+
+        // a lighter isotope has a lower transition frequency, so the laser is blue detuned.
+        assert_approx_eq!(
+            sampler_storage
+                .get(atom3)
+                .expect("entity not found")
+                .contents[0]
+                .detuning_pi,
+            -10.0e6 + 2.0 * constant::PI * 124.5e6,
+            1e-2_f64
+        );
     }
 }
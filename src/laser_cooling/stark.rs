@@ -0,0 +1,214 @@
+//! Stark shift of a cooling transition, induced by static or slowly varying electric fields.
+//!
+//! The ground state of an atom is shifted by its `StaticPolarizability` and `ElectricDipole` (see
+//! [crate::electric::force]), and the excited state of a transition by its `ExcitedStateStark`. The
+//! difference between the two shifts moves the transition frequency, and therefore the detuning of all
+//! cooling beams addressing the transition.
+//!
+//! The Stark shift is only calculated when the [crate::electric::ElectricFieldPlugin] is added before the
+//! [super::LaserCoolingPlugin]. Adding the [crate::electric::ElectricFieldPlugin] afterwards panics, see
+//! [StarkShiftOmitted].
+
+use std::marker::PhantomData;
+
+use super::transition::TransitionComponent;
+use crate::constant::HBAR;
+use crate::electric::force::{ElectricDipole, StaticPolarizability};
+use crate::electric::ElectricFieldSampler;
+use crate::initiate::NewlyCreated;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+/// An atom component that represents the static polarizability and dipole moment of the excited state
+/// of transition `T`.
+///
+/// The fields have the same meaning as `StaticPolarizability` and `ElectricDipole`, which describe the
+/// ground state.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct ExcitedStateStark<T>
+where
+    T: TransitionComponent,
+{
+    /// Static polarizability of the excited state, in SI units of C m^2/V.
+    pub polarizability: f64,
+    /// Projection of the dipole moment of the excited state onto the field, in SI units of C m.
+    pub dipole_moment: f64,
+    #[serde(skip)]
+    phantom: PhantomData<T>,
+}
+
+impl<T> ExcitedStateStark<T>
+where
+    T: TransitionComponent,
+{
+    pub fn new(polarizability: f64, dipole_moment: f64) -> Self {
+        ExcitedStateStark {
+            polarizability,
+            dipole_moment,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The Stark shift of the energy of the excited state in a field of the given magnitude, in J.
+    pub fn energy(&self, field: f64) -> f64 {
+        StaticPolarizability {
+            polarizability: self.polarizability,
+        }
+        .energy(field)
+            + ElectricDipole {
+                moment: self.dipole_moment,
+            }
+            .energy(field)
+    }
+}
+
+impl<T> Component for ExcitedStateStark<T>
+where
+    T: TransitionComponent,
+{
+    type Storage = VecStorage<Self>;
+}
+
+/// A resource that lists the transitions whose [super::LaserCoolingPlugin] was added without the
+/// [crate::electric::ElectricFieldPlugin], and which therefore do not include the Stark shift.
+///
+/// The [crate::electric::ElectricFieldPlugin] panics when it is added while this resource exists, rather
+/// than silently leaving out the Stark shift of these transitions.
+#[derive(Default)]
+pub struct StarkShiftOmitted {
+    pub transitions: Vec<&'static str>,
+}
+
+/// Represents the shift of the transition frequency of `T` caused by electric fields.
+#[derive(Clone, Copy, Serialize)]
+pub struct StarkShiftSampler<T>
+where
+    T: TransitionComponent,
+{
+    /// Shift of the transition frequency, in units of rad/s.
+    pub shift: f64,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Default for StarkShiftSampler<T>
+where
+    T: TransitionComponent,
+{
+    fn default() -> Self {
+        StarkShiftSampler {
+            shift: f64::NAN,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Component for StarkShiftSampler<T>
+where
+    T: TransitionComponent,
+{
+    type Storage = VecStorage<Self>;
+}
+
+/// Attaches a `StarkShiftSampler` to newly created atoms that have an `ExcitedStateStark`.
+#[derive(Default)]
+pub struct AttachStarkShiftSamplersToNewlyCreatedAtomsSystem<T>(PhantomData<T>)
+where
+    T: TransitionComponent;
+
+impl<'a, T> System<'a> for AttachStarkShiftSamplersToNewlyCreatedAtomsSystem<T>
+where
+    T: TransitionComponent,
+{
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, NewlyCreated>,
+        ReadStorage<'a, ExcitedStateStark<T>>,
+        Read<'a, LazyUpdate>,
+    );
+    fn run(&mut self, (ent, newly_created, stark, updater): Self::SystemData) {
+        for (ent, _, _) in (&ent, &newly_created, &stark).join() {
+            updater.insert(ent, StarkShiftSampler::<T>::default());
+        }
+    }
+}
+
+/// Calculates the differential Stark shift of transition `T` from the electric field at each atom.
+#[derive(Default)]
+pub struct CalculateStarkShiftSystem<T>(PhantomData<T>)
+where
+    T: TransitionComponent;
+
+impl<'a, T> System<'a> for CalculateStarkShiftSystem<T>
+where
+    T: TransitionComponent,
+{
+    type SystemData = (
+        ReadStorage<'a, ElectricFieldSampler>,
+        ReadStorage<'a, StaticPolarizability>,
+        ReadStorage<'a, ElectricDipole>,
+        ReadStorage<'a, ExcitedStateStark<T>>,
+        WriteStorage<'a, StarkShiftSampler<T>>,
+    );
+
+    fn run(
+        &mut self,
+        (fields, polarizabilities, dipoles, excited, mut stark_shift_samplers): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        (
+            &fields,
+            polarizabilities.maybe(),
+            dipoles.maybe(),
+            &excited,
+            &mut stark_shift_samplers,
+        )
+            .par_join()
+            .for_each(|(field, polarizability, dipole, excited, sampler)| {
+                let ground = polarizability.map_or(0.0, |p| p.energy(field.magnitude))
+                    + dipole.map_or(0.0, |d| d.energy(field.magnitude));
+                sampler.shift = (excited.energy(field.magnitude) - ground) / HBAR;
+            });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::species::Rubidium87_780D2;
+    use assert_approx_eq::assert_approx_eq;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn test_stark_shift() {
+        let mut test_world = World::new();
+        test_world.register::<ElectricFieldSampler>();
+        test_world.register::<StaticPolarizability>();
+        test_world.register::<ElectricDipole>();
+        test_world.register::<ExcitedStateStark<Rubidium87_780D2>>();
+        test_world.register::<StarkShiftSampler<Rubidium87_780D2>>();
+
+        let ground = StaticPolarizability::atomic_units(318.8);
+        let excited = StaticPolarizability::atomic_units(868.8);
+        let atom = test_world
+            .create_entity()
+            .with(ElectricFieldSampler::volts_per_metre(Vector3::new(
+                0.0, 0.0, 1.0e6,
+            )))
+            .with(ground)
+            .with(ExcitedStateStark::<Rubidium87_780D2>::new(
+                excited.polarizability,
+                0.0,
+            ))
+            .with(StarkShiftSampler::<Rubidium87_780D2>::default())
+            .build();
+
+        CalculateStarkShiftSystem::<Rubidium87_780D2>::default().run_now(&test_world);
+        let samplers = test_world.read_storage::<StarkShiftSampler<Rubidium87_780D2>>();
+        // The excited state is more polarizable, so the transition frequency decreases.
+        let expected = -0.5 * (excited.polarizability - ground.polarizability) * 1.0e12 / HBAR;
+        let shift = samplers.get(atom).unwrap().shift;
+        assert_approx_eq!(shift, expected, 1e-6);
+        assert!(shift < 0.0);
+    }
+}
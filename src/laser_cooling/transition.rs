@@ -1,3 +1,5 @@
+use crate::constant::BOHRMAG;
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 
 /// Physical constants of an atomic transition used for laser cooling.
@@ -88,4 +90,129 @@ macro_rules! transition {
             type Storage = specs::VecStorage<Self>;
         }
     };
-}
\ No newline at end of file
+}
+
+/// Angular momentum and Landé g-factor of the states addressed by a cooling transition.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct ZeemanLevel {
+    /// Total angular momentum of the level, `F` (or `J` for atoms without hyperfine structure).
+    pub f: f64,
+    /// Landé g-factor of the level.
+    pub g: f64,
+}
+
+/// Physical constants of a laser cooling transition, which can be loaded at runtime.
+///
+/// The Zeeman shifts are calculated for the stretched states of the cooling cycle, eg the
+/// `|F, m_F = F> -> |F', m_F' = F + 1>` transition for σ+ light.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct TransitionParameters {
+    /// Frequency of the transition, in Hz.
+    pub frequency: f64,
+    /// Linewidth of the transition, in Hz.
+    pub linewidth: f64,
+    /// Saturation intensity, in units of W/m^2.
+    pub saturation_intensity: f64,
+    /// The lower level of the transition.
+    pub lower: ZeemanLevel,
+    /// The upper level of the transition.
+    pub upper: ZeemanLevel,
+}
+
+impl TransitionParameters {
+    /// Shift of the sigma+ transition per unit magnetic field, in J/T.
+    pub fn mup(&self) -> f64 {
+        stretched_magnetic_moments(&self.lower, &self.upper).0
+    }
+
+    /// Shift of the sigma- transition per unit magnetic field, in J/T.
+    pub fn mum(&self) -> f64 {
+        stretched_magnetic_moments(&self.lower, &self.upper).1
+    }
+
+    /// Shift of the pi transition per unit magnetic field, in J/T.
+    pub fn muz(&self) -> f64 {
+        stretched_magnetic_moments(&self.lower, &self.upper).2
+    }
+}
+
+/// Calculates the shifts per unit magnetic field of the sigma+, sigma- and pi transitions between
+/// the stretched states of two levels, in J/T.
+pub fn stretched_magnetic_moments(lower: &ZeemanLevel, upper: &ZeemanLevel) -> (f64, f64, f64) {
+    let sigma_plus = (upper.g * (lower.f + 1.0) - lower.g * lower.f) * BOHRMAG;
+    let pi = (upper.g - lower.g) * lower.f * BOHRMAG;
+    (sigma_plus, -sigma_plus, pi)
+}
+
+/// Generates a laser-cooling transition whose constants are set at runtime.
+///
+/// The generated struct can be used in the same way as a transition created by [transition],
+/// but its `TransitionParameters` must first be set with `set`, eg from a
+/// [crate::species::registry::SpeciesRegistry].
+///
+/// # Limitations
+///
+/// The constants of an [AtomicTransition] are associated functions without access to the `World`, so
+/// the parameters are stored in a static of the generated struct rather than in the simulation:
+///
+/// * The parameters can only be set once per process. Setting different parameters later returns an
+///   error, and the first parameters remain in use.
+/// * All simulations in the process share the parameters, including simulations run one after another
+///   and tests run in parallel.
+///
+/// To simulate several species with runtime constants in one process, generate a separate transition
+/// for each of them, eg `dynamic_transition!(FirstSpecies)` and `dynamic_transition!(SecondSpecies)`.
+///
+/// # Arguments:
+/// * `transition_name`: name of the generated struct.
+#[macro_export]
+macro_rules! dynamic_transition {
+    ($transition_name:ident) => {
+        /// A laser cooling transition with constants set at runtime.
+        #[derive(Copy, Clone, Default)]
+        pub struct $transition_name;
+        impl $transition_name {
+            fn cell() -> &'static std::sync::OnceLock<$crate::laser_cooling::transition::TransitionParameters> {
+                static CELL: std::sync::OnceLock<$crate::laser_cooling::transition::TransitionParameters> =
+                    std::sync::OnceLock::new();
+                &CELL
+            }
+
+            /// Sets the constants of the transition.
+            ///
+            /// Returns an error if the transition has already been set to different parameters anywhere
+            /// in the process.
+            pub fn set(parameters: $crate::laser_cooling::transition::TransitionParameters) -> Result<(), String> {
+                let current = Self::cell().get_or_init(|| parameters);
+                if *current == parameters {
+                    Ok(())
+                } else {
+                    Err(format!("{} has already been set.", stringify!($transition_name)))
+                }
+            }
+
+            /// Gets the constants of the transition.
+            ///
+            /// Panics if the transition has not been set.
+            pub fn parameters() -> &'static $crate::laser_cooling::transition::TransitionParameters {
+                Self::cell()
+                    .get()
+                    .expect(concat!(stringify!($transition_name), " has not been set."))
+            }
+        }
+        impl $crate::laser_cooling::transition::AtomicTransition for $transition_name {
+            fn frequency() -> f64 { Self::parameters().frequency }
+            fn linewidth() -> f64 { Self::parameters().linewidth }
+            fn wavelength() -> f64 { $crate::constant::C / Self::frequency() }
+            fn mup() -> f64 { Self::parameters().mup() }
+            fn mum() -> f64 { Self::parameters().mum() }
+            fn muz() -> f64 { Self::parameters().muz() }
+            fn saturation_intensity() -> f64 { Self::parameters().saturation_intensity }
+            fn rate_prefactor() -> f64 { Self::gamma().powi(3) / (Self::saturation_intensity() * 8.0) }
+            fn gamma() -> f64 { Self::linewidth() * 2.0 * std::f64::consts::PI }
+        }
+        impl specs::Component for $transition_name {
+            type Storage = specs::VecStorage<Self>;
+        }
+    };
+}
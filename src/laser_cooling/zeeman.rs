@@ -8,6 +8,7 @@ use crate::initiate::NewlyCreated;
 use serde::Serialize;
 use specs::prelude::*;
 
+use super::isotope::IsotopeShift;
 use super::transition::TransitionComponent;
 
 /// Represents the (angular) Zeemanshift of the atom depending on the magnetic field it experiences
@@ -59,6 +60,8 @@ impl<'a, T> System<'a> for AttachZeemanShiftSamplersToNewlyCreatedAtomsSystem<T>
 }
 
 /// Calculates the Zeeman shift for each atom in each cooling beam.
+///
+/// Atoms with an `IsotopeShift` use the magnetic moments of the cycling transition of their isotope.
 #[derive(Default)]
 pub struct CalculateZeemanShiftSystem<T>(PhantomData<T>) where T : TransitionComponent;
 impl<'a, T> System<'a> for CalculateZeemanShiftSystem<T> where T : TransitionComponent {
@@ -66,11 +69,12 @@ impl<'a, T> System<'a> for CalculateZeemanShiftSystem<T> where T : TransitionCom
         WriteStorage<'a, ZeemanShiftSampler<T>>,
         ReadStorage<'a, MagneticFieldSampler>,
         ReadStorage<'a, T>,
+        ReadStorage<'a, IsotopeShift<T>>,
     );
 
     fn run(
         &mut self,
-        (mut zeeman_sampler, magnetic_field_sampler, atomic_transition): Self::SystemData,
+        (mut zeeman_sampler, magnetic_field_sampler, atomic_transition, isotope_shifts): Self::SystemData,
     ) {
         use rayon::prelude::*;
 
@@ -78,12 +82,17 @@ impl<'a, T> System<'a> for CalculateZeemanShiftSystem<T> where T : TransitionCom
             &mut zeeman_sampler,
             &magnetic_field_sampler,
             &atomic_transition,
+            isotope_shifts.maybe(),
         )
             .par_join()
-            .for_each(|(zeeman, magnetic_field, _transition)| {
-                zeeman.sigma_plus = T::mup() / HBAR * magnetic_field.magnitude;
-                zeeman.sigma_minus = T::mum() / HBAR * magnetic_field.magnitude;
-                zeeman.sigma_pi = T::muz() / HBAR * magnetic_field.magnitude;
+            .for_each(|(zeeman, magnetic_field, _transition, isotope)| {
+                let (mup, mum, muz) = match isotope {
+                    Some(isotope) => isotope.magnetic_moments(),
+                    None => (T::mup(), T::mum(), T::muz()),
+                };
+                zeeman.sigma_plus = mup / HBAR * magnetic_field.magnitude;
+                zeeman.sigma_minus = mum / HBAR * magnetic_field.magnitude;
+                zeeman.sigma_pi = muz / HBAR * magnetic_field.magnitude;
             });
     }
 }
@@ -103,6 +112,7 @@ pub mod tests {
     fn test_calculate_zeeman_shift_system() {
         let mut test_world = World::new();
         test_world.register::<MagneticFieldSampler>();
+        test_world.register::<IsotopeShift<Strontium88_461>>();
         test_world.register::<Strontium88_461>();
         test_world.register::<ZeemanShiftSampler<Strontium88_461>>();
 
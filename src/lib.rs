@@ -12,17 +12,20 @@ pub mod collisions;
 pub mod constant;
 pub mod destructor;
 pub mod dipole;
+pub mod electric;
 //pub mod ecs;
 pub mod gravity;
 pub mod initiate;
 pub mod integration_tests;
 pub mod integrator;
+pub mod interferometry;
 pub mod laser;
 pub mod laser_cooling;
 pub mod magnetic;
 pub mod maths;
 pub mod output;
 pub mod ramp;
+pub mod rydberg;
 pub mod shapes;
 pub mod sim_region;
 pub mod species;
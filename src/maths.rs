@@ -55,6 +55,137 @@ pub fn gaussian_dis(std: f64, distance_squared: f64) -> f64 {
     1.0 / (2.0 * PI * std * std) * EXP.powf(-distance_squared / 2.0 / (std * std))
 }
 
+/// The gamma function, evaluated using the Lanczos approximation.
+pub fn gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // reflection formula
+        PI / ((PI * x).sin() * gamma(1.0 - x))
+    } else {
+        let x = x - 1.0;
+        let t = x + G + 0.5;
+        let series = COEFFICIENTS
+            .iter()
+            .enumerate()
+            .skip(1)
+            .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64));
+        (2.0 * PI).sqrt() * t.powf(x + 0.5) * (-t).exp() * series
+    }
+}
+
+/// Generalised Laguerre polynomial `L_p^alpha(x)`, evaluated by recurrence.
+pub fn laguerre(p: u32, alpha: f64, x: f64) -> f64 {
+    let mut previous = 1.0;
+    if p == 0 {
+        return previous;
+    }
+    let mut current = 1.0 + alpha - x;
+    for k in 1..p {
+        let k = k as f64;
+        let next = ((2.0 * k + 1.0 + alpha - x) * current - (k + alpha) * previous) / (k + 1.0);
+        previous = current;
+        current = next;
+    }
+    current
+}
+
+/// Physicists' Hermite polynomial `H_n(x)`, evaluated by recurrence.
+pub fn hermite(n: u32, x: f64) -> f64 {
+    let mut previous = 1.0;
+    if n == 0 {
+        return previous;
+    }
+    let mut current = 2.0 * x;
+    for k in 1..n {
+        let next = 2.0 * x * current - 2.0 * k as f64 * previous;
+        previous = current;
+        current = next;
+    }
+    current
+}
+
+/// Factorial of a non-negative integer stored as a float, as arises for sums of angular momenta.
+fn factorial(n: f64) -> f64 {
+    (1..=n.round() as u64).map(|i| i as f64).product()
+}
+
+/// Triangle coefficient used in the Racah formula, or `None` if `a`, `b`, `c` do not satisfy the triangle condition.
+fn triangle_coefficient(a: f64, b: f64, c: f64) -> Option<f64> {
+    let terms = [a + b - c, a - b + c, -a + b + c];
+    if terms.iter().any(|&t| t < -1e-9 || (t - t.round()).abs() > 1e-9) {
+        return None;
+    }
+    Some(
+        (factorial(terms[0]) * factorial(terms[1]) * factorial(terms[2])
+            / factorial(a + b + c + 1.0))
+        .sqrt(),
+    )
+}
+
+/// The Wigner 6-j symbol `{j1 j2 j3; j4 j5 j6}`, evaluated using the Racah formula.
+pub fn wigner_6j(j1: f64, j2: f64, j3: f64, j4: f64, j5: f64, j6: f64) -> f64 {
+    let triads = [
+        triangle_coefficient(j1, j2, j3),
+        triangle_coefficient(j1, j5, j6),
+        triangle_coefficient(j4, j2, j6),
+        triangle_coefficient(j4, j5, j3),
+    ];
+    if triads.iter().any(|t| t.is_none()) {
+        return 0.0;
+    }
+    let prefactor: f64 = triads.iter().map(|t| t.unwrap()).product();
+
+    let sums = [j1 + j2 + j3, j1 + j5 + j6, j4 + j2 + j6, j4 + j5 + j3];
+    let differences = [j1 + j2 + j4 + j5, j2 + j3 + j5 + j6, j3 + j1 + j6 + j4];
+    let t_min = sums.iter().cloned().fold(f64::MIN, f64::max).round() as i64;
+    let t_max = differences.iter().cloned().fold(f64::MAX, f64::min).round() as i64;
+
+    let mut total = 0.0;
+    for t in t_min..=t_max {
+        let tf = t as f64;
+        let denominator: f64 = sums.iter().map(|s| factorial(tf - s)).product::<f64>()
+            * differences.iter().map(|d| factorial(d - tf)).product::<f64>();
+        let sign = if t % 2 == 0 { 1.0 } else { -1.0 };
+        total += sign * factorial(tf + 1.0) / denominator;
+    }
+    prefactor * total
+}
+
+/// Advances an Ornstein-Uhlenbeck process by a time `dt`, using the exact update.
+///
+/// # Arguments
+///
+/// `x`: current value of the process
+///
+/// `sigma`: stationary standard deviation of the process
+///
+/// `correlation_time`: correlation time of the process, in the same units as `dt`
+///
+/// `dt`: time step
+///
+/// `gaussian`: a sample from the standard normal distribution.
+pub fn ornstein_uhlenbeck_step(
+    x: f64,
+    sigma: f64,
+    correlation_time: f64,
+    dt: f64,
+    gaussian: f64,
+) -> f64 {
+    let decay = (-dt / correlation_time).exp();
+    x * decay + sigma * (1.0 - decay * decay).sqrt() * gaussian
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,4 +198,29 @@ mod tests {
         let (distance, _) = get_minimum_distance_line_point(&pos, &centre, &dir);
         assert!(distance > 0.942, "{}", distance < 0.943);
     }
+
+    #[test]
+    fn test_special_functions() {
+        assert!((gamma(5.0) - 24.0).abs() < 1e-10);
+        assert!((gamma(0.5) - PI.sqrt()).abs() < 1e-10);
+        assert!((laguerre(2, 1.0, 0.5) - (0.5 * 0.25 - 3.0 * 0.5 + 3.0)).abs() < 1e-12);
+        assert!((hermite(3, 0.7) - (8.0 * 0.343 - 12.0 * 0.7)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_wigner_6j() {
+        // {a b c; 0 c b} = (-1)^(a+b+c) / sqrt((2b+1)(2c+1))
+        assert!((wigner_6j(1.0, 1.0, 1.0, 0.0, 1.0, 1.0) + 1.0 / 3.0).abs() < 1e-12);
+        assert!((wigner_6j(1.0, 0.5, 0.5, 0.0, 0.5, 0.5) - 0.5).abs() < 1e-12);
+        assert!((wigner_6j(1.0, 1.0, 1.0, 1.0, 1.0, 1.0) - 1.0 / 6.0).abs() < 1e-12);
+        assert_eq!(wigner_6j(1.0, 1.0, 3.0, 1.0, 1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_ornstein_uhlenbeck_step() {
+        // without noise the process decays exponentially.
+        assert!((ornstein_uhlenbeck_step(2.0, 1.0, 1.0, 0.5, 0.0) - 2.0 * (-0.5_f64).exp()).abs() < 1e-12);
+        // for long steps the process forgets its initial value.
+        assert!((ornstein_uhlenbeck_step(2.0, 3.0, 1.0, 100.0, 1.0) - 3.0).abs() < 1e-12);
+    }
 }
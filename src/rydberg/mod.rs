@@ -0,0 +1,545 @@
+//! Rydberg excitation of atoms, with blockade and van der Waals interactions between Rydberg atoms.
+//!
+//! Atoms with a [Rydberg] component are excited to a Rydberg state by two-photon excitation. The two steps
+//! are driven by laser beams with a [RydbergLight] component, whose intensities at each atom give the Rabi
+//! frequencies of the lower and upper transitions. The effective two-photon Rabi frequency is
+//! `Ω = Ω1 Ω2 / (2Δ)`, where `Δ` is the detuning from the intermediate state.
+//!
+//! Excitation is described by rate equations, which are valid when the excitation is incoherent, for
+//! example because of the linewidth of the lasers. The excitation rate is `Ω² γ / (γ² + 4δ²)`, where `γ` is
+//! the dephasing rate and `δ` the two-photon detuning. Rydberg atoms within the [neighbours] of an atom shift
+//! the Rydberg state by the van der Waals interaction `C6 / r⁶`, which suppresses excitation within the
+//! blockade radius. Rydberg atoms decay back to the ground state with the lifetime of the Rydberg state, and
+//! repel (or attract) each other with the van der Waals force.
+//!
+//! Both atoms of a pair may be excited during the same step, so the timestep should be short compared to
+//! the inverse of the excitation rate.
+
+use crate::atom::{Force, Position};
+use crate::constant::{C, EPSILON0, HBAR, PI};
+use crate::integrator::{Timestep, INTEGRATE_POSITION_SYSTEM_NAME};
+use crate::laser::index::LaserIndex;
+use crate::laser::intensity::LaserIntensitySamplers;
+use crate::laser::LaserPlugin;
+use crate::simulation::Plugin;
+use neighbours::Neighbours;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+pub mod neighbours;
+
+/// An atom component describing the Rydberg state the atom can be excited to.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct Rydberg {
+    /// van der Waals coefficient of the interaction between two Rydberg atoms, in SI units of J m^6.
+    pub c6: f64,
+    /// Lifetime of the Rydberg state, in s.
+    pub lifetime: f64,
+}
+
+impl Rydberg {
+    /// Energy of the interaction between two Rydberg atoms separated by a distance `r`, in J.
+    pub fn interaction(&self, r: f64) -> f64 {
+        self.c6 / r.powi(6)
+    }
+
+    /// The blockade radius for the given two-photon Rabi frequency or linewidth, in rad/s.
+    ///
+    /// Within the blockade radius, the interaction shift is larger than the excitation linewidth.
+    pub fn blockade_radius(&self, linewidth: f64) -> f64 {
+        (self.c6.abs() / (HBAR * linewidth)).powf(1.0 / 6.0)
+    }
+}
+
+impl Component for Rydberg {
+    type Storage = VecStorage<Self>;
+}
+
+/// Marks an atom as being in the Rydberg state.
+pub struct RydbergExcited;
+
+impl Component for RydbergExcited {
+    type Storage = VecStorage<Self>;
+}
+
+/// The step of the two-photon excitation driven by a `RydbergLight`.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExcitationStep {
+    /// The transition from the ground state to the intermediate state.
+    Lower,
+    /// The transition from the intermediate state to the Rydberg state.
+    Upper,
+}
+
+/// A component marking a laser beam as driving one step of the Rydberg excitation.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct RydbergLight {
+    /// The step of the excitation driven by the beam.
+    pub step: ExcitationStep,
+    /// Constant relating the Rabi frequency to the square root of the intensity, in SI units of
+    /// rad/s/(W/m^2)^(1/2).
+    pub rabi_coefficient: f64,
+}
+
+impl RydbergLight {
+    /// Creates a `RydbergLight` for a transition with the given linewidth (Hz) and saturation intensity (W/m^2).
+    ///
+    /// The Rabi frequency is `Γ sqrt(I / 2 I_sat)`.
+    pub fn from_transition(
+        step: ExcitationStep,
+        linewidth: f64,
+        saturation_intensity: f64,
+    ) -> Self {
+        RydbergLight {
+            step,
+            rabi_coefficient: 2.0 * PI * linewidth / (2.0 * saturation_intensity).sqrt(),
+        }
+    }
+
+    /// Creates a `RydbergLight` for a transition with the given dipole matrix element, in SI units of C m.
+    ///
+    /// This is normally used for the upper transition, for which the dipole matrix element is small.
+    pub fn from_dipole_moment(step: ExcitationStep, dipole_moment: f64) -> Self {
+        RydbergLight {
+            step,
+            rabi_coefficient: dipole_moment / HBAR * (2.0 / (C * EPSILON0)).sqrt(),
+        }
+    }
+
+    /// The Rabi frequency of the transition for the given intensity, in rad/s.
+    pub fn rabi_frequency(&self, intensity: f64) -> f64 {
+        self.rabi_coefficient * intensity.max(0.0).sqrt()
+    }
+}
+
+impl Component for RydbergLight {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// A resource describing the detunings of the Rydberg excitation lasers.
+///
+/// Rydberg atoms are only excited if this resource exists.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct RydbergExcitation {
+    /// Detuning of the lower transition from the intermediate state, in rad/s.
+    pub intermediate_detuning: f64,
+    /// Two-photon detuning from the Rydberg state, in rad/s.
+    pub detuning: f64,
+    /// Dephasing rate of the two-photon transition, for example from the laser linewidth, in rad/s.
+    pub dephasing: f64,
+}
+
+impl RydbergExcitation {
+    /// The effective Rabi frequency of the two-photon transition, in rad/s.
+    pub fn two_photon_rabi_frequency(&self, lower: f64, upper: f64) -> f64 {
+        lower * upper / (2.0 * self.intermediate_detuning)
+    }
+
+    /// The rate of excitation (or stimulated de-excitation), in Hz.
+    ///
+    /// # Arguments
+    ///
+    /// `rabi_frequency`: the two-photon Rabi frequency, in rad/s.
+    ///
+    /// `shift`: the shift of the Rydberg state by interactions with other Rydberg atoms, in rad/s.
+    pub fn rate(&self, rabi_frequency: f64, shift: f64) -> f64 {
+        let detuning = self.detuning - shift;
+        rabi_frequency.powi(2) * self.dephasing / (self.dephasing.powi(2) + 4.0 * detuning.powi(2))
+    }
+}
+
+/// A system that attaches `LaserIndex` components to entities which have `RydbergLight` but no index.
+pub struct AttachIndexToRydbergLightSystem;
+impl<'a> System<'a> for AttachIndexToRydbergLightSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, RydbergLight>,
+        ReadStorage<'a, LaserIndex>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(&mut self, (ent, rydberg_light, indices, updater): Self::SystemData) {
+        for (ent, _, _) in (&ent, &rydberg_light, !&indices).join() {
+            updater.insert(ent, LaserIndex::default());
+        }
+    }
+}
+
+/// A system that attaches `Neighbours` to atoms which have `Rydberg` but no neighbour list.
+pub struct AttachNeighboursToRydbergAtomsSystem;
+impl<'a> System<'a> for AttachNeighboursToRydbergAtomsSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Rydberg>,
+        ReadStorage<'a, Neighbours>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(&mut self, (ent, rydberg, neighbours, updater): Self::SystemData) {
+        for (ent, _, _) in (&ent, &rydberg, !&neighbours).join() {
+            updater.insert(ent, Neighbours::default());
+        }
+    }
+}
+
+/// Sum of the interaction shifts of the Rydberg state of an atom due to its excited neighbours, in rad/s.
+fn interaction_shift(
+    rydberg: &Rydberg,
+    position: &Position,
+    neighbours: &Neighbours,
+    positions: &ReadStorage<Position>,
+    excited: &ReadStorage<RydbergExcited>,
+) -> f64 {
+    neighbours
+        .entities
+        .iter()
+        .filter(|neighbour| excited.get(**neighbour).is_some())
+        .filter_map(|neighbour| positions.get(*neighbour))
+        .map(|other| rydberg.interaction((other.pos - position.pos).norm()) / HBAR)
+        .sum()
+}
+
+/// Excites atoms to the Rydberg state and returns them to the ground state.
+///
+/// Atoms in the ground state are excited at the rate given by the [RydbergExcitation] resource, including the
+/// interaction shift from their excited neighbours. Excited atoms return to the ground state by stimulated
+/// emission at the same rate, and by spontaneous decay with the lifetime of the Rydberg state.
+pub struct RydbergExcitationSystem<const N: usize>;
+
+impl<'a, const N: usize> System<'a> for RydbergExcitationSystem<N> {
+    type SystemData = (
+        Entities<'a>,
+        Option<Read<'a, RydbergExcitation>>,
+        ReadStorage<'a, Rydberg>,
+        ReadStorage<'a, RydbergExcited>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Neighbours>,
+        ReadStorage<'a, LaserIntensitySamplers<N>>,
+        ReadStorage<'a, RydbergLight>,
+        ReadStorage<'a, LaserIndex>,
+        ReadExpect<'a, Timestep>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            excitation,
+            rydberg,
+            excited,
+            positions,
+            neighbours,
+            intensities,
+            rydberg_light,
+            indices,
+            timestep,
+            updater,
+        ): Self::SystemData,
+    ) {
+        use rayon::prelude::*;
+
+        let excitation = match excitation {
+            Some(excitation) => *excitation,
+            None => return,
+        };
+        let lights: Vec<(RydbergLight, LaserIndex)> = (&rydberg_light, &indices)
+            .join()
+            .map(|(light, index)| (*light, *index))
+            .collect();
+
+        (
+            &entities,
+            &rydberg,
+            &positions,
+            &neighbours,
+            &intensities,
+            excited.maybe(),
+        )
+            .par_join()
+            .for_each(
+                |(entity, rydberg, position, atom_neighbours, intensities, is_excited)| {
+                    // The Rabi frequencies of beams driving the same step add.
+                    let rabi = |step: ExcitationStep| -> f64 {
+                        lights
+                            .iter()
+                            .filter(|(light, _)| light.step == step)
+                            .map(|(light, index)| {
+                                light.rabi_frequency(intensities.contents[index.index].intensity)
+                            })
+                            .sum()
+                    };
+                    let rabi_frequency = excitation.two_photon_rabi_frequency(
+                        rabi(ExcitationStep::Lower),
+                        rabi(ExcitationStep::Upper),
+                    );
+                    let shift =
+                        interaction_shift(rydberg, position, atom_neighbours, &positions, &excited);
+                    let mut rate = excitation.rate(rabi_frequency, shift);
+                    if is_excited.is_some() {
+                        rate += 1.0 / rydberg.lifetime;
+                    }
+
+                    let probability = 1.0 - (-rate * timestep.delta).exp();
+                    if rand::thread_rng().gen_range(0.0..1.0) < probability {
+                        if is_excited.is_some() {
+                            updater.remove::<RydbergExcited>(entity);
+                        } else {
+                            updater.insert(entity, RydbergExcited);
+                        }
+                    }
+                },
+            );
+    }
+}
+
+/// Applies the van der Waals force between pairs of Rydberg atoms.
+///
+/// The force on an atom due to a Rydberg atom at a separation `r` is `6 C6 r / |r|⁸`.
+pub struct RydbergInteractionForceSystem;
+
+impl<'a> System<'a> for RydbergInteractionForceSystem {
+    type SystemData = (
+        ReadStorage<'a, Rydberg>,
+        ReadStorage<'a, RydbergExcited>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Neighbours>,
+        WriteStorage<'a, Force>,
+    );
+
+    fn run(&mut self, (rydberg, excited, positions, neighbours, mut forces): Self::SystemData) {
+        use rayon::prelude::*;
+
+        (&rydberg, &excited, &positions, &neighbours, &mut forces)
+            .par_join()
+            .for_each(|(rydberg, _, position, atom_neighbours, force)| {
+                for neighbour in atom_neighbours.entities.iter() {
+                    if excited.get(*neighbour).is_none() {
+                        continue;
+                    }
+                    if let Some(other) = positions.get(*neighbour) {
+                        let separation = position.pos - other.pos;
+                        force.force += 6.0 * rydberg.c6 * separation / separation.norm().powi(8);
+                    }
+                }
+            });
+    }
+}
+
+/// This plugin implements Rydberg excitation, blockade and van der Waals interactions.
+///
+/// See also [crate::rydberg]
+///
+/// # Generic Arguments
+///
+/// * `N`: The maximum number of laser beams (must match the `LaserPlugin`).
+pub struct RydbergPlugin<const N: usize>;
+impl<const N: usize> Plugin for RydbergPlugin<N> {
+    fn build(&self, builder: &mut crate::simulation::SimulationBuilder) {
+        add_systems_to_dispatch::<N>(&mut builder.dispatcher_builder, &[]);
+        register_components(&mut builder.world);
+    }
+    fn deps(&self) -> Vec<Box<dyn Plugin>> {
+        vec![Box::new(LaserPlugin::<{ N }>)]
+    }
+}
+
+/// Adds the systems required by the module to the dispatcher.
+///
+/// #Arguments
+///
+/// `builder`: the dispatch builder to modify
+///
+/// `deps`: any dependencies that must be completed before the systems run.
+fn add_systems_to_dispatch<const N: usize>(
+    builder: &mut DispatcherBuilder<'static, 'static>,
+    deps: &[&str],
+) {
+    builder.add(
+        AttachIndexToRydbergLightSystem,
+        "attach_rydberg_index",
+        deps,
+    );
+    builder.add(
+        AttachNeighboursToRydbergAtomsSystem,
+        "attach_rydberg_neighbours",
+        deps,
+    );
+    builder.add(
+        neighbours::BuildNeighbourListsSystem,
+        "build_neighbour_lists",
+        &[INTEGRATE_POSITION_SYSTEM_NAME],
+    );
+    builder.add(
+        RydbergInteractionForceSystem,
+        "apply_rydberg_force",
+        &["build_neighbour_lists", "clear"],
+    );
+    builder.add(
+        RydbergExcitationSystem::<N>,
+        "rydberg_excitation",
+        &[
+            "build_neighbour_lists",
+            "sample_laser_intensity",
+            "apply_rydberg_force",
+        ],
+    );
+}
+
+fn register_components(world: &mut World) {
+    world.register::<Rydberg>();
+    world.register::<RydbergExcited>();
+    world.register::<RydbergLight>();
+    world.register::<Neighbours>();
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::laser::intensity::LaserIntensitySampler;
+    use crate::laser::DEFAULT_BEAM_LIMIT;
+    use assert_approx_eq::assert_approx_eq;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn test_rydberg_light() {
+        let light = RydbergLight::from_transition(ExcitationStep::Lower, 6.065e6, 16.69);
+        // At the saturation intensity, the Rabi frequency is Γ/sqrt(2).
+        assert_approx_eq!(
+            light.rabi_frequency(16.69),
+            2.0 * PI * 6.065e6 / 2.0_f64.sqrt(),
+            1.0
+        );
+
+        let excitation = RydbergExcitation {
+            intermediate_detuning: 2.0 * PI * 500.0e6,
+            detuning: 0.0,
+            dephasing: 2.0 * PI * 1.0e6,
+        };
+        let rabi = excitation.two_photon_rabi_frequency(2.0 * PI * 10.0e6, 2.0 * PI * 10.0e6);
+        assert_approx_eq!(rabi, 2.0 * PI * 0.1e6, 1e-6);
+        assert_approx_eq!(
+            excitation.rate(rabi, 0.0),
+            rabi.powi(2) / excitation.dephasing,
+            1e-6
+        );
+
+        // At the blockade radius the interaction shifts the Rydberg state by the linewidth.
+        let rydberg = Rydberg {
+            c6: 5.7e-58,
+            lifetime: 150.0e-6,
+        };
+        let radius = rydberg.blockade_radius(excitation.dephasing);
+        assert_approx_eq!(
+            rydberg.interaction(radius) / HBAR,
+            excitation.dephasing,
+            1e-3
+        );
+    }
+
+    fn create_world() -> World {
+        let mut test_world = World::new();
+        test_world.register::<Rydberg>();
+        test_world.register::<RydbergExcited>();
+        test_world.register::<RydbergLight>();
+        test_world.register::<Position>();
+        test_world.register::<Neighbours>();
+        test_world.register::<LaserIntensitySamplers<{ DEFAULT_BEAM_LIMIT }>>();
+        test_world.register::<LaserIndex>();
+        test_world.register::<Force>();
+        test_world.insert(Timestep { delta: 1.0e-7 });
+        test_world.insert(neighbours::NeighbourListParameters::default());
+        test_world
+    }
+
+    #[test]
+    fn test_rydberg_blockade() {
+        let mut test_world = create_world();
+        test_world.insert(RydbergExcitation {
+            intermediate_detuning: 2.0 * PI * 500.0e6,
+            detuning: 0.0,
+            dephasing: 2.0 * PI * 1.0e6,
+        });
+        for (i, step) in [ExcitationStep::Lower, ExcitationStep::Upper]
+            .iter()
+            .enumerate()
+        {
+            test_world
+                .create_entity()
+                .with(RydbergLight {
+                    step: *step,
+                    rabi_coefficient: 1.0e6,
+                })
+                .with(LaserIndex {
+                    index: i,
+                    initiated: true,
+                })
+                .build();
+        }
+
+        // A ground state atom 1um from an excited atom is blockaded.
+        let rydberg = Rydberg {
+            c6: 5.7e-58,
+            lifetime: 1.0,
+        };
+        let intensities = LaserIntensitySamplers {
+            contents: [LaserIntensitySampler { intensity: 1.0e6 }; DEFAULT_BEAM_LIMIT],
+        };
+        let excited_atom = test_world
+            .create_entity()
+            .with(rydberg)
+            .with(RydbergExcited)
+            .with(Position::new())
+            .with(Neighbours::default())
+            .with(intensities)
+            .with(Force::new())
+            .build();
+        let blockaded_atom = test_world
+            .create_entity()
+            .with(rydberg)
+            .with(Position {
+                pos: Vector3::new(1.0e-6, 0.0, 0.0),
+            })
+            .with(Neighbours::default())
+            .with(intensities)
+            .with(Force::new())
+            .build();
+        let distant_atom = test_world
+            .create_entity()
+            .with(rydberg)
+            .with(Position {
+                pos: Vector3::new(1.0e-4, 0.0, 0.0),
+            })
+            .with(Neighbours::default())
+            .with(intensities)
+            .with(Force::new())
+            .build();
+
+        // The unblockaded atoms are excited or de-excited with near certainty in a single step, while the
+        // interaction shift of the blockaded atom is much larger than the linewidth.
+        neighbours::BuildNeighbourListsSystem.run_now(&test_world);
+        RydbergExcitationSystem::<{ DEFAULT_BEAM_LIMIT }>.run_now(&test_world);
+        test_world.maintain();
+        {
+            let excited = test_world.read_storage::<RydbergExcited>();
+            assert!(excited.get(excited_atom).is_none());
+            assert!(excited.get(blockaded_atom).is_none());
+            assert!(excited.get(distant_atom).is_some());
+        }
+
+        // Two excited atoms repel each other, while the distant atom is not a neighbour.
+        for atom in [excited_atom, blockaded_atom].iter() {
+            test_world
+                .write_storage::<RydbergExcited>()
+                .insert(*atom, RydbergExcited)
+                .unwrap();
+        }
+        RydbergInteractionForceSystem.run_now(&test_world);
+        let forces = test_world.read_storage::<Force>();
+        let force = forces.get(blockaded_atom).unwrap().force;
+        assert_approx_eq!(force[0], 6.0 * 5.7e-58 / 1.0e-6_f64.powi(7), 1e-20);
+        assert_approx_eq!(forces.get(excited_atom).unwrap().force[0], -force[0], 1e-20);
+        assert_eq!(forces.get(distant_atom).unwrap().force[0], 0.0);
+    }
+}
@@ -0,0 +1,140 @@
+//! Neighbour lists of atoms, built from their positions.
+//!
+//! Space is divided into cubic cells with a width equal to the cutoff distance, so the neighbours of an
+//! atom can only be found in the 27 cells surrounding it. The lists are rebuilt every step.
+
+use crate::atom::Position;
+use hashbrown::HashMap;
+use nalgebra::Vector3;
+use specs::prelude::*;
+
+/// A resource that sets the cutoff distance of the neighbour lists.
+#[derive(Clone, Copy)]
+pub struct NeighbourListParameters {
+    /// Atoms closer than the cutoff distance are neighbours, in m.
+    pub cutoff: f64,
+}
+
+impl Default for NeighbourListParameters {
+    fn default() -> Self {
+        NeighbourListParameters { cutoff: 20.0e-6 }
+    }
+}
+
+/// The atoms within the cutoff distance of an atom.
+///
+/// Only atoms with a `Neighbours` component are included in the neighbour lists.
+#[derive(Clone, Default)]
+pub struct Neighbours {
+    pub entities: Vec<Entity>,
+}
+
+impl Component for Neighbours {
+    type Storage = VecStorage<Self>;
+}
+
+/// Index of a cell of the grid.
+type Cell = (i64, i64, i64);
+
+/// The cell of the grid which contains the position.
+fn cell_of(pos: &Vector3<f64>, width: f64) -> Cell {
+    (
+        (pos[0] / width).floor() as i64,
+        (pos[1] / width).floor() as i64,
+        (pos[2] / width).floor() as i64,
+    )
+}
+
+/// Builds the `Neighbours` of each atom from the `Position` of the atoms.
+pub struct BuildNeighbourListsSystem;
+
+impl<'a> System<'a> for BuildNeighbourListsSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, Neighbours>,
+        Read<'a, NeighbourListParameters>,
+    );
+
+    fn run(&mut self, (entities, positions, mut neighbours, parameters): Self::SystemData) {
+        use rayon::prelude::*;
+
+        let cutoff = parameters.cutoff;
+        let mut cells: HashMap<Cell, Vec<(Entity, Vector3<f64>)>> = HashMap::new();
+        for (entity, position, _) in (&entities, &positions, &neighbours).join() {
+            cells
+                .entry(cell_of(&position.pos, cutoff))
+                .or_default()
+                .push((entity, position.pos));
+        }
+
+        (&entities, &positions, &mut neighbours)
+            .par_join()
+            .for_each(|(entity, position, neighbours)| {
+                neighbours.entities.clear();
+                let (x, y, z) = cell_of(&position.pos, cutoff);
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        for dz in -1..=1 {
+                            if let Some(cell) = cells.get(&(x + dx, y + dy, z + dz)) {
+                                neighbours.entities.extend(
+                                    cell.iter()
+                                        .filter(|(other, pos)| {
+                                            *other != entity && (pos - position.pos).norm() < cutoff
+                                        })
+                                        .map(|(other, _)| *other),
+                                );
+                            }
+                        }
+                    }
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_neighbour_lists() {
+        let mut test_world = World::new();
+        test_world.register::<Position>();
+        test_world.register::<Neighbours>();
+        test_world.insert(NeighbourListParameters { cutoff: 1.0e-5 });
+
+        let positions = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.9e-5, 0.0, 0.0),
+            Vector3::new(-0.5e-5, 0.5e-5, 0.0),
+            Vector3::new(3.0e-5, 0.0, 0.0),
+        ];
+        let atoms: Vec<Entity> = positions
+            .iter()
+            .map(|pos| {
+                test_world
+                    .create_entity()
+                    .with(Position { pos: *pos })
+                    .with(Neighbours::default())
+                    .build()
+            })
+            .collect();
+        // Atoms without a `Neighbours` component are not included.
+        test_world
+            .create_entity()
+            .with(Position {
+                pos: Vector3::new(1.0e-6, 0.0, 0.0),
+            })
+            .build();
+
+        let mut system = BuildNeighbourListsSystem;
+        system.run_now(&test_world);
+
+        let neighbours = test_world.read_storage::<Neighbours>();
+        let mut first = neighbours.get(atoms[0]).unwrap().entities.clone();
+        first.sort();
+        assert_eq!(first, vec![atoms[1], atoms[2]]);
+        assert_eq!(neighbours.get(atoms[1]).unwrap().entities, vec![atoms[0]]);
+        assert!(neighbours.get(atoms[3]).unwrap().entities.is_empty());
+    }
+}
@@ -53,6 +53,11 @@ impl SimulationBuilder {
         self.plugins.push(Box::new(plugin));
     }
 
+    /// Returns true if a plugin of type `P` has already been added to the [SimulationBuilder].
+    pub fn has_plugin<P: Plugin>(&self) -> bool {
+        self.plugins.iter().any(|p| p.name() == type_name::<P>())
+    }
+
     fn check_plugin_dependencies(&self, plugin: &impl Plugin) {
         for dep in plugin.deps() {
             if !self.plugins.iter().map(|p| p.name()).any(|n| n == dep.name()) {
@@ -1,10 +1,13 @@
 //! Predefined species that can be used in AtomECS.
 //! 
 use crate::constant::BOHRMAG;
-use crate::{transition, species};
+use crate::dipole::polarizability::{AtomicLevel, DipoleTransition};
+use crate::{dynamic_transition, transition, species};
+
+pub mod registry;
 
 transition!(Strontium88_461, 650_759_219_088_937.0, 32e6, 430.0, BOHRMAG, -BOHRMAG, 0.0);
-species!(Strontium88, Strontium88_461, 88);
+species!(Strontium88, Strontium88_461, 88, isotopes: ["461" => Strontium88_461, "689" => Strontium88_689]);
 
 transition!(Rubidium87_780D2, 384_228_115_202_521.0, 6.065e6, 16.69, BOHRMAG, -BOHRMAG, 0.0); //[Steck, 87 D2]
 species!(Rubidium87, Rubidium87_780D2, 87);
@@ -17,4 +20,60 @@ transition!(
     BOHRMAG,
     -BOHRMAG,
     0.0
-); 
+);
+
+// The A(v'=0) <- X(v=0) transition of CaF, with effective magnetic moments of a type-I cycling transition.
+transition!(
+    CalciumFluoride59_606,
+    494_460_000_000_000.0, // [Anderegg et. al. (2017)](doi:10.1103/PhysRevLett.119.103201)
+    8.3e6,
+    49.0,
+    0.5 * BOHRMAG,
+    -0.5 * BOHRMAG,
+    0.0
+);
+species!(CalciumFluoride59, CalciumFluoride59_606, 59);
+
+dynamic_transition!(DynamicTransition);
+
+/// The 5S<sub>1/2</sub> ground level of Rubidium 87, coupled by the D1 and D2 lines. [Steck, 87 D line data]
+pub fn rubidium87_ground_level() -> AtomicLevel {
+    AtomicLevel {
+        j: 0.5,
+        transitions: vec![
+            DipoleTransition {
+                wavelength: 794.979e-9,
+                linewidth: 5.746e6,
+                j: 0.5,
+                upper: true,
+            },
+            DipoleTransition {
+                wavelength: 780.241e-9,
+                linewidth: 6.065e6,
+                j: 1.5,
+                upper: true,
+            },
+        ],
+    }
+}
+
+/// The 5s<sup>2</sup> <sup>1</sup>S<sub>0</sub> ground level of Strontium 88, coupled by the 461nm and 689nm lines.
+pub fn strontium88_ground_level() -> AtomicLevel {
+    AtomicLevel {
+        j: 0.0,
+        transitions: vec![
+            DipoleTransition {
+                wavelength: 460.862e-9,
+                linewidth: 32e6,
+                j: 1.0,
+                upper: true,
+            },
+            DipoleTransition {
+                wavelength: 689.449e-9,
+                linewidth: 7.4e3,
+                j: 1.0,
+                upper: true,
+            },
+        ],
+    }
+}
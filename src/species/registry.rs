@@ -0,0 +1,443 @@
+//! A registry of atomic species, loaded at runtime from a data table.
+//!
+//! Each entry of the table describes the mass of a species, its laser cooling transitions, the
+//! hyperfine constants of its levels and the dipole transitions of its ground level. Entries may also
+//! list the naturally abundant isotopes of the element, with the isotope shift of each transition, and
+//! molecules list the vibrational branching ratios of their cooling transition. Static polarizabilities
+//! and dipole moments give the Stark shifts and forces of species in electric fields. The table is written in
+//! YAML or JSON, with the species names as keys; see `data/species.yaml` for the format. The built-in
+//! table includes entries for Li6, Li7, Na23, K39, K40, K41, Rb85, Rb87, Cs133, Sr88, Yb174, Dy164,
+//! Er166 and Ca40, and for the molecules CaF, SrF and YO.
+//!
+//! Transitions from the registry are simulated using a transition created by [crate::dynamic_transition],
+//! such as [super::DynamicTransition]. The `TransitionParameters` of the transition are set from the
+//! registry before the simulation is built, so the species can be chosen at runtime. The parameters of a
+//! transition can only be set once per process, see [crate::dynamic_transition].
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::atom::Mass;
+use crate::atom_sources::mass::{IsotopeDistribution, MassDistribution, MassRatio};
+use crate::dipole::polarizability::AtomicLevel;
+use crate::dipole::Polarizability;
+use crate::electric::force::{ElectricDipole, StaticPolarizability};
+use crate::laser_cooling::isotope::Isotope;
+use crate::laser_cooling::molecule::VibrationalBranching;
+use crate::laser_cooling::stark::ExcitedStateStark;
+use crate::laser_cooling::transition::{TransitionComponent, TransitionParameters};
+use serde::{Deserialize, Serialize};
+
+/// The built-in table of species data.
+const BUILTIN_TABLE: &str = include_str!("../../data/species.yaml");
+
+/// The magnetic dipole and electric quadrupole hyperfine constants of a fine-structure level.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct HyperfineConstants {
+    /// Name of the level, eg `5P3/2`.
+    pub level: String,
+    /// Total electronic angular momentum `J` of the level.
+    pub j: f64,
+    /// Magnetic dipole constant `A`, in Hz.
+    pub a: f64,
+    /// Electric quadrupole constant `B`, in Hz.
+    #[serde(default)]
+    pub b: f64,
+}
+
+impl HyperfineConstants {
+    /// Energy shift of the hyperfine level `F` from the fine-structure level, in Hz.
+    ///
+    /// # Arguments
+    ///
+    /// `f`: total angular momentum of the hyperfine level.
+    ///
+    /// `nuclear_spin`: the nuclear spin `I` of the atom.
+    pub fn shift(&self, f: f64, nuclear_spin: f64) -> f64 {
+        let i = nuclear_spin;
+        let j = self.j;
+        let k = f * (f + 1.0) - i * (i + 1.0) - j * (j + 1.0);
+        let mut shift = 0.5 * self.a * k;
+        if i > 0.5 && j > 0.5 {
+            shift += self.b * (1.5 * k * (k + 1.0) - 2.0 * i * (i + 1.0) * j * (j + 1.0))
+                / (4.0 * i * (2.0 * i - 1.0) * j * (2.0 * j - 1.0));
+        }
+        shift
+    }
+}
+
+/// An isotope of the element of a species.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct IsotopeData {
+    /// Mass of the isotope, in atomic mass units.
+    pub mass: f64,
+    /// Natural abundance of the isotope.
+    pub abundance: f64,
+    /// Shifts of the transitions of the species for this isotope, by transition name.
+    #[serde(default)]
+    pub transitions: BTreeMap<String, Isotope>,
+}
+
+/// Atomic data for a single species.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SpeciesData {
+    /// Mass of the atom, in atomic mass units.
+    pub mass: f64,
+    /// Nuclear spin `I`.
+    #[serde(default)]
+    pub nuclear_spin: f64,
+    /// Name of the transition normally used for laser cooling.
+    pub cooling_transition: String,
+    /// Laser cooling transitions of the species, by name.
+    pub transitions: BTreeMap<String, TransitionParameters>,
+    /// Hyperfine constants of the levels of the species.
+    #[serde(default)]
+    pub hyperfine: Vec<HyperfineConstants>,
+    /// The ground level and its dipole transitions, used to calculate the polarizability.
+    pub ground_level: Option<AtomicLevel>,
+    /// Isotopes of the element, including this species, for atom sources of natural abundance.
+    #[serde(default)]
+    pub isotopes: Vec<IsotopeData>,
+    /// Vibrational branching ratios of the excited state of the cooling transition, for molecules.
+    #[serde(default)]
+    pub vibrational_branching: Vec<f64>,
+    /// Static polarizability of the ground level, in atomic units.
+    #[serde(default)]
+    pub static_polarizability: Option<f64>,
+    /// Static polarizabilities of the upper levels of the transitions, by transition name, in atomic units.
+    #[serde(default)]
+    pub excited_static_polarizability: BTreeMap<String, f64>,
+    /// Permanent electric dipole moment of the ground state of a polar molecule, in Debye.
+    #[serde(default)]
+    pub dipole_moment: Option<f64>,
+}
+
+impl SpeciesData {
+    /// Gets the named transition of the species.
+    pub fn transition(&self, name: &str) -> Option<&TransitionParameters> {
+        self.transitions.get(name)
+    }
+
+    /// Gets the transition normally used for laser cooling.
+    pub fn cooling_transition(&self) -> Option<&TransitionParameters> {
+        self.transition(&self.cooling_transition)
+    }
+
+    /// Gets the hyperfine constants of the named level.
+    pub fn hyperfine(&self, level: &str) -> Option<&HyperfineConstants> {
+        self.hyperfine
+            .iter()
+            .find(|constants| constants.level == level)
+    }
+
+    /// Creates a `Mass` component for atoms of this species.
+    pub fn mass(&self) -> Mass {
+        Mass { value: self.mass }
+    }
+
+    /// Creates the `VibrationalBranching` of the cooling transition for molecules of this species.
+    ///
+    /// Returns `None` for atoms, which have no vibrational branching ratios in the table.
+    pub fn vibrational_branching(&self) -> Option<VibrationalBranching> {
+        if self.vibrational_branching.is_empty() {
+            None
+        } else {
+            Some(VibrationalBranching::new(
+                self.vibrational_branching.clone(),
+            ))
+        }
+    }
+
+    /// Creates a `MassDistribution` of the isotopes of the element in their natural abundance.
+    ///
+    /// If the table lists no isotopes, the distribution contains only this species.
+    pub fn mass_distribution(&self) -> MassDistribution {
+        if self.isotopes.is_empty() {
+            return MassDistribution::new(vec![MassRatio {
+                mass: self.mass,
+                ratio: 1.0,
+            }]);
+        }
+        MassDistribution::new(
+            self.isotopes
+                .iter()
+                .map(|isotope| MassRatio {
+                    mass: isotope.mass,
+                    ratio: isotope.abundance,
+                })
+                .collect(),
+        )
+    }
+
+    /// Creates the `IsotopeDistribution` for the `MassDistribution` of this species.
+    ///
+    /// Each isotope which shifts any transitions is given the `IsotopeShifts` of all of them, so that atom
+    /// sources attach an `IsotopeShift` for each transition of the species to atoms of that isotope.
+    pub fn isotope_distribution(&self) -> IsotopeDistribution {
+        let isotopes = if self.isotopes.is_empty() {
+            vec![None]
+        } else {
+            self.isotopes
+                .iter()
+                .map(|isotope| {
+                    if isotope.transitions.is_empty() {
+                        None
+                    } else {
+                        Some(isotope.transitions.clone())
+                    }
+                })
+                .collect()
+        };
+        IsotopeDistribution { isotopes }
+    }
+
+    /// Creates a `StaticPolarizability` component for atoms of this species.
+    pub fn static_polarizability(&self) -> Option<StaticPolarizability> {
+        self.static_polarizability
+            .map(StaticPolarizability::atomic_units)
+    }
+
+    /// Creates an `ElectricDipole` component for molecules of this species.
+    ///
+    /// The molecules are assumed to be fully oriented by the field, in the high-field seeking state.
+    pub fn electric_dipole(&self) -> Option<ElectricDipole> {
+        self.dipole_moment.map(ElectricDipole::debye)
+    }
+
+    /// Creates an `ExcitedStateStark` component for the named transition, from the static polarizability
+    /// of its upper level.
+    pub fn excited_state_stark<T>(&self, transition: &str) -> Option<ExcitedStateStark<T>>
+    where
+        T: TransitionComponent,
+    {
+        self.excited_static_polarizability
+            .get(transition)
+            .map(|polarizability| {
+                ExcitedStateStark::new(
+                    StaticPolarizability::atomic_units(*polarizability).polarizability,
+                    0.0,
+                )
+            })
+    }
+
+    /// Calculates the scalar `Polarizability` of the ground level in light of the given wavelength, in m.
+    ///
+    /// Returns `None` if the table does not describe the ground level.
+    pub fn polarizability(&self, wavelength: f64) -> Option<Polarizability> {
+        self.ground_level.as_ref().map(|level| Polarizability {
+            prefactor: level.polarizability_components(wavelength).0,
+        })
+    }
+}
+
+/// A collection of `SpeciesData`, indexed by the name of each species.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(transparent)]
+pub struct SpeciesRegistry {
+    species: BTreeMap<String, SpeciesData>,
+}
+
+fn invalid_data<E: std::fmt::Display>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+impl SpeciesRegistry {
+    /// Creates a registry containing the built-in table of species.
+    pub fn builtin() -> Self {
+        Self::from_yaml_str(BUILTIN_TABLE).expect("The built-in species table is invalid.")
+    }
+
+    /// Parses a table of species written in YAML.
+    pub fn from_yaml_str(table: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(table)
+    }
+
+    /// Parses a table of species written in JSON.
+    pub fn from_json_str(table: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(table)
+    }
+
+    /// Loads a table of species from a file.
+    ///
+    /// Files with a `.json` extension are read as JSON, and all other files as YAML.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let table = fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_json_str(&table).map_err(invalid_data),
+            _ => Self::from_yaml_str(&table).map_err(invalid_data),
+        }
+    }
+
+    /// Adds the species of another registry, replacing any entries with the same name.
+    pub fn extend(&mut self, other: SpeciesRegistry) {
+        self.species.extend(other.species);
+    }
+
+    /// Adds a species to the registry, replacing any entry with the same name.
+    pub fn insert(&mut self, name: &str, species: SpeciesData) {
+        self.species.insert(name.to_string(), species);
+    }
+
+    /// Gets the data for the named species.
+    pub fn get(&self, name: &str) -> Option<&SpeciesData> {
+        self.species.get(name)
+    }
+
+    /// Names of all species in the registry.
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.species.keys()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::laser_cooling::transition::AtomicTransition;
+    use crate::species::{Rubidium87_780D2, Strontium88_461};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_builtin_registry() {
+        let registry = SpeciesRegistry::builtin();
+        for name in [
+            "Li6", "Li7", "Na23", "K39", "K40", "K41", "Rb85", "Rb87", "Cs133", "Sr88", "Yb174",
+            "Dy164", "Er166", "Ca40",
+        ] {
+            let species = registry.get(name).expect("species not found");
+            assert!(species.cooling_transition().is_some(), "{}", name);
+            assert!(species.ground_level.is_some(), "{}", name);
+        }
+
+        // The registry agrees with the compile-time transitions.
+        let rb87 = registry.get("Rb87").unwrap().cooling_transition().unwrap();
+        assert_approx_eq!(rb87.frequency, Rubidium87_780D2::frequency(), 1.0);
+        assert_approx_eq!(rb87.mup(), Rubidium87_780D2::mup(), 1e-26);
+        assert_approx_eq!(rb87.mum(), Rubidium87_780D2::mum(), 1e-26);
+        let sr88 = registry.get("Sr88").unwrap().transition("461").unwrap();
+        assert_approx_eq!(sr88.linewidth, Strontium88_461::linewidth(), 1.0);
+        assert_approx_eq!(sr88.mup(), Strontium88_461::mup(), 1e-27);
+        assert_approx_eq!(sr88.muz(), Strontium88_461::muz(), 1e-27);
+    }
+
+    #[test]
+    fn test_hyperfine_splitting() {
+        // Ground state hyperfine splitting of Rb87 is 6.834682611 GHz.
+        let rb87 = SpeciesRegistry::builtin();
+        let rb87 = rb87.get("Rb87").unwrap();
+        let ground = rb87.hyperfine("5S1/2").unwrap();
+        assert_approx_eq!(
+            ground.shift(2.0, rb87.nuclear_spin) - ground.shift(1.0, rb87.nuclear_spin),
+            6.834682611e9,
+            1e3
+        );
+        // Splitting between F'=3 and F'=2 of the Rb87 D2 excited state is 266.650 MHz.
+        let excited = rb87.hyperfine("5P3/2").unwrap();
+        assert_approx_eq!(
+            excited.shift(3.0, rb87.nuclear_spin) - excited.shift(2.0, rb87.nuclear_spin),
+            266.650e6,
+            0.01e6
+        );
+    }
+
+    #[test]
+    fn test_isotope_mass_distribution() {
+        let registry = SpeciesRegistry::builtin();
+        let sr = registry.get("Sr88").unwrap();
+        let distribution = sr.mass_distribution();
+        let isotopes = sr.isotope_distribution();
+        assert_eq!(distribution.distribution.len(), 4);
+        assert_eq!(isotopes.isotopes.len(), 4);
+        let total: f64 = distribution.distribution.iter().map(|mr| mr.ratio).sum();
+        assert_approx_eq!(total, 1.0, 1e-12);
+        let sr88 = distribution
+            .distribution
+            .iter()
+            .position(|mr| mr.mass > 87.5)
+            .unwrap();
+        assert!(isotopes.isotopes[sr88].is_none());
+        assert_approx_eq!(distribution.distribution[sr88].ratio, 0.8258, 1e-3);
+        let sr86 = distribution
+            .distribution
+            .iter()
+            .position(|mr| (mr.mass - 86.0).abs() < 0.5)
+            .unwrap();
+        let sr86_shifts = isotopes.isotopes[sr86].as_ref().unwrap();
+        assert!(sr86_shifts["461"].shift < 0.0);
+        assert!(sr86_shifts["689"].shift < 0.0);
+
+        let rb = registry.get("Rb87").unwrap();
+        assert_eq!(rb.mass_distribution().distribution.len(), 1);
+        assert_eq!(rb.isotope_distribution().isotopes, vec![None]);
+    }
+
+    #[test]
+    fn test_molecule_branching() {
+        let registry = SpeciesRegistry::builtin();
+        let branching = registry
+            .get("CaF")
+            .unwrap()
+            .vibrational_branching()
+            .unwrap();
+        assert!(branching.photon_budget(2) > 1.0e4);
+        assert!(registry
+            .get("Rb87")
+            .unwrap()
+            .vibrational_branching()
+            .is_none());
+    }
+
+    #[test]
+    fn test_stark_parameters() {
+        let registry = SpeciesRegistry::builtin();
+        let rb87 = registry.get("Rb87").unwrap();
+        let ground = rb87.static_polarizability().unwrap();
+        let excited = rb87.excited_state_stark::<Rubidium87_780D2>("D2").unwrap();
+        assert!(excited.polarizability > ground.polarizability);
+        assert!(rb87.electric_dipole().is_none());
+
+        let caf = registry.get("CaF").unwrap();
+        assert_approx_eq!(caf.electric_dipole().unwrap().moment, 1.024e-29, 1e-32);
+        assert!(caf.excited_state_stark::<Rubidium87_780D2>("A-X").is_none());
+    }
+
+    #[test]
+    fn test_load_json_table() {
+        let table = r#"{
+            "X": {
+                "mass": 10.0,
+                "cooling_transition": "A",
+                "transitions": {
+                    "A": {
+                        "frequency": 1e14,
+                        "linewidth": 1e6,
+                        "saturation_intensity": 10.0,
+                        "lower": { "f": 0.0, "g": 0.0 },
+                        "upper": { "f": 1.0, "g": 1.0 }
+                    }
+                }
+            }
+        }"#;
+        let mut registry = SpeciesRegistry::builtin();
+        registry.extend(SpeciesRegistry::from_json_str(table).unwrap());
+        let species = registry.get("X").unwrap();
+        assert_eq!(species.mass().value, 10.0);
+        assert!(species.polarizability(1064e-9).is_none());
+        assert!(registry.get("Rb87").is_some());
+    }
+
+    #[test]
+    fn test_polarizability_matches_two_level_estimate() {
+        let registry = SpeciesRegistry::builtin();
+        let yb = registry.get("Yb174").unwrap();
+        let estimate = Polarizability::calculate_for(1064e-9, 398.911e-9, 29.1e6);
+        let polarizability = yb.polarizability(1064e-9).unwrap();
+        assert_approx_eq!(
+            polarizability.prefactor,
+            estimate.prefactor,
+            0.05 * estimate.prefactor.abs()
+        );
+    }
+}